@@ -0,0 +1,204 @@
+use crate::camera::Camera;
+
+use crate::image::ImageFormat;
+
+use crate::rtvec3::{Point3, RtVec3};
+
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Camera placement at a point in time (seconds, or any unit as long as it's used consistently).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraKeyframe {
+    pub time: f64,
+    pub lookfrom: Point3,
+    pub lookat: Point3,
+    pub fov: f64,
+    pub focus_dist: f64,
+}
+
+impl CameraKeyframe {
+    pub fn new(time: f64, lookfrom: Point3, lookat: Point3, fov: f64, focus_dist: f64) -> Self {
+        CameraKeyframe {
+            time,
+            lookfrom,
+            lookat,
+            fov,
+            focus_dist,
+        }
+    }
+
+    fn to_values(self) -> [f64; 8] {
+        [
+            self.lookfrom.x, self.lookfrom.y, self.lookfrom.z,
+            self.lookat.x, self.lookat.y, self.lookat.z,
+            self.fov, self.focus_dist,
+        ]
+    }
+
+    fn from_values(time: f64, values: [f64; 8]) -> Self {
+        CameraKeyframe::new(
+            time,
+            RtVec3::new(values[0], values[1], values[2]),
+            RtVec3::new(values[3], values[4], values[5]),
+            values[6],
+            values[7],
+        )
+    }
+}
+
+// Keyframed camera motion, interpolated with Catmull-Rom splines so the camera passes through
+// every keyframe with smooth velocity. Tangents account for uneven keyframe spacing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CameraPath {
+    keyframes: Vec<CameraKeyframe>,
+    // Some(duration) for paths that loop back to their first keyframe `duration` after it, like turntables
+    loop_duration: Option<f64>,
+}
+
+impl CameraPath {
+    // Keyframes may be given in any order, but no two at the same time: the spline's tangents
+    // divide by the time between neighbouring keyframes.
+    pub fn new(mut keyframes: Vec<CameraKeyframe>) -> Result<Self, String> {
+        if keyframes.is_empty() {
+            return Err("a camera path needs at least one keyframe".to_string());
+        }
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        if let Some(pair) = keyframes.windows(2).find(|pair| pair[1].time.partial_cmp(&pair[0].time) != Some(Ordering::Greater)) {
+            return Err(format!("keyframe times must all be different numbers, got {} and {}", pair[0].time, pair[1].time));
+        }
+        Ok(CameraPath {
+            keyframes,
+            loop_duration: None,
+        })
+    }
+
+    // A path that returns to its first keyframe `duration` after it and then repeats.
+    pub fn looping(keyframes: Vec<CameraKeyframe>, duration: f64) -> Result<Self, String> {
+        let mut path = CameraPath::new(keyframes)?;
+        let span = path.keyframes[path.keyframes.len() - 1].time - path.keyframes[0].time;
+        if duration.is_nan() || duration <= span {
+            return Err(format!("loop duration {} must be longer than the {} spanned by the keyframes", duration, span));
+        }
+        path.loop_duration = Some(duration);
+        Ok(path)
+    }
+
+    // Keyframe `index` with out of range indices wrapped around for loops, or linearly
+    // extrapolated past the ends of open paths.
+    fn key(&self, index: i64) -> (f64, [f64; 8]) {
+        let count = self.keyframes.len() as i64;
+        match self.loop_duration {
+            Some(duration) => {
+                let wrapped = self.keyframes[index.rem_euclid(count) as usize];
+                let laps = index.div_euclid(count) as f64;
+                (wrapped.time + laps * duration, wrapped.to_values())
+            }
+            None if index < 0 || index >= count => {
+                let (edge, inner) = if index < 0 { (0, 1.min(count - 1)) } else { (count - 1, (count - 2).max(0)) };
+                let edge = self.keyframes[edge as usize];
+                let inner = self.keyframes[inner as usize];
+                let mut values = edge.to_values();
+                for (value, inner_value) in values.iter_mut().zip(inner.to_values()) {
+                    *value = 2.0 * *value - inner_value;
+                }
+                (2.0 * edge.time - inner.time, values)
+            }
+            None => {
+                let key = self.keyframes[index as usize];
+                (key.time, key.to_values())
+            }
+        }
+    }
+
+    pub fn sample(&self, time: f64) -> CameraKeyframe {
+        let count = self.keyframes.len() as i64;
+        let first_time = self.keyframes[0].time;
+        let local_time = match self.loop_duration {
+            Some(duration) => first_time + (time - first_time).rem_euclid(duration),
+            None => time.clamp(first_time, self.keyframes[count as usize - 1].time),
+        };
+        if count == 1 {
+            return CameraKeyframe::from_values(time, self.keyframes[0].to_values());
+        }
+
+        // Segment [i, i + 1] containing the time, the last one wraps to the first key in a loop
+        let mut segment = 0;
+        while segment + 1 < count && self.key(segment + 1).0 <= local_time {
+            segment += 1;
+        }
+        if segment == count - 1 && self.loop_duration.is_none() {
+            segment = count - 2;
+        }
+
+        let (t0, p0) = self.key(segment - 1);
+        let (t1, p1) = self.key(segment);
+        let (t2, p2) = self.key(segment + 1);
+        let (t3, p3) = self.key(segment + 2);
+
+        // Cubic Hermite with Catmull-Rom tangents
+        let span = t2 - t1;
+        let s = if span > 0.0 { (local_time - t1) / span } else { 0.0 };
+        let (s2, s3) = (s * s, s * s * s);
+        let h00 = 2.0 * s3 - 3.0 * s2 + 1.0;
+        let h10 = s3 - 2.0 * s2 + s;
+        let h01 = -2.0 * s3 + 3.0 * s2;
+        let h11 = s3 - s2;
+
+        let mut values = [0.0; 8];
+        for index in 0..8 {
+            let m1 = (p2[index] - p0[index]) / (t2 - t0) * span;
+            let m2 = (p3[index] - p1[index]) / (t3 - t1) * span;
+            values[index] = h00 * p1[index] + h10 * m1 + h01 * p2[index] + h11 * m2;
+        }
+        CameraKeyframe::from_values(time, values)
+    }
+}
+
+// Renders a camera path to a numbered image sequence: frame_0001.ppm, frame_0002.ppm, ... (with
+// the extension of the camera's image format).
+pub struct Animation {
+    pub path: CameraPath,
+    pub start_time: f64,
+    pub end_time: f64,
+    pub frame_count: u32,
+    pub output_dir: PathBuf,
+}
+
+impl Animation {
+    pub fn new(path: CameraPath, start_time: f64, end_time: f64, frame_count: u32, output_dir: impl Into<PathBuf>) -> Self {
+        Animation {
+            path,
+            start_time,
+            end_time,
+            frame_count,
+            output_dir: output_dir.into(),
+        }
+    }
+
+    // Time of frame `index` (0 based), frames evenly spread from start_time to end_time inclusive.
+    pub fn frame_time(&self, index: u32) -> f64 {
+        if self.frame_count <= 1 {
+            return self.start_time;
+        }
+        self.start_time + (self.end_time - self.start_time) * index as f64 / (self.frame_count - 1) as f64
+    }
+
+    pub fn frame_path(output_dir: &Path, index: u32, format: ImageFormat) -> PathBuf {
+        output_dir.join(format!("frame_{:04}.{}", index + 1, format.extension()))
+    }
+
+    // Renders every frame with `camera`, only its view and output file change between frames.
+    pub fn render(&self, camera: &mut Camera) -> std::io::Result<()> {
+        fs::create_dir_all(&self.output_dir)?;
+        for index in 0..self.frame_count {
+            let key = self.path.sample(self.frame_time(index));
+            println!("Rendering frame {} of {}", index + 1, self.frame_count);
+            camera.set_view(key.lookfrom, key.lookat, key.fov, key.focus_dist);
+            camera.set_output(Animation::frame_path(&self.output_dir, index, camera.format()));
+            camera.build_file()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+use crate::film::Film;
+
+use crate::hit::HitRecord;
+
+use crate::ray::Ray;
+
+use crate::rtvec3::RtVec3;
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+// Arbitrary output variables: first-hit feature passes rendered next to the beauty image,
+// for compositing and denoising.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Aov {
+    Normal,     // Front facing surface normal, components in [-1, 1]
+    Depth,      // Linear distance along the camera view axis
+    Position,   // World space hit point
+    Albedo,     // Surface color reported by the hit material
+    MaterialId, // 1 based index of the material in scene order, 0 where nothing was hit
+    ObjectId,   // 1 based index of the object in the world list, 0 where nothing was hit
+}
+
+impl Aov {
+    pub const ALL: [Aov; 6] = [Aov::Normal, Aov::Depth, Aov::Position, Aov::Albedo, Aov::MaterialId, Aov::ObjectId];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aov::Normal => "normal",
+            Aov::Depth => "depth",
+            Aov::Position => "position",
+            Aov::Albedo => "albedo",
+            Aov::MaterialId => "material_id",
+            Aov::ObjectId => "object_id",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Aov> {
+        Aov::ALL.iter().copied().find(|aov| aov.name() == name)
+    }
+
+    // Ids can't be averaged across an edge, they keep the value of the first sample in each pixel.
+    pub fn is_id(&self) -> bool {
+        matches!(self, Aov::MaterialId | Aov::ObjectId)
+    }
+}
+
+// Everything the passes need to know about the first surface a camera ray hit.
+pub struct FirstHit {
+    pub normal: RtVec3,
+    pub depth: f64,
+    pub position: RtVec3,
+    pub albedo: RtVec3,
+    pub material_id: u32,
+    pub object_id: u32,
+}
+
+impl FirstHit {
+    pub fn new(ray: &Ray, record: &HitRecord, view_direction: RtVec3, material_id: u32) -> Self {
+        let albedo = record.material.albedo(record);
+        FirstHit {
+            normal: record.normal,
+            depth: (record.p - ray.origin()).dot(&view_direction),
+            position: record.p,
+            albedo: RtVec3::new(albedo.r, albedo.g, albedo.b),
+            material_id,
+            object_id: record.object_id as u32 + 1,
+        }
+    }
+
+    fn value(&self, aov: Aov) -> RtVec3 {
+        match aov {
+            Aov::Normal => self.normal,
+            Aov::Depth => RtVec3::new(self.depth, self.depth, self.depth),
+            Aov::Position => self.position,
+            Aov::Albedo => self.albedo,
+            Aov::MaterialId => RtVec3::new(self.material_id as f64, self.material_id as f64, self.material_id as f64),
+            Aov::ObjectId => RtVec3::new(self.object_id as f64, self.object_id as f64, self.object_id as f64),
+        }
+    }
+}
+
+// One accumulation buffer per requested pass.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AovBuffers {
+    pub passes: Vec<(Aov, Film)>,
+}
+
+impl AovBuffers {
+    pub fn new(aovs: &[Aov], width: u32, height: u32) -> Self {
+        AovBuffers {
+            passes: aovs.iter().map(|aov| (*aov, Film::new(width, height))).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.passes.is_empty()
+    }
+
+    pub fn get(&self, aov: Aov) -> Option<&Film> {
+        self.passes.iter().find(|(pass, _)| *pass == aov).map(|(_, film)| film)
+    }
+
+    // Adds the passes of `other`, rendered separately with the same set of passes.
+    pub fn merge(&mut self, other: &AovBuffers) {
+        for ((_, film), (_, other_film)) in self.passes.iter_mut().zip(&other.passes) {
+            film.merge(other_film);
+        }
+    }
+
+    // Every pass cut down to the same block of pixels, see `Film::crop`.
+    pub fn crop(&self, i: u32, j: u32, width: u32, height: u32) -> AovBuffers {
+        AovBuffers {
+            passes: self.passes.iter().map(|(aov, film)| (*aov, film.crop(i, j, width, height))).collect(),
+        }
+    }
+
+    // Adds one camera sample to every pass, `None` when the ray escaped the scene.
+    pub fn add_sample(&mut self, i: u32, j: u32, hit: Option<&FirstHit>) {
+        for (aov, film) in self.passes.iter_mut() {
+            if aov.is_id() && film.sample_count(i, j) > 0 {
+                continue;
+            }
+            let value = match hit {
+                Some(hit) => hit.value(*aov),
+                None => RtVec3::new(0.0, 0.0, 0.0),
+            };
+            film.add_sample(i, j, value);
+        }
+    }
+
+    // Writes the `requested` passes next to the beauty image, e.g. image.ppm => image_normal.pfm
+    pub fn write(&self, output_path: &Path, requested: &[Aov]) -> std::io::Result<()> {
+        for (aov, film) in &self.passes {
+            if requested.contains(aov) {
+                write_pfm(film, &aov_path(output_path, *aov))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn aov_path(output_path: &Path, aov: Aov) -> PathBuf {
+    let stem = output_path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("image");
+    output_path.with_file_name(format!("{}_{}.pfm", stem, aov.name()))
+}
+
+// Portable float map: unclamped 32 bit RGB, scanlines stored bottom to top.
+pub fn write_pfm(film: &Film, path: &Path) -> std::io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    file.write_all(format!("PF\n{} {}\n-1.0\n", film.width, film.height).as_bytes())?;
+    for pixel_h in (0..film.height).rev() {
+        for pixel_w in 0..film.width {
+            let value = film.resolve(pixel_w, pixel_h);
+            for channel in [value.x(), value.y(), value.z()] {
+                file.write_all(&(channel as f32).to_le_bytes())?;
+            }
+        }
+    }
+    file.flush()
+}
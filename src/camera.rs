@@ -1,184 +1,818 @@
-use crate::ray::{Ray, Color};
-use crate::ray::{write_color_to_pixel, color};
-
-use crate::rtvec3::{Point3, RtVec3};
-
-use crate::hit::HittableList;
-
-use crate::{degrees_to_radians, sample_square};
-
-use std::fs::File;
-use std::io::Write;
-
-pub struct Camera{
-    world: HittableList,
-    ray_color: Color,
-    aspect_ratio: f64,
-    fov: f64,
-    defocus_disk_u: RtVec3,
-    defocus_disk_v: RtVec3,
-    defocus_angle: f64,
-    focus_dist: f64,
-    lookfrom: Point3,
-    lookat: Point3,
-    vup: RtVec3,
-    image_width: u32,
-    image_height: u32,
-    sample_bounce_max: u32,
-    samples_per_pixel: u32,
-    pixel_samples_scale: f64,
-    viewport_width: f64,
-    viewport_height: f64,
-    camera_center: Point3,
-    viewport_u: RtVec3,
-    viewport_v: RtVec3,
-    pixel_delta_u: RtVec3,
-    pixel_delta_v: RtVec3,
-    viewport_upper_left: RtVec3,
-    pixel_00_loc: RtVec3,
-}
-
-impl Camera {
-    pub fn new(
-        world: HittableList,
-        ray_color: Color,
-        aspect_ratio: f64,
-        fov: f64,
-        defocus_angle: f64,
-        focus_dist: f64,
-        lookfrom: Point3,
-        lookat: Point3,
-        vup: RtVec3,
-        image_width: u32,
-        samples_per_pixel: u32,
-        sample_bounce_max: u32,
-    ) -> Self {
-        // Rendered image height
-        let mut image_height: u32 = (image_width as f64 / aspect_ratio) as u32; 
-        if image_height < 1 {
-            image_height = 1;
-        }
-
-        // Camera Viewport Data
-        let camera_center: Point3 = lookfrom;
-        let pixel_samples_scale = 1.0 / samples_per_pixel as f64;
-    
-        let look_sum: Point3 = lookfrom - lookat;
-        let theta: f64 = degrees_to_radians(fov);
-        let h: f64 = f64::tan(theta / 2.0);
-
-        let viewport_height: f64 = 2.0 * h * focus_dist;
-        let viewport_width: f64  = viewport_height * (image_width as f64 / image_height as f64);
-    
-        // Camera frame basis vectors
-        // Calculate the u,v,w unit basis vectors for the camera coordinate frame.
-        let w = look_sum.unit_vector();
-        let u = vup.cross(&w).unit_vector();
-        let v = w.cross(&u);
-
-        // Calculate the vectors across the horizontal and down the vertical viewport edges.
-        let viewport_u: RtVec3 = viewport_width * u;
-        let viewport_v: RtVec3 = viewport_height * -v;
-    
-        // Calculate the horizontal and vertical delta vectors from pixel to pixel.
-        let pixel_delta_u: RtVec3 = viewport_u / image_width as f64;  // Offset to pixel to the right
-        let pixel_delta_v: RtVec3 = viewport_v / image_height as f64; // Offset to pixel below
-    
-        // Calculate the location of the upper left pixel.
-        let viewport_upper_left: RtVec3 = camera_center - (focus_dist * w) - viewport_u / 2 - viewport_v / 2;
-        let pixel_00_loc: RtVec3 = viewport_upper_left + 0.5 * (pixel_delta_u + pixel_delta_v);
-    
-        // Calculate the camera defocus disk basis vectors.
-        let defocus_radius: f64 = focus_dist * f64::tan(degrees_to_radians(defocus_angle / 2.0));
-        let defocus_disk_u: RtVec3 = u * defocus_radius;
-        let defocus_disk_v: RtVec3 = v * defocus_radius;
-
-        Camera{
-            world,
-            ray_color,
-            aspect_ratio,
-            fov,
-            defocus_disk_u,
-            defocus_disk_v,
-            defocus_angle,
-            focus_dist,
-            lookfrom,
-            lookat,
-            vup,
-            image_width,
-            image_height,
-            sample_bounce_max,
-            samples_per_pixel,
-            pixel_samples_scale, 
-            viewport_width,
-            viewport_height,
-            camera_center,
-            viewport_u,
-            viewport_v,
-            pixel_delta_u,
-            pixel_delta_v,
-            viewport_upper_left,
-            pixel_00_loc,
-        }
-    }
-
-    pub fn render(&self) {
-        let _ = Self::build_file(&self);
-    }
-
-    fn defocus_disk_sample(&self) -> Point3 {
-        // Returns a random point in the camera defocus disk.
-        let p: RtVec3 = RtVec3::random_in_unit_disk();
-        let center: Point3 = self.camera_center + (p.x * self.defocus_disk_u) + (p.y * self.defocus_disk_v);
-        center
-    }
-
-    fn get_ray(
-        &self,
-        i: u32,
-        j: u32,
-    ) -> Ray {
-        // Construct a camera ray originating from the defocus disk and directed at a randomly
-        // sampled point around the pixel location i, j.
-
-        let offset = sample_square();
-        let pixel_sample = self.pixel_00_loc
-            + ((i as f64 + offset.x()) * self.pixel_delta_u)
-            + ((j as f64 + offset.y()) * self.pixel_delta_v);
-        
-        let ray_origin: RtVec3 = if self.defocus_angle <= 0.0 {
-            self.camera_center
-        } else {
-            self.defocus_disk_sample()
-        };
-        // let ray_origin = self.camera_center;
-        let ray_direction = pixel_sample - ray_origin;
-        let ray: Ray = Ray::new(ray_origin, ray_direction);
-        ray
-    }
-
-    fn build_file(&self) -> std::io::Result<()> {
-        // Setup
-        let mut file = File::create("image.ppm")?;
-        file.write_all(b"P3\n")?;
-        let img_dim = format!("{:?} {:?}\n", self.image_width, self.image_height);
-        file.write_all(img_dim.as_bytes())?;
-        file.write_all(b"255\n")?;
-        
-        // Pixel Algo
-        for pixel_h in 0..self.image_height {
-            println!("Scanline's remaining: {:?} ", (self.image_height - pixel_h));
-            for pixel_w in 0..self.image_width {
-                let mut average_pixel_color_sum: RtVec3 = RtVec3::new(0.0, 0.0, 0.0);
-                for _ in 0..self.samples_per_pixel {
-                    let ray = self.get_ray(pixel_w, pixel_h);
-                    average_pixel_color_sum = average_pixel_color_sum + color(ray, &self.world, self.sample_bounce_max);
-                }
-                let average_pixel_color = average_pixel_color_sum * self.pixel_samples_scale;
-                write_color_to_pixel(average_pixel_color, &mut file)?;
-            }
-        }
-        println!("Generation finished.");
-        Ok(())
-    }
-}
\ No newline at end of file
+use crate::aov::{write_pfm, Aov, AovBuffers, FirstHit};
+
+use crate::checkpoint::{fingerprint_hash, Checkpoint, RenderFingerprint};
+
+use crate::crop::{Crop, CropOutput, CropWindow};
+
+use crate::denoise::Denoiser;
+
+use crate::film::Film;
+
+use crate::filter::Filter;
+
+use crate::ray::{Background, Ray, Color};
+use crate::ray::{color_to_rgb8, write_color_to_pixel, color, color_spectral};
+
+use crate::rtvec3::{Point3, RtVec3};
+
+use crate::stereo::{Stereo, StereoMode};
+
+use crate::hit::{Hittable, HittableList, HitRecord};
+
+use crate::image::ImageFormat;
+
+use crate::lens::{passes_vignetting, Aperture};
+
+use crate::material::default_material_lambertian;
+
+use crate::projection::Projection;
+
+use crate::scene::CameraSettings;
+
+use crate::spectrum::SampledWavelengths;
+
+use crate::tonemap::ToneMapper;
+
+use crate::{degrees_to_radians, random_float, sample_square, Interval};
+use crate::{rng_state, restore_rng_state, seed_rng, seed_rng_stream};
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::thread;
+
+#[allow(dead_code)]
+pub struct Camera{
+    world: HittableList,
+    ray_color: Color,
+    aspect_ratio: f64,
+    fov: f64,
+    defocus_disk_u: RtVec3,
+    defocus_disk_v: RtVec3,
+    defocus_angle: f64,
+    focus_dist: f64,
+    lookfrom: Point3,
+    lookat: Point3,
+    vup: RtVec3,
+    image_width: u32,
+    image_height: u32,
+    sample_bounce_max: u32,
+    samples_per_pixel: u32,
+    pixel_samples_scale: f64,
+    viewport_width: f64,
+    viewport_height: f64,
+    camera_center: Point3,
+    viewport_u: RtVec3,
+    viewport_v: RtVec3,
+    pixel_delta_u: RtVec3,
+    pixel_delta_v: RtVec3,
+    viewport_upper_left: RtVec3,
+    pixel_00_loc: RtVec3,
+    output_path: PathBuf,
+    seed: Option<u64>,
+    checkpoint_path: Option<PathBuf>,
+    checkpoint_interval: u32,
+    tone_mapper: ToneMapper,
+    aovs: Vec<Aov>,
+    material_ids: HashMap<usize, u32>,
+    denoiser: Option<Denoiser>,
+    filter: Filter,
+    u: RtVec3,
+    v: RtVec3,
+    w: RtVec3,
+    projection: Projection,
+    stereo: Option<Stereo>,
+    aperture: Aperture,
+    optical_vignetting: f64,
+    crop: Option<Crop>,
+    format: ImageFormat,
+    background: Background,
+    spectral: bool,
+}
+
+impl Camera {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        world: HittableList,
+        ray_color: Color,
+        aspect_ratio: f64,
+        fov: f64,
+        defocus_angle: f64,
+        focus_dist: f64,
+        lookfrom: Point3,
+        lookat: Point3,
+        vup: RtVec3,
+        image_width: u32,
+        samples_per_pixel: u32,
+        sample_bounce_max: u32,
+    ) -> Self {
+        // Rendered image height
+        let mut image_height: u32 = (image_width as f64 / aspect_ratio) as u32; 
+        if image_height < 1 {
+            image_height = 1;
+        }
+
+        let pixel_samples_scale = 1.0 / samples_per_pixel as f64;
+        let zero = RtVec3::new(0.0, 0.0, 0.0);
+
+        let mut camera = Camera{
+            world,
+            ray_color,
+            aspect_ratio,
+            fov,
+            defocus_disk_u: zero,
+            defocus_disk_v: zero,
+            defocus_angle,
+            focus_dist,
+            lookfrom,
+            lookat,
+            vup,
+            image_width,
+            image_height,
+            sample_bounce_max,
+            samples_per_pixel,
+            pixel_samples_scale, 
+            viewport_width: 0.0,
+            viewport_height: 0.0,
+            camera_center: lookfrom,
+            viewport_u: zero,
+            viewport_v: zero,
+            pixel_delta_u: zero,
+            pixel_delta_v: zero,
+            viewport_upper_left: zero,
+            pixel_00_loc: zero,
+            output_path: PathBuf::from("image.ppm"),
+            seed: None,
+            checkpoint_path: None,
+            checkpoint_interval: 0,
+            tone_mapper: ToneMapper::default(),
+            aovs: Vec::new(),
+            material_ids: HashMap::new(),
+            denoiser: None,
+            filter: Filter::default(),
+            u: zero,
+            v: zero,
+            w: zero,
+            projection: Projection::default(),
+            stereo: None,
+            aperture: Aperture::default(),
+            optical_vignetting: 0.0,
+            crop: None,
+            format: ImageFormat::default(),
+            background: Background::default(),
+            spectral: false,
+        };
+        camera.set_view(lookfrom, lookat, fov, focus_dist);
+        camera
+    }
+
+    // Moves the camera, recomputing its frame and viewport. Everything else is left as is.
+    pub fn set_view(&mut self, lookfrom: Point3, lookat: Point3, fov: f64, focus_dist: f64) {
+        self.lookfrom = lookfrom;
+        self.lookat = lookat;
+        self.fov = fov;
+        self.focus_dist = focus_dist;
+
+        // Camera Viewport Data
+        self.camera_center = lookfrom;
+    
+        let look_sum: Point3 = lookfrom - lookat;
+        let theta: f64 = degrees_to_radians(fov);
+        let h: f64 = f64::tan(theta / 2.0);
+
+        self.viewport_height = 2.0 * h * focus_dist;
+        self.viewport_width = self.viewport_height * (self.image_width as f64 / self.image_height as f64);
+    
+        // Camera frame basis vectors
+        // Calculate the u,v,w unit basis vectors for the camera coordinate frame.
+        self.w = look_sum.unit_vector();
+        self.u = self.vup.cross(&self.w).unit_vector();
+        self.v = self.w.cross(&self.u);
+
+        // Calculate the vectors across the horizontal and down the vertical viewport edges.
+        self.viewport_u = self.viewport_width * self.u;
+        self.viewport_v = self.viewport_height * -self.v;
+    
+        // Calculate the horizontal and vertical delta vectors from pixel to pixel.
+        self.pixel_delta_u = self.viewport_u / self.image_width as f64;  // Offset to pixel to the right
+        self.pixel_delta_v = self.viewport_v / self.image_height as f64; // Offset to pixel below
+    
+        // Calculate the location of the upper left pixel.
+        self.viewport_upper_left = self.camera_center - (focus_dist * self.w) - self.viewport_u / 2 - self.viewport_v / 2;
+        self.pixel_00_loc = self.viewport_upper_left + 0.5 * (self.pixel_delta_u + self.pixel_delta_v);
+    
+        // Calculate the camera defocus disk basis vectors.
+        let defocus_radius: f64 = focus_dist * f64::tan(degrees_to_radians(self.defocus_angle / 2.0));
+        self.defocus_disk_u = self.u * defocus_radius;
+        self.defocus_disk_v = self.v * defocus_radius;
+    }
+
+    pub fn world(&self) -> &HittableList {
+        &self.world
+    }
+
+    pub fn format(&self) -> ImageFormat {
+        self.format
+    }
+
+    // Placement and lens of the camera, as stored in scene files.
+    pub fn settings(&self) -> CameraSettings {
+        CameraSettings {
+            aspect_ratio: self.aspect_ratio,
+            fov: self.fov,
+            lookfrom: self.lookfrom,
+            lookat: self.lookat,
+            vup: self.vup,
+            defocus_angle: self.defocus_angle,
+            focus_dist: self.focus_dist,
+            aperture: self.aperture.clone(),
+            background: self.background,
+        }
+    }
+
+    // Where the finished image is written, "image.ppm" by default.
+    pub fn with_output(mut self, path: impl Into<PathBuf>) -> Self {
+        self.set_output(path);
+        self
+    }
+
+    pub fn set_output(&mut self, path: impl Into<PathBuf>) {
+        self.output_path = path.into();
+    }
+
+    // Seeds the random generator before rendering, making the image reproducible.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    // Saves the render state to `path` every `interval` scanlines so it can be resumed.
+    pub fn with_checkpoint(mut self, path: impl Into<PathBuf>, interval: u32) -> Self {
+        self.checkpoint_path = Some(path.into());
+        self.checkpoint_interval = interval.max(1);
+        self
+    }
+
+    // Exposure and tone curve applied when the accumulated radiance is written out.
+    pub fn with_tone_mapping(mut self, tone_mapper: ToneMapper) -> Self {
+        self.tone_mapper = tone_mapper;
+        self
+    }
+
+    // First-hit passes written next to the image as <output>_<pass>.pfm
+    pub fn with_aovs(mut self, aovs: &[Aov]) -> Self {
+        self.aovs = aovs.to_vec();
+        // Material ids follow the order materials appear in the world.
+        let mut materials = Vec::new();
+        self.world.collect_materials(&mut materials);
+        self.material_ids.clear();
+        for material in materials {
+            let next_id = self.material_ids.len() as u32 + 1;
+            self.material_ids.entry(Rc::as_ptr(&material) as *const () as usize).or_insert(next_id);
+        }
+        self
+    }
+
+    // Denoises the image before it is written, the unfiltered image is kept as <output>_noisy.
+    // The normal, depth and albedo passes the filter relies on are gathered automatically.
+    pub fn with_denoiser(mut self, denoiser: Denoiser) -> Self {
+        self.denoiser = Some(denoiser);
+        self
+    }
+
+    // Reconstruction filter samples are splatted with, a box covering one pixel by default.
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    // Maps image positions to rays, thin lens perspective by default.
+    pub fn with_projection(mut self, projection: Projection) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    // Renders both eyes into one image, each eye keeps the camera's image size.
+    pub fn with_stereo(mut self, stereo: Stereo) -> Self {
+        self.stereo = Some(stereo);
+        self
+    }
+
+    // Shape of the defocus aperture, circular by default.
+    pub fn with_aperture(mut self, aperture: Aperture) -> Self {
+        self.aperture = aperture;
+        self
+    }
+
+    // Cat-eye vignetting, how far the lens barrel cuts into the aperture at the frame corners
+    // (in aperture radii, 0.0 disables it).
+    pub fn with_optical_vignetting(mut self, strength: f64) -> Self {
+        self.optical_vignetting = strength;
+        self
+    }
+
+    // Renders only the pixels inside `window`, of the stereo image as a whole when rendering both eyes.
+    pub fn with_crop(mut self, window: CropWindow, output: CropOutput) -> Self {
+        self.crop = Some(Crop::new(window, output));
+        self
+    }
+
+    // What rays leaving the scene see, the sky gradient by default.
+    pub fn with_background(mut self, background: Background) -> Self {
+        self.background = background;
+        self
+    }
+
+    // Traces wavelengths instead of RGB, so dispersive glass splits light into its colors.
+    pub fn with_spectral(mut self, spectral: bool) -> Self {
+        self.spectral = spectral;
+        self
+    }
+
+    // File format of the image (and of the noisy image kept when denoising), ASCII PPM by default.
+    pub fn with_format(mut self, format: ImageFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn render(&self) {
+        let _ = self.build_file();
+    }
+
+    // Renders on `threads` threads and writes the image. Scenes are built from `Rc`s and can't be
+    // shared, so every thread builds its own camera with `make_camera`, the first error building
+    // one ends the render. Scanlines are rendered in
+    // blocks of the checkpoint interval (all of them at once without checkpoints), every
+    // threads-th scanline of a block on the same thread. Finished blocks are added to the image
+    // in order and a checkpoint is saved after each, like a single threaded render, and an
+    // existing checkpoint is resumed from. Seeded cameras give the same image for any thread count.
+    pub fn render_threaded<F>(make_camera: F, threads: u32) -> std::io::Result<()>
+    where
+        F: Fn() -> std::io::Result<Camera> + Sync,
+    {
+        let threads = threads.max(1);
+        let camera = make_camera()?;
+        let (mut film, mut aovs, start_row) = camera.start_or_resume()?;
+        let (_, height) = camera.output_dimensions();
+        let block_rows = if camera.checkpoint_path.is_some() { camera.checkpoint_interval } else { height.max(1) };
+        let blocks: Vec<(u32, u32)> = (start_row..height)
+            .step_by(block_rows as usize)
+            .map(|start| (start, (start + block_rows).min(height)))
+            .collect();
+
+        thread::scope(|scope| -> std::io::Result<()> {
+            let (sender, receiver) = mpsc::channel();
+            for worker in 0..threads {
+                let sender = sender.clone();
+                let make_camera = &make_camera;
+                let blocks = &blocks;
+                scope.spawn(move || {
+                    let camera = match make_camera() {
+                        Ok(camera) => camera,
+                        Err(error) => {
+                            let _ = sender.send(Err(error));
+                            return;
+                        }
+                    };
+                    for (block, &(start, stop)) in blocks.iter().enumerate() {
+                        let (mut film, mut aovs) = camera.empty_buffers();
+                        for row in (start..stop).filter(|row| row % threads == worker) {
+                            camera.render_row(&mut film, &mut aovs, row);
+                        }
+                        // Sending only fails once the render has been abandoned
+                        if sender.send(Ok((block, worker, film, aovs))).is_err() {
+                            return;
+                        }
+                    }
+                });
+            }
+            drop(sender);
+
+            // Each thread's share of every block, kept until the blocks before it are complete
+            let mut parts: Vec<Vec<Option<(Film, AovBuffers)>>> = blocks.iter().map(|_| vec![None; threads as usize]).collect();
+            let mut next_block = 0;
+            for part in receiver {
+                let (block, worker, part_film, part_aovs) = part?;
+                parts[block][worker as usize] = Some((part_film, part_aovs));
+                while next_block < blocks.len() && parts[next_block].iter().all(Option::is_some) {
+                    for (part_film, part_aovs) in parts[next_block].drain(..).flatten() {
+                        film.merge(&part_film);
+                        aovs.merge(&part_aovs);
+                    }
+                    let rows_done = blocks[next_block].1;
+                    println!("Scanline's remaining: {:?} ", height - rows_done);
+                    if rows_done < height {
+                        camera.save_checkpoint(&film, &aovs, rows_done)?;
+                    }
+                    next_block += 1;
+                }
+            }
+            if next_block < blocks.len() {
+                return Err(Error::other("a render thread stopped before finishing its scanlines"));
+            }
+            Ok(())
+        })?;
+        camera.finish(&film, &aovs)
+    }
+
+    // Continues a render from the configured checkpoint file and writes the finished image.
+    pub fn resume(&self) -> std::io::Result<()> {
+        let mut checkpoint = self.load_checkpoint()?;
+        let (_, height) = self.output_dimensions();
+        self.render_rows(&mut checkpoint.film, &mut checkpoint.aovs, checkpoint.next_row, height)?;
+        self.finish(&checkpoint.film, &checkpoint.aovs)
+    }
+
+    // Renders scanlines up to `stop_row` (resuming from the checkpoint when one exists) and
+    // leaves a checkpoint behind instead of an image, for renders split across several jobs.
+    pub fn render_until(&self, stop_row: u32) -> std::io::Result<()> {
+        let (mut film, mut aovs, start_row) = self.start_or_resume()?;
+        let stop_row = stop_row.min(self.output_dimensions().1);
+        self.render_rows(&mut film, &mut aovs, start_row, stop_row)?;
+        self.save_checkpoint(&film, &aovs, stop_row.max(start_row))
+    }
+
+    fn defocus_disk_sample(&self, image_position: (f64, f64)) -> Option<Point3> {
+        // Returns a random point on the camera aperture, or None when the lens barrel blocks it.
+        let p: RtVec3 = self.aperture.sample();
+        if !passes_vignetting(p, image_position, self.optical_vignetting) {
+            return None;
+        }
+        Some(self.camera_center + (p.x * self.defocus_disk_u) + (p.y * self.defocus_disk_v))
+    }
+
+    // Size of the written image, wider or taller than image_width x image_height in stereo.
+    fn output_dimensions(&self) -> (u32, u32) {
+        match &self.stereo {
+            Some(stereo) => stereo.output_dimensions(self.image_width, self.image_height),
+            None => (self.image_width, self.image_height),
+        }
+    }
+
+    // Pixels being rendered as (min, max) corners, max exclusive. The whole image unless cropped.
+    fn crop_bounds(&self) -> ((u32, u32), (u32, u32)) {
+        let (width, height) = self.output_dimensions();
+        match &self.crop {
+            Some(crop) => crop.pixel_bounds(width, height),
+            None => ((0, 0), (width, height)),
+        }
+    }
+
+    fn get_ray(
+        &self,
+        i: u32,
+        j: u32,
+        offset: RtVec3,
+    ) -> Option<Ray> {
+        // Construct a camera ray originating from the defocus disk and directed at the point
+        // `offset` away from the centre of output pixel i, j.
+        let (eye, i, j) = match &self.stereo {
+            Some(stereo) => stereo.eye_pixel(i, j, self.image_width, self.image_height),
+            None => (0.0, i, j),
+        };
+        let eye_offset = match &self.stereo {
+            Some(stereo) => eye * stereo.interocular / 2.0,
+            None => 0.0,
+        };
+        let to_world = |local: RtVec3| local.x() * self.u + local.y() * self.v - local.z() * self.w;
+
+        let omnidirectional = matches!(self.stereo, Some(Stereo { mode: StereoMode::OmniDirectional, .. }));
+        if omnidirectional || self.projection != Projection::Perspective {
+            let s = (i as f64 + 0.5 + offset.x()) / self.image_width as f64;
+            let t = (j as f64 + 0.5 + offset.y()) / self.image_height as f64;
+            let aspect_ratio = self.image_width as f64 / self.image_height as f64;
+            if omnidirectional {
+                let (_, direction) = Projection::Equirectangular.local_ray(s, t, aspect_ratio)?;
+                // Each eye sits on the viewing circle, perpendicular to the horizontal look direction
+                let longitude = (s - 0.5) * 2.0 * std::f64::consts::PI;
+                let eye_position = eye_offset * RtVec3::new(f64::cos(longitude), 0.0, -f64::sin(longitude));
+                return Some(Ray::new(self.camera_center + to_world(eye_position), to_world(direction)));
+            }
+            let (origin, direction) = self.projection.local_ray(s, t, aspect_ratio)?;
+            let origin = origin + RtVec3::new(eye_offset, 0.0, 0.0);
+            return Some(Ray::new(self.camera_center + to_world(origin), to_world(direction)));
+        }
+
+        let pixel_sample = self.pixel_00_loc
+            + ((i as f64 + offset.x()) * self.pixel_delta_u)
+            + ((j as f64 + offset.y()) * self.pixel_delta_v);
+        
+        let ray_origin: RtVec3 = if self.defocus_angle <= 0.0 {
+            self.camera_center
+        } else {
+            let image_position = (
+                2.0 * (i as f64 + 0.5 + offset.x()) / self.image_width as f64 - 1.0,
+                1.0 - 2.0 * (j as f64 + 0.5 + offset.y()) / self.image_height as f64,
+            );
+            self.defocus_disk_sample(image_position)?
+        };
+        // Off-axis stereo: both eyes aim at the same focus plane point, so they converge there
+        let ray_origin = ray_origin + eye_offset * self.u;
+        let ray_direction = pixel_sample - ray_origin;
+        Some(Ray::new(ray_origin, ray_direction))
+    }
+
+    // Requested passes plus the feature buffers the denoiser needs.
+    fn gathered_aovs(&self) -> Vec<Aov> {
+        let mut aovs = self.aovs.clone();
+        if self.denoiser.is_some() {
+            for feature in [Aov::Normal, Aov::Depth, Aov::Albedo] {
+                if !aovs.contains(&feature) {
+                    aovs.push(feature);
+                }
+            }
+        }
+        aovs
+    }
+
+    fn start_buffers(&self) -> (Film, AovBuffers) {
+        if let Some(seed) = self.seed {
+            seed_rng(seed);
+        }
+        self.empty_buffers()
+    }
+
+    fn empty_buffers(&self) -> (Film, AovBuffers) {
+        let (width, height) = self.output_dimensions();
+        (Film::new(width, height), AovBuffers::new(&self.gathered_aovs(), width, height))
+    }
+
+    // Buffers and the scanline to continue from, out of the checkpoint when one exists.
+    fn start_or_resume(&self) -> std::io::Result<(Film, AovBuffers, u32)> {
+        match &self.checkpoint_path {
+            Some(path) if path.exists() => {
+                let checkpoint = self.load_checkpoint()?;
+                Ok((checkpoint.film, checkpoint.aovs, checkpoint.next_row))
+            }
+            _ => {
+                let (film, aovs) = self.start_buffers();
+                Ok((film, aovs, 0))
+            }
+        }
+    }
+
+    fn load_checkpoint(&self) -> std::io::Result<Checkpoint> {
+        let path = self.checkpoint_path.as_ref().ok_or_else(|| {
+            Error::new(ErrorKind::NotFound, "no checkpoint file configured for this camera")
+        })?;
+        let checkpoint = Checkpoint::load(path)?;
+        if (checkpoint.film.width, checkpoint.film.height) != self.output_dimensions()
+            || checkpoint.samples_per_pixel != self.samples_per_pixel
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("checkpoint {:?} was written for a different image size or sample count; delete it to start over", path),
+            ));
+        }
+        let checkpoint_aovs: Vec<Aov> = checkpoint.aovs.passes.iter().map(|(aov, _)| *aov).collect();
+        if checkpoint_aovs != self.gathered_aovs() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("checkpoint {:?} was written with a different set of output passes; delete it to start over", path),
+            ));
+        }
+        let fingerprint = self.fingerprint();
+        if checkpoint.fingerprint.seed != fingerprint.seed {
+            let name = |seed: Option<u64>| seed.map_or("none".to_string(), |seed| seed.to_string());
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "checkpoint {:?} was written with seed {}, this render uses seed {}; delete it to start over",
+                    path,
+                    name(checkpoint.fingerprint.seed),
+                    name(fingerprint.seed)
+                ),
+            ));
+        }
+        if checkpoint.fingerprint.settings != fingerprint.settings {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "checkpoint {:?} was written with different filter, projection, tone mapping or lens settings; delete it to start over",
+                    path
+                ),
+            ));
+        }
+        if checkpoint.fingerprint.scene != fingerprint.scene {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("checkpoint {:?} was written for a different scene or camera placement; delete it to start over", path),
+            ));
+        }
+        restore_rng_state(&checkpoint.rng);
+        Ok(checkpoint)
+    }
+
+    // Identifies what this camera renders, so only the same render picks up its checkpoint. Not
+    // every world can be written to a scene file, so the world is known by what a grid of rays
+    // through the viewport hits: distances, normals and the surface and light colors there.
+    fn fingerprint(&self) -> RenderFingerprint {
+        let settings = format!(
+            "{:?}",
+            (
+                self.filter,
+                self.projection,
+                self.tone_mapper,
+                self.stereo,
+                &self.aperture,
+                self.optical_vignetting,
+                self.crop,
+                self.sample_bounce_max,
+                self.spectral,
+            )
+        );
+        let mut scene = format!("{:?}", self.settings());
+        let probes = 16;
+        for j in 0..probes {
+            for i in 0..probes {
+                let s = (i as f64 + 0.5) / probes as f64;
+                let t = (j as f64 + 0.5) / probes as f64;
+                let target = self.viewport_upper_left + s * self.viewport_u + t * self.viewport_v;
+                let ray = Ray::new(self.camera_center, target - self.camera_center);
+                let mut record = HitRecord::new(ray.origin(), ray.origin(), 0.0, false, default_material_lambertian());
+                if self.world.hit(&ray, Interval::new(0.001, f64::INFINITY), &mut record) {
+                    let material = &record.material;
+                    scene += &format!("{:?}", (record.t, record.normal, material.albedo(&record), material.emitted(&record)));
+                } else {
+                    scene += "miss";
+                }
+            }
+        }
+        RenderFingerprint {
+            seed: self.seed,
+            settings: fingerprint_hash(settings.as_bytes()),
+            scene: fingerprint_hash(scene.as_bytes()),
+        }
+    }
+
+    fn save_checkpoint(&self, film: &Film, aovs: &AovBuffers, next_row: u32) -> std::io::Result<()> {
+        match &self.checkpoint_path {
+            Some(path) => Checkpoint::new(self.samples_per_pixel, self.fingerprint(), next_row, rng_state(), film.clone(), aovs.clone())
+                .save(path),
+            None => Ok(()),
+        }
+    }
+
+    fn first_hit(&self, ray: &Ray) -> Option<FirstHit> {
+        let mut record = HitRecord::new(
+            RtVec3::new(0.0, 0.0, 0.0),
+            RtVec3::new(0.0, 0.0, 0.0),
+            0.0,
+            false,
+            default_material_lambertian(),
+        );
+        if !self.world.hit(ray, Interval::new(0.001, f64::INFINITY), &mut record) {
+            return None;
+        }
+        let material_id = self.material_ids
+            .get(&(Rc::as_ptr(&record.material) as *const () as usize))
+            .copied()
+            .unwrap_or(0);
+        Some(FirstHit::new(ray, &record, -self.w, material_id))
+    }
+
+    // Pixels a sample taken in pixel i, j may be splatted into. In stereo a sample stays within
+    // its own eye's half of the image, so wide filters don't bleed across the seam. Samples never
+    // leave the crop window either, pixels outside it stay black.
+    fn splat_bounds(&self, i: u32, j: u32) -> ((u32, u32), (u32, u32)) {
+        let (width, height) = self.output_dimensions();
+        let ((min_i, min_j), (max_i, max_j)) = match &self.stereo {
+            Some(stereo) => {
+                let (_, eye_i, eye_j) = stereo.eye_pixel(i, j, self.image_width, self.image_height);
+                let (min_i, min_j) = (i - eye_i, j - eye_j);
+                ((min_i, min_j), (min_i + self.image_width - 1, min_j + self.image_height - 1))
+            }
+            None => ((0, 0), (width - 1, height - 1)),
+        };
+        let ((crop_min_i, crop_min_j), (crop_max_i, crop_max_j)) = self.crop_bounds();
+        (
+            (min_i.max(crop_min_i), min_j.max(crop_min_j)),
+            (max_i.min(crop_max_i - 1), max_j.min(crop_max_j - 1)),
+        )
+    }
+
+    fn render_rows(&self, film: &mut Film, aovs: &mut AovBuffers, start_row: u32, stop_row: u32) -> std::io::Result<()> {
+        // Pixel Algo
+        let (_, height) = self.output_dimensions();
+        let (_, (_, max_j)) = self.crop_bounds();
+        for pixel_h in start_row..stop_row {
+            if pixel_h < max_j {
+                println!("Scanline's remaining: {:?} ", (max_j - pixel_h));
+            }
+            self.render_row(film, aovs, pixel_h);
+            let rows_done = pixel_h + 1;
+            if self.checkpoint_interval > 0 && rows_done % self.checkpoint_interval == 0 && rows_done < height {
+                self.save_checkpoint(film, aovs, rows_done)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Samples every pixel of one scanline, skipping rows outside the crop window.
+    fn render_row(&self, film: &mut Film, aovs: &mut AovBuffers, pixel_h: u32) {
+        let ((min_i, min_j), (max_i, max_j)) = self.crop_bounds();
+        if pixel_h < min_j || pixel_h >= max_j {
+            return;
+        }
+        if let Some(seed) = self.seed {
+            // Stream 0 is left to whatever the seed is used for outside rendering, like building the scene
+            seed_rng_stream(seed, pixel_h as u64 + 1);
+        }
+        for pixel_w in min_i..max_i {
+            for _ in 0..self.samples_per_pixel {
+                let offset = sample_square();
+                let ray = self.get_ray(pixel_w, pixel_h, offset);
+                if !aovs.is_empty() {
+                    let first_hit = ray.and_then(|ray| self.first_hit(&ray));
+                    aovs.add_sample(pixel_w, pixel_h, first_hit.as_ref());
+                }
+                let sample_color = match ray {
+                    Some(ray) if self.spectral => {
+                        let mut wavelengths = SampledWavelengths::sample(random_float());
+                        let radiance = color_spectral(ray, &self.world, self.sample_bounce_max, &self.background, &mut wavelengths);
+                        wavelengths.to_rgb(radiance)
+                    }
+                    Some(ray) => color(ray, &self.world, self.sample_bounce_max, &self.background),
+                    None => RtVec3::new(0.0, 0.0, 0.0),
+                };
+                let sample_x = pixel_w as f64 + 0.5 + offset.x();
+                let sample_y = pixel_h as f64 + 0.5 + offset.y();
+                let (min, max) = self.splat_bounds(pixel_w, pixel_h);
+                film.splat_within(sample_x, sample_y, sample_color, &self.filter, min, max);
+            }
+        }
+    }
+
+    // Renders from scratch and writes the image, reporting what `render` leaves unchecked.
+    pub(crate) fn build_file(&self) -> std::io::Result<()> {
+        let (mut film, mut aovs) = self.start_buffers();
+        let (_, height) = self.output_dimensions();
+        self.render_rows(&mut film, &mut aovs, 0, height)?;
+        self.finish(&film, &aovs)
+    }
+
+    fn finish(&self, film: &Film, aovs: &AovBuffers) -> std::io::Result<()> {
+        // Only the crop window holds samples, so the denoiser sees just that region
+        let ((min_i, min_j), (max_i, max_j)) = self.crop_bounds();
+        let region = film.crop(min_i, min_j, max_i - min_i, max_j - min_j);
+        let region_aovs = aovs.crop(min_i, min_j, max_i - min_i, max_j - min_j);
+        let full_frame = matches!(self.crop, Some(Crop { output: CropOutput::FullFrame, .. }));
+        let framed = |region: Film| {
+            if !full_frame {
+                return region;
+            }
+            let mut frame = Film::new(film.width, film.height);
+            frame.paste(&region, min_i, min_j);
+            frame
+        };
+
+        match (&self.denoiser, region_aovs.get(Aov::Normal), region_aovs.get(Aov::Depth), region_aovs.get(Aov::Albedo)) {
+            (Some(denoiser), Some(normal), Some(depth), Some(albedo)) => {
+                let stem = self.output_path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("image");
+                let denoised = denoiser.denoise(&region, normal, depth, albedo);
+                self.write_film(&framed(region), &self.output_path.with_file_name(format!("{}_noisy.{}", stem, self.format.extension())))?;
+                self.write_film(&framed(denoised), &self.output_path)?;
+            }
+            _ => self.write_film(&framed(region), &self.output_path)?,
+        }
+        if full_frame {
+            aovs.write(&self.output_path, &self.aovs)?;
+        } else {
+            region_aovs.write(&self.output_path, &self.aovs)?;
+        }
+        // The image is complete, the checkpoint is no longer needed.
+        if let Some(path) = &self.checkpoint_path {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+        println!("Generation finished.");
+        Ok(())
+    }
+    fn write_film(&self, film: &Film, path: &Path) -> std::io::Result<()> {
+        if self.format == ImageFormat::Pfm {
+            return write_pfm(film, path);
+        }
+        // Setup
+        let mut file = BufWriter::new(File::create(path)?);
+        let magic: &[u8] = if self.format == ImageFormat::BinaryPpm { b"P6\n" } else { b"P3\n" };
+        file.write_all(magic)?;
+        let img_dim = format!("{:?} {:?}\n", film.width, film.height);
+        file.write_all(img_dim.as_bytes())?;
+        file.write_all(b"255\n")?;
+
+        for pixel_h in 0..film.height {
+            for pixel_w in 0..film.width {
+                let pixel = film.resolve(pixel_w, pixel_h);
+                match self.format {
+                    ImageFormat::BinaryPpm => file.write_all(&color_to_rgb8(pixel, &self.tone_mapper))?,
+                    _ => write_color_to_pixel(pixel, &self.tone_mapper, &mut file)?,
+                }
+            }
+        }
+        file.flush()
+    }
+}
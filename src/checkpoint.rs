@@ -0,0 +1,214 @@
+use crate::aov::{Aov, AovBuffers};
+
+use crate::film::Film;
+
+use crate::rtvec3::RtVec3;
+
+use crate::RngState;
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Error, ErrorKind, Write};
+use std::path::Path;
+
+const CHECKPOINT_MAGIC: &str = "RTCHECKPOINT 2";
+
+// What a render was started with beyond its size, sample count and passes. A checkpoint is only
+// resumed by a render with the same fingerprint, anything else would mix two images.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RenderFingerprint {
+    pub seed: Option<u64>,
+    pub settings: u64, // Hash of the filter, projection, tone mapping and other camera settings
+    pub scene: u64,    // Hash of the camera placement and of the world it looks at
+}
+
+// Snapshot of an interrupted render: the accumulation buffers, the scanline to continue from
+// and the random generator state at that point. Floats are stored as their raw bits so a
+// resumed render continues from exactly the same numbers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Checkpoint {
+    pub samples_per_pixel: u32,
+    pub fingerprint: RenderFingerprint,
+    pub next_row: u32,
+    pub rng: RngState,
+    pub film: Film,
+    pub aovs: AovBuffers,
+}
+
+impl Checkpoint {
+    pub fn new(
+        samples_per_pixel: u32,
+        fingerprint: RenderFingerprint,
+        next_row: u32,
+        rng: RngState,
+        film: Film,
+        aovs: AovBuffers,
+    ) -> Self {
+        Checkpoint {
+            samples_per_pixel,
+            fingerprint,
+            next_row,
+            rng,
+            film,
+            aovs,
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        // Write next to the target and rename, so a kill mid-write never leaves a corrupt checkpoint.
+        let temp_path = path.with_extension("tmp");
+        {
+            let mut file = BufWriter::new(File::create(&temp_path)?);
+            writeln!(file, "{}", CHECKPOINT_MAGIC)?;
+            writeln!(file, "dimensions {} {}", self.film.width, self.film.height)?;
+            writeln!(file, "samples_per_pixel {}", self.samples_per_pixel)?;
+            match self.fingerprint.seed {
+                Some(seed) => writeln!(file, "seed {}", seed)?,
+                None => writeln!(file, "seed none")?,
+            }
+            writeln!(file, "settings {:016x}", self.fingerprint.settings)?;
+            writeln!(file, "scene {:016x}", self.fingerprint.scene)?;
+            writeln!(file, "next_row {}", self.next_row)?;
+            let seed: String = self.rng.seed.iter().map(|byte| format!("{:02x}", byte)).collect();
+            writeln!(file, "rng {} {} {}", seed, self.rng.stream, self.rng.word_pos)?;
+            write_pixels(&mut file, &self.film)?;
+            for (aov, film) in &self.aovs.passes {
+                writeln!(file, "aov {}", aov.name())?;
+                write_pixels(&mut file, film)?;
+            }
+            file.flush()?;
+        }
+        fs::rename(&temp_path, path)
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let file = BufReader::new(File::open(path)?);
+        let mut lines = file.lines();
+
+        let header = next_line(&mut lines, "header")?;
+        if header != CHECKPOINT_MAGIC {
+            if header.starts_with("RTCHECKPOINT") {
+                return Err(invalid_data("checkpoint was written by an older version, delete it to start over".to_string()));
+            }
+            return Err(invalid_data("not a render checkpoint".to_string()));
+        }
+        let dimensions = keyed_fields(&next_line(&mut lines, "dimensions")?, "dimensions", 2)?;
+        let width: u32 = parse_field(&dimensions[0])?;
+        let height: u32 = parse_field(&dimensions[1])?;
+        let samples_per_pixel: u32 = parse_field(&keyed_fields(&next_line(&mut lines, "samples_per_pixel")?, "samples_per_pixel", 1)?[0])?;
+        let seed = keyed_fields(&next_line(&mut lines, "seed")?, "seed", 1)?.remove(0);
+        let seed: Option<u64> = if seed == "none" { None } else { Some(parse_field(&seed)?) };
+        let settings = parse_hash(&keyed_fields(&next_line(&mut lines, "settings")?, "settings", 1)?[0])?;
+        let scene = parse_hash(&keyed_fields(&next_line(&mut lines, "scene")?, "scene", 1)?[0])?;
+        let fingerprint = RenderFingerprint { seed, settings, scene };
+        let next_row: u32 = parse_field(&keyed_fields(&next_line(&mut lines, "next_row")?, "next_row", 1)?[0])?;
+
+        let rng_fields = keyed_fields(&next_line(&mut lines, "rng")?, "rng", 3)?;
+        if rng_fields[0].len() != 64 {
+            return Err(invalid_data("rng seed must be 32 hex encoded bytes".to_string()));
+        }
+        let mut seed = [0u8; 32];
+        for (index, byte) in seed.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&rng_fields[0][index * 2..index * 2 + 2], 16)
+                .map_err(|err| invalid_data(err.to_string()))?;
+        }
+        let rng = RngState {
+            seed,
+            stream: parse_field(&rng_fields[1])?,
+            word_pos: parse_field(&rng_fields[2])?,
+        };
+
+        let mut film = Film::new(width, height);
+        read_pixels(&mut lines, &mut film)?;
+
+        let mut aovs = AovBuffers::new(&[], width, height);
+        while let Some(line) = lines.next() {
+            let name = keyed_fields(&line?, "aov", 1)?.remove(0);
+            let aov = Aov::from_name(&name).ok_or_else(|| invalid_data(format!("unknown aov '{}'", name)))?;
+            let mut aov_film = Film::new(width, height);
+            read_pixels(&mut lines, &mut aov_film)?;
+            aovs.passes.push((aov, aov_film));
+        }
+
+        Ok(Checkpoint::new(samples_per_pixel, fingerprint, next_row, rng, film, aovs))
+    }
+}
+
+fn write_pixels(file: &mut impl Write, film: &Film) -> std::io::Result<()> {
+    for ((pixel, weight), count) in film.pixels.iter().zip(&film.weights).zip(&film.sample_counts) {
+        writeln!(
+            file,
+            "{:016x} {:016x} {:016x} {:016x} {}",
+            pixel.x.to_bits(),
+            pixel.y.to_bits(),
+            pixel.z.to_bits(),
+            weight.to_bits(),
+            count
+        )?;
+    }
+    Ok(())
+}
+
+fn next_line(
+    lines: &mut impl Iterator<Item = std::io::Result<String>>,
+    what: &str,
+) -> std::io::Result<String> {
+    match lines.next() {
+        Some(line) => line,
+        None => Err(invalid_data(format!("checkpoint ended before {}", what))),
+    }
+}
+
+fn read_pixels(
+    lines: &mut impl Iterator<Item = std::io::Result<String>>,
+    film: &mut Film,
+) -> std::io::Result<()> {
+    for index in 0..film.pixels.len() {
+        let line = next_line(lines, "pixel data")?;
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(invalid_data(format!("malformed pixel entry {}", index)));
+        }
+        let channel = |field: &str| -> std::io::Result<f64> {
+            u64::from_str_radix(field, 16)
+                .map(f64::from_bits)
+                .map_err(|err| invalid_data(err.to_string()))
+        };
+        film.pixels[index] = RtVec3::new(channel(fields[0])?, channel(fields[1])?, channel(fields[2])?);
+        film.weights[index] = channel(fields[3])?;
+        film.sample_counts[index] = parse_field(fields[4])?;
+    }
+    Ok(())
+}
+
+// 64-bit FNV-1a, stable across platforms and compiler versions unlike `DefaultHasher`, so a
+// checkpoint written by one build can be checked by the next.
+pub fn fingerprint_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3))
+}
+
+fn parse_hash(field: &str) -> std::io::Result<u64> {
+    u64::from_str_radix(field, 16).map_err(|err| invalid_data(err.to_string()))
+}
+
+fn invalid_data(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+fn keyed_fields(line: &str, key: &str, count: usize) -> std::io::Result<Vec<String>> {
+    let mut fields = line.split_whitespace();
+    if fields.next() != Some(key) {
+        return Err(invalid_data(format!("expected '{}' in checkpoint", key)));
+    }
+    let values: Vec<String> = fields.map(str::to_string).collect();
+    if values.len() != count {
+        return Err(invalid_data(format!("'{}' expects {} value(s)", key, count)));
+    }
+    Ok(values)
+}
+
+fn parse_field<T: std::str::FromStr>(field: &str) -> std::io::Result<T>
+where
+    T::Err: std::fmt::Display,
+{
+    field.parse().map_err(|err: T::Err| invalid_data(err.to_string()))
+}
@@ -0,0 +1,179 @@
+use crate::image::ImageFormat;
+
+use std::path::PathBuf;
+
+const USAGE_HEAD: &str = "\
+Usage: raytracing_in_a_weekend [OPTIONS]
+
+Options:
+  --width <PIXELS>      Image width, the height follows the scene's aspect ratio [default: 1920]
+  --spp <COUNT>         Samples per pixel [default: 500]
+  --max-depth <COUNT>   Maximum number of bounces per ray [default: 50]
+  --seed <NUMBER>       Random seed, the same seed renders the same image [default: 2024]
+  --scene <NAME|FILE>   Built-in scene, a .toml scene file or a .gltf / .glb model
+";
+
+const USAGE_TAIL: &str = "  --output <PATH>       Image file to write, .ppm or .pfm [default: image.<format extension>]
+  --threads <COUNT>     Render threads [default: all cores]
+  --checkpoint <FILE>   Save progress to FILE every 20 scanlines, and resume from it if it
+                        exists. Delete it to start the render over
+  --format <FORMAT>     ppm, ppm-binary or pfm [default: from the output extension, else ppm]
+  --save-scene <FILE>   Write the scene to a .toml scene file instead of rendering it
+  --spectral            Trace wavelengths instead of RGB, for dispersion in glass
+  -h, --help            Print this help
+";
+
+const USAGE_INDENT: usize = 24;
+const USAGE_WIDTH: usize = 80;
+
+// Help text listing `scenes`, the built-in scene names, under --scene. The first one is the
+// default, as in `parse_args`.
+pub fn usage(scenes: &[&str]) -> String {
+    let indent = " ".repeat(USAGE_INDENT);
+    let mut usage = USAGE_HEAD.to_string();
+    if let Some(scene) = scenes.first() {
+        usage.push_str(&format!("{}[default: {}]\n", indent, scene));
+    }
+    // Names wrapped into lines of at most USAGE_WIDTH characters
+    let mut line = String::new();
+    for (index, scene) in scenes.iter().enumerate() {
+        let entry = if index + 1 < scenes.len() { format!("{},", scene) } else { scene.to_string() };
+        if !line.is_empty() && line.len() + 1 + entry.len() > USAGE_WIDTH {
+            usage.push_str(&line);
+            usage.push('\n');
+            line.clear();
+        }
+        line.push_str(if line.is_empty() { &indent } else { " " });
+        line.push_str(&entry);
+    }
+    if !line.is_empty() {
+        usage.push_str(&line);
+        usage.push('\n');
+    }
+    usage.push_str(USAGE_TAIL);
+    usage
+}
+
+// Settings for one render, as given on the command line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderOptions {
+    pub width: u32,
+    pub samples_per_pixel: u32,
+    pub max_depth: u32,
+    pub seed: u64,
+    pub scene: String,
+    pub output: PathBuf,
+    pub threads: u32,
+    pub format: ImageFormat,
+    pub save_scene: Option<PathBuf>,
+    pub checkpoint: Option<PathBuf>,
+    pub spectral: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Render(RenderOptions),
+    Help,
+}
+
+// Parses the arguments following the program name. `scenes` lists the built-in scene names,
+// the first one is the default. Any .toml, .gltf or .glb path is accepted as a scene file.
+pub fn parse_args<I>(args: I, scenes: &[&str]) -> Result<Command, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut width = 1920;
+    let mut samples_per_pixel = 500;
+    let mut max_depth = 50;
+    let mut seed = 2024;
+    let mut scene = scenes.first().map(|scene| scene.to_string()).unwrap_or_default();
+    let mut output: Option<PathBuf> = None;
+    let mut threads = std::thread::available_parallelism().map(|count| count.get() as u32).unwrap_or(1);
+    let mut format: Option<ImageFormat> = None;
+    let mut save_scene: Option<PathBuf> = None;
+    let mut checkpoint: Option<PathBuf> = None;
+    let mut spectral = false;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        // Both "--width 640" and "--width=640" are accepted
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+            _ => (arg.clone(), None),
+        };
+        if flag == "-h" || flag == "--help" {
+            return Ok(Command::Help);
+        }
+        // The only flag without a value
+        if arg == "--spectral" {
+            spectral = true;
+            continue;
+        }
+        let mut value = || match inline_value.clone().or_else(|| args.next()) {
+            Some(value) => Ok(value),
+            None => Err(format!("{} needs a value", flag)),
+        };
+        match flag.as_str() {
+            "--width" => width = parse_count(&flag, &value()?)?,
+            "--spp" => samples_per_pixel = parse_count(&flag, &value()?)?,
+            "--max-depth" => max_depth = parse_count(&flag, &value()?)?,
+            "--threads" => threads = parse_count(&flag, &value()?)?,
+            "--seed" => {
+                let value = value()?;
+                seed = value.parse().map_err(|_| format!("--seed expects a whole number, got \"{}\"", value))?;
+            }
+            "--scene" => {
+                let value = value()?;
+                let is_file = [".toml", ".gltf", ".glb"].iter().any(|extension| value.ends_with(extension));
+                if !scenes.contains(&value.as_str()) && !is_file {
+                    return Err(format!("unknown scene \"{}\", expected a .toml, .gltf or .glb file or one of: {}", value, scenes.join(", ")));
+                }
+                scene = value;
+            }
+            "--output" => output = Some(PathBuf::from(value()?)),
+            "--format" => {
+                let value = value()?;
+                let names: Vec<&str> = ImageFormat::ALL.iter().map(|format| format.name()).collect();
+                format = Some(ImageFormat::from_name(&value).ok_or_else(|| {
+                    format!("unknown format \"{}\", expected one of: {}", value, names.join(", "))
+                })?);
+            }
+            "--save-scene" => save_scene = Some(PathBuf::from(value()?)),
+            "--checkpoint" => checkpoint = Some(PathBuf::from(value()?)),
+            _ => return Err(format!("unexpected argument \"{}\"", arg)),
+        }
+    }
+
+    let format = format.unwrap_or_else(|| match &output {
+        Some(path) if path.extension().is_some_and(|extension| extension == "pfm") => ImageFormat::Pfm,
+        _ => ImageFormat::Ppm,
+    });
+    // Images are only written as netpbm, other extensions would get the wrong kind of file
+    if let Some(path) = &output {
+        if path.extension().is_some_and(|extension| extension != format.extension()) {
+            return Err(format!("--output {:?} should end in .{}, images are written as {}", path, format.extension(), format.name()));
+        }
+    }
+    let output = output.unwrap_or_else(|| PathBuf::from(format!("image.{}", format.extension())));
+    Ok(Command::Render(RenderOptions {
+        width,
+        samples_per_pixel,
+        max_depth,
+        seed,
+        scene,
+        output,
+        threads,
+        format,
+        save_scene,
+        checkpoint,
+        spectral,
+    }))
+}
+
+// Positive whole number, zero pixels / samples / bounces / threads is never useful.
+fn parse_count(flag: &str, value: &str) -> Result<u32, String> {
+    match value.parse::<u32>() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err(format!("{} expects a positive whole number, got \"{}\"", flag, value)),
+    }
+}
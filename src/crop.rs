@@ -0,0 +1,47 @@
+// Region of interest: only pixels inside the window are rendered.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CropWindow {
+    // Pixel corners of the output image, min inclusive and max exclusive
+    Pixels { min: (u32, u32), max: (u32, u32) },
+    // Fractions of the output image size, (0, 0) top left and (1, 1) bottom right
+    Normalized { min: (f64, f64), max: (f64, f64) },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CropOutput {
+    Cropped,   // Only the window is written, the image is the size of the window
+    FullFrame, // The full image is written, pixels outside the window are black
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Crop {
+    pub window: CropWindow,
+    pub output: CropOutput,
+}
+
+impl Crop {
+    pub fn new(window: CropWindow, output: CropOutput) -> Self {
+        Crop { window, output }
+    }
+
+    // Window in pixels of a `width` x `height` image as (min, max) corners, min inclusive and max
+    // exclusive. Clamped to the image and never empty.
+    pub fn pixel_bounds(&self, width: u32, height: u32) -> ((u32, u32), (u32, u32)) {
+        let (min, max) = match self.window {
+            CropWindow::Pixels { min, max } => (min, max),
+            CropWindow::Normalized { min, max } => {
+                let to_pixel = |fraction: f64, size: u32| (fraction.clamp(0.0, 1.0) * size as f64).round() as u32;
+                (
+                    (to_pixel(min.0, width), to_pixel(min.1, height)),
+                    (to_pixel(max.0, width), to_pixel(max.1, height)),
+                )
+            }
+        };
+        let min_i = min.0.min(width - 1);
+        let min_j = min.1.min(height - 1);
+        let max_i = max.0.clamp(min_i + 1, width);
+        let max_j = max.1.clamp(min_j + 1, height);
+        ((min_i, min_j), (max_i, max_j))
+    }
+}
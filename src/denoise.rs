@@ -0,0 +1,140 @@
+use crate::film::Film;
+
+use crate::rtvec3::RtVec3;
+
+// Edge-aware à-trous wavelet denoiser (the spatial filter from SVGF).
+// Repeated 5x5 passes with a growing step size blur the noisy radiance, while weights from the
+// first-hit normal, depth and albedo passes stop the blur at geometry and material edges.
+// Filtering happens on demodulated radiance (radiance / albedo) so texture detail survives.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Denoiser {
+    pub iterations: u32,      // Number of à-trous passes, the footprint doubles with each one
+    pub sigma_luminance: f64, // Tolerance to luminance differences, relative to the local noise level
+    pub sigma_normal: f64,    // Exponent on the normal similarity, higher keeps creases sharper
+    pub sigma_depth: f64,     // Tolerance to relative depth differences
+    pub sigma_albedo: f64,    // Tolerance to albedo differences
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        Denoiser {
+            iterations: 5,
+            sigma_luminance: 4.0,
+            sigma_normal: 128.0,
+            sigma_depth: 0.1,
+            sigma_albedo: 0.1,
+        }
+    }
+}
+
+const KERNEL: [f64; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+const EPSILON: f64 = 1e-4;
+
+fn luminance(color: RtVec3) -> f64 {
+    0.2126 * color.x() + 0.7152 * color.y() + 0.0722 * color.z()
+}
+
+fn resolved(film: &Film) -> Vec<RtVec3> {
+    let mut pixels = Vec::with_capacity(film.pixels.len());
+    for pixel_h in 0..film.height {
+        for pixel_w in 0..film.width {
+            pixels.push(film.resolve(pixel_w, pixel_h));
+        }
+    }
+    pixels
+}
+
+impl Denoiser {
+    // Returns the denoised beauty image, every pixel carrying a single resolved sample.
+    pub fn denoise(&self, beauty: &Film, normal: &Film, depth: &Film, albedo: &Film) -> Film {
+        let (width, height) = (beauty.width as i64, beauty.height as i64);
+        let normals = resolved(normal);
+        let depths: Vec<f64> = resolved(depth).iter().map(|d| d.x()).collect();
+        let albedos: Vec<RtVec3> = resolved(albedo)
+            .iter()
+            .map(|a| RtVec3::new(a.x().max(EPSILON), a.y().max(EPSILON), a.z().max(EPSILON)))
+            .collect();
+
+        // Demodulate
+        let mut irradiance: Vec<RtVec3> = resolved(beauty)
+            .iter()
+            .zip(&albedos)
+            .map(|(color, albedo)| *color / *albedo)
+            .collect();
+
+        for iteration in 0..self.iterations {
+            let step = 1i64 << iteration;
+            let noise = self.local_deviation(&irradiance, width, height);
+            let mut filtered = irradiance.clone();
+
+            for y in 0..height {
+                for x in 0..width {
+                    let p = (y * width + x) as usize;
+                    let luminance_p = luminance(irradiance[p]);
+                    let mut sum = RtVec3::new(0.0, 0.0, 0.0);
+                    let mut weight_sum = 0.0;
+
+                    for (ky, kernel_y) in KERNEL.iter().enumerate() {
+                        for (kx, kernel_x) in KERNEL.iter().enumerate() {
+                            let qx = x + (kx as i64 - 2) * step;
+                            let qy = y + (ky as i64 - 2) * step;
+                            if qx < 0 || qy < 0 || qx >= width || qy >= height {
+                                continue;
+                            }
+                            let q = (qy * width + qx) as usize;
+
+                            let weight_normal = normals[p].dot(&normals[q]).max(0.0).powf(self.sigma_normal);
+                            let depth_scale = self.sigma_depth * depths[p].abs().max(EPSILON) * step as f64;
+                            let weight_depth = f64::exp(-(depths[p] - depths[q]).abs() / depth_scale);
+                            let albedo_difference = (albedos[p] - albedos[q]).length();
+                            let weight_albedo = f64::exp(-albedo_difference / self.sigma_albedo);
+                            let luminance_difference = (luminance_p - luminance(irradiance[q])).abs();
+                            let weight_luminance = f64::exp(-luminance_difference / (self.sigma_luminance * noise[p] + EPSILON));
+
+                            let weight = kernel_x * kernel_y * weight_normal * weight_depth * weight_albedo * weight_luminance;
+                            sum = sum + weight * irradiance[q];
+                            weight_sum += weight;
+                        }
+                    }
+
+                    if weight_sum > 0.0 {
+                        filtered[p] = sum / weight_sum;
+                    }
+                }
+            }
+            irradiance = filtered;
+        }
+
+        // Remodulate
+        let mut output = Film::new(beauty.width, beauty.height);
+        for pixel_h in 0..beauty.height {
+            for pixel_w in 0..beauty.width {
+                let p = (pixel_h as i64 * width + pixel_w as i64) as usize;
+                output.add_sample(pixel_w, pixel_h, irradiance[p] * albedos[p]);
+            }
+        }
+        output
+    }
+
+    // Standard deviation of luminance over each pixel's 3x3 neighbourhood, the noise estimate
+    // the luminance edge-stopping function is scaled by.
+    fn local_deviation(&self, image: &[RtVec3], width: i64, height: i64) -> Vec<f64> {
+        let mut deviation = vec![0.0; image.len()];
+        for y in 0..height {
+            for x in 0..width {
+                let (mut sum, mut sum_squared, mut count) = (0.0, 0.0, 0.0);
+                for qy in (y - 1).max(0)..=(y + 1).min(height - 1) {
+                    for qx in (x - 1).max(0)..=(x + 1).min(width - 1) {
+                        let l = luminance(image[(qy * width + qx) as usize]);
+                        sum += l;
+                        sum_squared += l * l;
+                        count += 1.0;
+                    }
+                }
+                let mean = sum / count;
+                deviation[(y * width + x) as usize] = (sum_squared / count - mean * mean).max(0.0).sqrt();
+            }
+        }
+        deviation
+    }
+}
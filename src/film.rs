@@ -0,0 +1,117 @@
+use crate::filter::Filter;
+
+use crate::rtvec3::RtVec3;
+
+// Accumulation buffer for a render in progress.
+// Each pixel holds the filter weighted sum of its radiance samples, the sum of those weights and
+// how many samples landed on it, so a partially rendered image can be stored and picked back up later.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Film {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<RtVec3>,
+    pub weights: Vec<f64>,
+    pub sample_counts: Vec<u32>,
+}
+
+impl Film {
+    pub fn new(width: u32, height: u32) -> Self {
+        let len = (width * height) as usize;
+        Film {
+            width,
+            height,
+            pixels: vec![RtVec3::new(0.0, 0.0, 0.0); len],
+            weights: vec![0.0; len],
+            sample_counts: vec![0; len],
+        }
+    }
+
+    fn index(&self, i: u32, j: u32) -> usize {
+        (j * self.width + i) as usize
+    }
+
+    pub fn add_sample(&mut self, i: u32, j: u32, color: RtVec3) {
+        self.add_weighted_sample(i, j, color, 1.0);
+    }
+
+    pub fn add_weighted_sample(&mut self, i: u32, j: u32, color: RtVec3, weight: f64) {
+        let index = self.index(i, j);
+        self.pixels[index] = self.pixels[index] + weight * color;
+        self.weights[index] += weight;
+        self.sample_counts[index] += 1;
+    }
+
+    // Spreads a sample taken at continuous image position (x, y) over every pixel the filter reaches.
+    // Pixel i, j has its centre at (i + 0.5, j + 0.5).
+    pub fn splat(&mut self, x: f64, y: f64, color: RtVec3, filter: &Filter) {
+        self.splat_within(x, y, color, filter, (0, 0), (self.width - 1, self.height - 1));
+    }
+
+    // Same as `splat`, but only touches pixels between the `min` and `max` corners (inclusive).
+    pub fn splat_within(&mut self, x: f64, y: f64, color: RtVec3, filter: &Filter, min: (u32, u32), max: (u32, u32)) {
+        let radius = filter.radius();
+        // Centres in (x - radius, x + radius], so a box of radius 0.5 only ever hits one pixel
+        let first_i = f64::max((x - 0.5 - radius).floor() + 1.0, min.0 as f64) as i64;
+        let last_i = f64::min((x - 0.5 + radius).floor(), max.0 as f64) as i64;
+        let first_j = f64::max((y - 0.5 - radius).floor() + 1.0, min.1 as f64) as i64;
+        let last_j = f64::min((y - 0.5 + radius).floor(), max.1 as f64) as i64;
+
+        for j in first_j..=last_j {
+            for i in first_i..=last_i {
+                let weight = filter.evaluate(i as f64 + 0.5 - x, j as f64 + 0.5 - y);
+                if weight != 0.0 {
+                    self.add_weighted_sample(i as u32, j as u32, color, weight);
+                }
+            }
+        }
+    }
+
+    pub fn sample_count(&self, i: u32, j: u32) -> u32 {
+        self.sample_counts[self.index(i, j)]
+    }
+
+    // Weighted average of all samples that reached pixel i, j. Black if none did, or if only the
+    // negative lobes of a filter like Mitchell's reached it (at the image edges), which would
+    // flip the pixel's sign or blow it up.
+    pub fn resolve(&self, i: u32, j: u32) -> RtVec3 {
+        let index = self.index(i, j);
+        let weight = self.weights[index];
+        if weight <= f64::EPSILON {
+            return RtVec3::new(0.0, 0.0, 0.0);
+        }
+        self.pixels[index] / weight
+    }
+
+    // Adds every sample gathered by `other`, a film of the same size rendered separately.
+    pub fn merge(&mut self, other: &Film) {
+        for index in 0..self.pixels.len() {
+            self.pixels[index] = self.pixels[index] + other.pixels[index];
+            self.weights[index] += other.weights[index];
+            self.sample_counts[index] += other.sample_counts[index];
+        }
+    }
+
+    // Copy of the `width` x `height` block of pixels whose top left pixel is i, j.
+    pub fn crop(&self, i: u32, j: u32, width: u32, height: u32) -> Film {
+        let mut cropped = Film::new(width, height);
+        cropped.paste_from(self, (i, j), (0, 0), (width, height));
+        cropped
+    }
+
+    // Places `film` into this one with its top left pixel at i, j.
+    pub fn paste(&mut self, film: &Film, i: u32, j: u32) {
+        self.paste_from(film, (0, 0), (i, j), (film.width, film.height));
+    }
+
+    fn paste_from(&mut self, film: &Film, from: (u32, u32), to: (u32, u32), size: (u32, u32)) {
+        for y in 0..size.1 {
+            for x in 0..size.0 {
+                let source = film.index(from.0 + x, from.1 + y);
+                let target = self.index(to.0 + x, to.1 + y);
+                self.pixels[target] = film.pixels[source];
+                self.weights[target] = film.weights[source];
+                self.sample_counts[target] = film.sample_counts[source];
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+// Pixel reconstruction filters.
+// Every camera sample is splatted into all pixels whose centre lies within the filter radius,
+// weighted by the filter at that offset. A box of radius 0.5 reproduces a plain per-pixel average.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Filter {
+    Box { radius: f64 },
+    Tent { radius: f64 },
+    Gaussian { radius: f64, alpha: f64 },             // alpha controls the falloff, larger is sharper
+    MitchellNetravali { radius: f64, b: f64, c: f64 }, // b = c = 1/3 is the recommended balance
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Filter::Box { radius: 0.5 }
+    }
+}
+
+impl Filter {
+    pub fn radius(&self) -> f64 {
+        match *self {
+            Filter::Box { radius } => radius,
+            Filter::Tent { radius } => radius,
+            Filter::Gaussian { radius, .. } => radius,
+            Filter::MitchellNetravali { radius, .. } => radius,
+        }
+    }
+
+    // Weight of a sample offset by (x, y) pixels from a pixel centre. All filters are separable.
+    pub fn evaluate(&self, x: f64, y: f64) -> f64 {
+        self.evaluate_1d(x) * self.evaluate_1d(y)
+    }
+
+    fn evaluate_1d(&self, x: f64) -> f64 {
+        let x = x.abs();
+        match *self {
+            Filter::Box { radius } => {
+                if x <= radius { 1.0 } else { 0.0 }
+            }
+            Filter::Tent { radius } => f64::max(0.0, radius - x),
+            Filter::Gaussian { radius, alpha } => {
+                // Shifted down so the filter reaches zero at its radius instead of being cut off
+                f64::max(0.0, f64::exp(-alpha * x * x) - f64::exp(-alpha * radius * radius))
+            }
+            Filter::MitchellNetravali { radius, b, c } => {
+                if x > radius {
+                    return 0.0;
+                }
+                // The cubic is defined over [0, 2], stretch it to cover the radius
+                let x = 2.0 * x / radius;
+                if x > 1.0 {
+                    ((-b - 6.0 * c) * x * x * x + (6.0 * b + 30.0 * c) * x * x
+                        + (-12.0 * b - 48.0 * c) * x + (8.0 * b + 24.0 * c)) / 6.0
+                } else {
+                    ((12.0 - 9.0 * b - 6.0 * c) * x * x * x + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+                        + (6.0 - 2.0 * b)) / 6.0
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,331 @@
+use crate::hit::HittableList;
+
+use crate::image::Image;
+
+use crate::material::{
+    new_material_diffuse_light_color, new_material_principled, new_material_principled_texture, Material, NormalMap,
+    PrincipledParameters,
+};
+
+use crate::mesh::{Aabb, Mesh};
+
+use crate::ray::Color;
+
+use crate::rtvec3::{Point3, RtVec3};
+
+use crate::scene::CameraSettings;
+
+use crate::scenes::Preset;
+
+use crate::texture::{ImageTexture, WrapMode};
+
+use gltf::camera::Projection;
+use gltf::image::Format;
+use gltf::mesh::Mode;
+use gltf::texture::WrappingMode;
+
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+use std::rc::Rc;
+
+// Column-major 4x4 matrix, as glTF stores node transforms
+type Matrix = [[f64; 4]; 4];
+
+const IDENTITY: Matrix = [[1.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0]];
+
+// Loads a .gltf (with its buffers and images) or .glb file into a world and camera.
+//
+// Triangle primitives of every node in the default scene become meshes, with the node
+// transforms baked into their vertices. Metallic-roughness materials (with transmission and IOR)
+// become Principled materials with the base color and normal textures, emissive ones
+// DiffuseLight. The first perspective camera is used, without one the camera is placed in front
+// of the model looking at its centre.
+pub fn load_gltf(path: &Path) -> std::io::Result<Preset> {
+    let invalid = |message: String| Error::new(ErrorKind::InvalidData, format!("{:?}: {}", path, message));
+    let (document, buffers, images) = gltf::import(path).map_err(|error| match error {
+        gltf::Error::Io(error) => Error::new(error.kind(), format!("{:?}: {}", path, error)),
+        error => invalid(error.to_string()),
+    })?;
+    let scene = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+        .ok_or_else(|| invalid("no scene".to_string()))?;
+
+    let mut loader = Loader {
+        buffers: &buffers,
+        images: &images,
+        materials: HashMap::new(),
+        world: HittableList::new(),
+        bounds: Aabb::empty(),
+        camera: None,
+    };
+    for node in scene.nodes() {
+        loader.visit(&node, &IDENTITY);
+    }
+
+    let camera = match loader.camera {
+        Some(camera) => camera,
+        None => frame_bounds(&loader.bounds),
+    };
+    Ok(Preset {
+        world: loader.world,
+        camera,
+    })
+}
+
+struct Loader<'a> {
+    buffers: &'a [gltf::buffer::Data],
+    images: &'a [gltf::image::Data],
+    materials: HashMap<Option<usize>, Rc<dyn Material>>, // By glTF material index, None for the default
+    world: HittableList,
+    bounds: Aabb,
+    camera: Option<CameraSettings>,
+}
+
+impl Loader<'_> {
+    fn visit(&mut self, node: &gltf::Node, parent: &Matrix) {
+        let local = node.transform().matrix().map(|column| column.map(f64::from));
+        let transform = multiply(parent, &local);
+
+        if let Some(mesh) = node.mesh() {
+            for primitive in mesh.primitives() {
+                self.add_primitive(&primitive, &transform);
+            }
+        }
+        if let (None, Some(camera)) = (&self.camera, node.camera()) {
+            if let Projection::Perspective(perspective) = camera.projection() {
+                // glTF cameras look down their local -Z axis with +Y up
+                let lookfrom = transform_point(&transform, [0.0, 0.0, 0.0]);
+                let forward = transform_direction(&transform, [0.0, 0.0, -1.0]).unit_vector();
+                let vup = transform_direction(&transform, [0.0, 1.0, 0.0]).unit_vector();
+                self.camera = Some(CameraSettings {
+                    aspect_ratio: perspective.aspect_ratio().map_or(16.0 / 9.0, f64::from),
+                    fov: f64::from(perspective.yfov()).to_degrees(),
+                    lookfrom,
+                    lookat: lookfrom + forward,
+                    vup,
+                    ..CameraSettings::default()
+                });
+            }
+        }
+        for child in node.children() {
+            self.visit(&child, &transform);
+        }
+    }
+
+    fn add_primitive(&mut self, primitive: &gltf::Primitive, transform: &Matrix) {
+        if primitive.mode() != Mode::Triangles {
+            return;
+        }
+        let reader = primitive.reader(|buffer| self.buffers.get(buffer.index()).map(|data| &data.0[..]));
+        let Some(positions) = reader.read_positions() else {
+            return;
+        };
+        let positions: Vec<Point3> = positions.map(|p| transform_point(transform, p.map(f64::from))).collect();
+
+        // Normals take the inverse transpose, the cofactor matrix is the same up to scale
+        let normal_matrix = cofactor(transform);
+        let normals = reader
+            .read_normals()
+            .map(|normals| normals.map(|n| transform_direction(&normal_matrix, n.map(f64::from)).unit_vector()).collect());
+
+        // glTF puts v = 0 at the top of the image, textures here put it at the bottom
+        let material = primitive.material();
+        let read_uvs = |set: u32| -> Option<Vec<(f64, f64)>> {
+            let uvs = reader.read_tex_coords(set)?;
+            Some(uvs.into_f32().map(|[u, v]| (f64::from(u), 1.0 - f64::from(v))).collect())
+        };
+        // The normal texture may use a coordinate set of its own
+        let base_set = material.pbr_metallic_roughness().base_color_texture().map(|info| info.tex_coord());
+        let normal_set = material.normal_texture().map(|normal| normal.tex_coord());
+        let uv_set = base_set.or(normal_set).unwrap_or(0);
+        let uvs = read_uvs(uv_set);
+        let normal_uvs = normal_set.filter(|&set| set != uv_set).and_then(read_uvs);
+
+        let indices: Vec<usize> = match reader.read_indices() {
+            Some(indices) => indices.into_u32().map(|index| index as usize).collect(),
+            None => (0..positions.len()).collect(),
+        };
+        if indices.iter().any(|&index| index >= positions.len()) {
+            eprintln!("Skipping mesh primitive with out of range vertex indices");
+            return;
+        }
+        let short = |count: Option<usize>| count.is_some_and(|count| count != positions.len());
+        if short(normals.as_ref().map(Vec::len)) || short(uvs.as_ref().map(Vec::len)) || short(normal_uvs.as_ref().map(Vec::len)) {
+            eprintln!("Skipping mesh primitive with vertex attributes that don't match its positions");
+            return;
+        }
+        // A mirroring transform flips the winding, swap it back so front faces stay outside
+        let mirrored = determinant(transform) < 0.0;
+        let triangles: Vec<[usize; 3]> = indices
+            .chunks_exact(3)
+            .map(|triangle| if mirrored { [triangle[0], triangle[2], triangle[1]] } else { [triangle[0], triangle[1], triangle[2]] })
+            .collect();
+
+        let material = self.material(&material);
+        let mut mesh = Mesh::new(positions, normals, uvs, triangles, material);
+        if let Some(normal_uvs) = normal_uvs {
+            mesh = mesh.with_normal_uvs(normal_uvs);
+        }
+        self.bounds.merge(&mesh.bounds());
+        self.world.add(Rc::new(mesh));
+    }
+
+    fn material(&mut self, material: &gltf::Material) -> Rc<dyn Material> {
+        if let Some(existing) = self.materials.get(&material.index()) {
+            return Rc::clone(existing);
+        }
+        let pbr = material.pbr_metallic_roughness();
+        let [r, g, b, _] = pbr.base_color_factor().map(f64::from);
+        let base_color = Color::new_rgb(r, g, b);
+        let emissive = material.emissive_factor().map(|channel| f64::from(channel * material.emissive_strength().unwrap_or(1.0)));
+
+        let converted = if emissive.iter().any(|&channel| channel > 0.0) {
+            new_material_diffuse_light_color(Color::new_rgb(emissive[0], emissive[1], emissive[2]))
+        } else {
+            let parameters = PrincipledParameters {
+                metallic: f64::from(pbr.metallic_factor()),
+                roughness: f64::from(pbr.roughness_factor()),
+                transmission: material.transmission().map_or(0.0, |transmission| f64::from(transmission.transmission_factor())),
+                ior: material.ior().map_or(1.5, f64::from),
+                ..PrincipledParameters::default()
+            };
+            let texture = pbr.base_color_texture().and_then(|info| {
+                let data = self.images.get(info.texture().source().index())?;
+                Some(sampled_texture(convert_image(data, base_color, true)?, &info.texture()))
+            });
+            let principled = match texture {
+                Some(texture) => new_material_principled_texture(Rc::new(texture), parameters),
+                None => new_material_principled(base_color, parameters),
+            };
+            // Normal textures hold tangent space normals, stored linearly
+            let normal_map = material.normal_texture().and_then(|normal| {
+                let data = self.images.get(normal.texture().source().index())?;
+                let image = convert_image(data, Color::new_rgb(1.0, 1.0, 1.0), false)?;
+                Some((sampled_texture(image, &normal.texture()), f64::from(normal.scale())))
+            });
+            match normal_map {
+                Some((texture, scale)) => Rc::new(NormalMap::new(principled, Rc::new(texture)).with_strength(scale)),
+                None => principled,
+            }
+        };
+        self.materials.insert(material.index(), Rc::clone(&converted));
+        converted
+    }
+}
+
+// The image wrapped the way the texture's sampler asks, repeating unless it says otherwise.
+fn sampled_texture(image: Image, texture: &gltf::Texture) -> ImageTexture {
+    let wrap_mode = |mode| match mode {
+        WrappingMode::ClampToEdge => WrapMode::Clamp,
+        WrappingMode::MirroredRepeat => WrapMode::MirroredRepeat,
+        WrappingMode::Repeat => WrapMode::Repeat,
+    };
+    let sampler = texture.sampler();
+    ImageTexture::new(image).with_wrap(wrap_mode(sampler.wrap_s()), wrap_mode(sampler.wrap_t()))
+}
+
+// Decodes texels (from sRGB when `srgb`) to linear color scaled by `factor`. None for formats that
+// aren't 8 or 16 bit color or greyscale.
+fn convert_image(data: &gltf::image::Data, factor: Color, srgb: bool) -> Option<Image> {
+    let (channels, bytes_per_channel) = match data.format {
+        Format::R8 => (1, 1),
+        Format::R8G8 => (2, 1),
+        Format::R8G8B8 => (3, 1),
+        Format::R8G8B8A8 => (4, 1),
+        Format::R16 => (1, 2),
+        Format::R16G16 => (2, 2),
+        Format::R16G16B16 => (3, 2),
+        Format::R16G16B16A16 => (4, 2),
+        _ => return None,
+    };
+    let channel = |texel: &[u8], index: usize| -> f64 {
+        let value = match bytes_per_channel {
+            1 => f64::from(texel[index]) / 255.0,
+            _ => f64::from(u16::from_le_bytes([texel[2 * index], texel[2 * index + 1]])) / 65535.0,
+        };
+        if srgb {
+            srgb_to_linear(value)
+        } else {
+            value
+        }
+    };
+    let pixels = data
+        .pixels
+        .chunks_exact(channels * bytes_per_channel)
+        .map(|texel| {
+            let (r, g, b) = if channels < 3 {
+                let grey = channel(texel, 0);
+                (grey, grey, grey)
+            } else {
+                (channel(texel, 0), channel(texel, 1), channel(texel, 2))
+            };
+            RtVec3::new(r * factor.r, g * factor.g, b * factor.b)
+        })
+        .collect();
+    Some(Image::new(data.width, data.height, pixels))
+}
+
+fn srgb_to_linear(value: f64) -> f64 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+// Looks at the model's bounding sphere from the front (+Z), a little above.
+fn frame_bounds(bounds: &Aabb) -> CameraSettings {
+    if bounds.min.x > bounds.max.x {
+        return CameraSettings::default();
+    }
+    let fov: f64 = 40.0;
+    let centre = bounds.centre();
+    let radius = (0.5 * (bounds.max - bounds.min).length()).max(1e-3);
+    let distance = radius / (0.5 * fov).to_radians().sin();
+    CameraSettings {
+        fov,
+        lookfrom: centre + distance * RtVec3::new(0.0, 0.25, 1.0).unit_vector(),
+        lookat: centre,
+        focus_dist: distance,
+        ..CameraSettings::default()
+    }
+}
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut product = [[0.0; 4]; 4];
+    for (column, product_column) in product.iter_mut().enumerate() {
+        for (row, value) in product_column.iter_mut().enumerate() {
+            *value = (0..4).map(|k| a[k][row] * b[column][k]).sum();
+        }
+    }
+    product
+}
+
+fn transform_point(m: &Matrix, p: [f64; 3]) -> Point3 {
+    let row = |r: usize| m[0][r] * p[0] + m[1][r] * p[1] + m[2][r] * p[2] + m[3][r];
+    Point3::new(row(0), row(1), row(2))
+}
+
+fn transform_direction(m: &Matrix, d: [f64; 3]) -> RtVec3 {
+    let row = |r: usize| m[0][r] * d[0] + m[1][r] * d[1] + m[2][r] * d[2];
+    RtVec3::new(row(0), row(1), row(2))
+}
+
+fn determinant(m: &Matrix) -> f64 {
+    m[0][0] * (m[1][1] * m[2][2] - m[2][1] * m[1][2]) - m[1][0] * (m[0][1] * m[2][2] - m[2][1] * m[0][2])
+        + m[2][0] * (m[0][1] * m[1][2] - m[1][1] * m[0][2])
+}
+
+// Cofactor matrix of the upper 3x3 block, each column the cross product of the other two.
+fn cofactor(m: &Matrix) -> Matrix {
+    let column = |c: usize| RtVec3::new(m[c][0], m[c][1], m[c][2]);
+    let as_column = |v: RtVec3| [v.x, v.y, v.z, 0.0];
+    [
+        as_column(column(1).cross(&column(2))),
+        as_column(column(2).cross(&column(0))),
+        as_column(column(0).cross(&column(1))),
+        [0.0, 0.0, 0.0, 1.0],
+    ]
+}
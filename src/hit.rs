@@ -1,162 +1,469 @@
-use crate::Interval;
-
-use crate::material::Material;
-use crate::material::default_material_lambertian;
-
-use crate::ray::Ray;
-
-use crate::rtvec3::{Point3, RtVec3};
-
-use std::rc::Rc;
-
-// Hit Record 
-#[derive(Clone)]
-pub struct HitRecord {
-    pub p: Point3,
-    pub normal: RtVec3,
-    pub t: f64,
-    pub front_face: bool,
-    pub material: Rc<dyn Material>,
-}
-
-impl HitRecord {
-    pub fn new(p: Point3, normal: RtVec3, t: f64, front_face: bool, material: Rc<dyn Material>) -> Self {
-        HitRecord {
-            p,
-            normal,
-            t,
-            front_face,
-            material,
-        }
-    }
-
-    pub fn set_face_normal (
-        &mut self,
-        ray: &Ray, 
-        outward_normal: RtVec3,
-    ) {
-        self.front_face = ray.direction().dot(&outward_normal) < 0.0;
-        self.normal = if self.front_face {
-            outward_normal
-        } else {
-            -outward_normal
-        };
-    }
-}
-
-// Hittables & Container 
-
-#[derive(Clone)]
-pub struct HittableList {
-    objects: Vec<Rc<dyn Hittable>>,
-}
-
-impl HittableList {
-    pub fn new() -> Self {
-        HittableList {
-            objects: Vec::new(),
-        }
-    }
-
-    pub fn with_object(object: Rc<dyn Hittable>) -> Self {
-        let mut list = HittableList::new();
-        list.add(object);
-        list
-    }
-
-    pub fn add(&mut self, object: Rc<dyn Hittable>) {
-        self.objects.push(object);
-    }
-
-    pub fn clear(&mut self) {
-        self.objects.clear();
-    }
-}
-
-pub trait Hittable {
-    fn hit(
-        &self, 
-        ray: &Ray,
-        interval: Interval,
-        record: &mut HitRecord,
-    ) -> bool;
-}
-
-impl Hittable for HittableList {
-    fn hit(
-        &self, 
-        ray: &Ray,
-        interval: Interval,
-        record: &mut HitRecord,
-    ) -> bool {
-        let default_material = default_material_lambertian();
-        let mut temp_record = HitRecord::new(Point3::new(0.0, 0.0, 0.0), RtVec3::new(0.0, 0.0, 0.0), 0.0, false, Rc::clone(&default_material));
-        let mut hit_anything: bool = false;
-        let mut closest_so_far = interval.max;
-
-        for object in &self.objects {
-            if object.hit(ray, Interval::new(interval.min, closest_so_far), &mut temp_record) {
-                hit_anything = true;
-                closest_so_far = temp_record.t;
-                *record = temp_record.clone();
-            }
-        }
-
-        hit_anything
-    }
-}
-
-// Geometry: Sphere
-#[derive(Clone)]
-pub struct Sphere {
-    pub center: Point3,
-    pub radius: f64,
-    pub material: Rc<dyn Material>,
-}
-
-impl Sphere {
-    pub fn new(center: Point3, radius: f64, material: Rc<dyn Material>) -> Sphere {
-        Sphere {
-            center,
-            radius,
-            material,
-        }
-    }
-}
-
-impl Hittable for Sphere {
-    fn hit(
-        &self, 
-        ray: &Ray,
-        interval: Interval,
-        record: &mut HitRecord,
-    ) -> bool {
-        let oc = ray.origin() - self.center;
-        let a = ray.direction().length_squared();
-        let half_b = ray.direction().dot(&oc);
-        let c = oc.length_squared() - self.radius * self.radius;
-
-        let discriminant = half_b * half_b - a * c;
-        if discriminant < 0.0 {
-            return false;
-        } 
-        let sqrtd = discriminant.sqrt();
-
-        // Find the nearest root that lies in the acceptable range.
-        let mut root = (-half_b - sqrtd) / a;
-        if !interval.surrounds(root) {
-            root = (-half_b + sqrtd) / a;
-            if !interval.surrounds(root) {
-                return false;
-            }
-        }
-
-        record.t = root;
-        record.p = ray.at(record.t);
-        record.normal = (record.p - self.center) / self.radius;
-        record.set_face_normal(ray, record.normal);
-        record.material = Rc::clone(&self.material);
-        
-        true
-    }
-}
\ No newline at end of file
+use crate::Interval;
+
+use crate::material::Material;
+use crate::material::default_material_lambertian;
+
+use crate::ray::{Color, Ray};
+
+use crate::rtvec3::{Point3, RtVec3};
+
+use crate::scene::{MaterialRegistry, ObjectDescription};
+
+use std::rc::Rc;
+
+// Hit Record 
+#[derive(Clone)]
+pub struct HitRecord {
+    pub p: Point3,
+    pub normal: RtVec3,
+    pub t: f64,
+    pub front_face: bool,
+    pub material: Rc<dyn Material>,
+    pub object_id: usize,
+    // Surface coordinates of the hit point, for texture lookups
+    pub u: f64,
+    pub v: f64,
+    // Coordinates for the normal map on surfaces that give it a set of its own
+    pub normal_uv: Option<(f64, f64)>,
+    // Interpolated color of meshes with per vertex colors, tints diffuse surfaces
+    pub vertex_color: Option<Color>,
+    // Direction of increasing u along the surface, orients anisotropic materials. Zero when the
+    // surface has none.
+    pub tangent: RtVec3,
+    // Direction of increasing v, together with the tangent and normal it orients normal maps
+    pub bitangent: RtVec3,
+    // World distance covered per unit of u and of v, along the tangent and bitangent. Zero when
+    // the surface doesn't say.
+    pub uv_lengths: (f64, f64),
+    // Normal of the actual surface on the ray's side. `normal` is the shading normal, which
+    // smooth meshes and normal maps bend away from it.
+    pub geometric_normal: RtVec3,
+}
+
+impl HitRecord {
+    pub fn new(p: Point3, normal: RtVec3, t: f64, front_face: bool, material: Rc<dyn Material>) -> Self {
+        HitRecord {
+            p,
+            normal,
+            t,
+            front_face,
+            material,
+            object_id: 0,
+            u: 0.0,
+            v: 0.0,
+            normal_uv: None,
+            vertex_color: None,
+            tangent: RtVec3::new(0.0, 0.0, 0.0),
+            bitangent: RtVec3::new(0.0, 0.0, 0.0),
+            uv_lengths: (0.0, 0.0),
+            geometric_normal: normal,
+        }
+    }
+
+    pub fn set_face_normal (
+        &mut self,
+        ray: &Ray, 
+        outward_normal: RtVec3,
+    ) {
+        self.front_face = ray.direction().dot(&outward_normal) < 0.0;
+        self.normal = if self.front_face {
+            outward_normal
+        } else {
+            -outward_normal
+        };
+        self.geometric_normal = self.normal;
+    }
+}
+
+// Hittables & Container 
+
+#[derive(Clone)]
+pub struct HittableList {
+    objects: Vec<Rc<dyn Hittable>>,
+}
+
+impl Default for HittableList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HittableList {
+    pub fn new() -> Self {
+        HittableList {
+            objects: Vec::new(),
+        }
+    }
+
+    pub fn with_object(object: Rc<dyn Hittable>) -> Self {
+        let mut list = HittableList::new();
+        list.add(object);
+        list
+    }
+
+    pub fn add(&mut self, object: Rc<dyn Hittable>) {
+        self.objects.push(object);
+    }
+
+    pub fn clear(&mut self) {
+        self.objects.clear();
+    }
+
+    pub fn objects(&self) -> &[Rc<dyn Hittable>] {
+        &self.objects
+    }
+}
+
+pub trait Hittable {
+    fn hit(
+        &self, 
+        ray: &Ray,
+        interval: Interval,
+        record: &mut HitRecord,
+    ) -> bool;
+
+    // Appends the materials this object uses, in scene order.
+    fn collect_materials(&self, _materials: &mut Vec<Rc<dyn Material>>) {}
+
+    // Scene file entries for this object, naming its materials through `materials`.
+    // `None` if the object (or one of its materials) can't be written out.
+    fn describe(&self, _materials: &mut MaterialRegistry) -> Option<Vec<ObjectDescription>> {
+        None
+    }
+}
+
+impl Hittable for HittableList {
+    fn hit(
+        &self, 
+        ray: &Ray,
+        interval: Interval,
+        record: &mut HitRecord,
+    ) -> bool {
+        let default_material = default_material_lambertian();
+        let mut temp_record = HitRecord::new(Point3::new(0.0, 0.0, 0.0), RtVec3::new(0.0, 0.0, 0.0), 0.0, false, Rc::clone(&default_material));
+        let mut hit_anything: bool = false;
+        let mut closest_so_far = interval.max;
+
+        for (object_id, object) in self.objects.iter().enumerate() {
+            // Only meshes set these, don't carry them over from an earlier hit
+            temp_record.vertex_color = None;
+            temp_record.normal_uv = None;
+            if object.hit(ray, Interval::new(interval.min, closest_so_far), &mut temp_record) {
+                hit_anything = true;
+                closest_so_far = temp_record.t;
+                temp_record.object_id = object_id;
+                *record = temp_record.clone();
+            }
+        }
+
+        hit_anything
+    }
+
+    fn collect_materials(&self, materials: &mut Vec<Rc<dyn Material>>) {
+        for object in &self.objects {
+            object.collect_materials(materials);
+        }
+    }
+
+    fn describe(&self, materials: &mut MaterialRegistry) -> Option<Vec<ObjectDescription>> {
+        let mut objects = Vec::new();
+        for object in &self.objects {
+            objects.extend(object.describe(materials)?);
+        }
+        Some(objects)
+    }
+}
+
+// Geometry: Sphere
+#[derive(Clone)]
+pub struct Sphere {
+    pub center: Point3,
+    pub radius: f64,
+    pub material: Rc<dyn Material>,
+}
+
+impl Sphere {
+    pub fn new(center: Point3, radius: f64, material: Rc<dyn Material>) -> Sphere {
+        Sphere {
+            center,
+            radius,
+            material,
+        }
+    }
+
+    // Texture coordinates of a point on the unit sphere: u is the angle around the y axis
+    // starting from -x, v runs from the bottom pole (0) to the top (1).
+    pub fn uv(p: &Point3) -> (f64, f64) {
+        let theta = f64::acos(-p.y);
+        let phi = f64::atan2(-p.z, p.x) + std::f64::consts::PI;
+        (phi / (2.0 * std::f64::consts::PI), theta / std::f64::consts::PI)
+    }
+}
+
+impl Hittable for Sphere {
+    fn hit(
+        &self, 
+        ray: &Ray,
+        interval: Interval,
+        record: &mut HitRecord,
+    ) -> bool {
+        let oc = ray.origin() - self.center;
+        let a = ray.direction().length_squared();
+        let half_b = ray.direction().dot(&oc);
+        let c = oc.length_squared() - self.radius * self.radius;
+
+        let discriminant = half_b * half_b - a * c;
+        if discriminant < 0.0 {
+            return false;
+        } 
+        let sqrtd = discriminant.sqrt();
+
+        // Find the nearest root that lies in the acceptable range.
+        let mut root = (-half_b - sqrtd) / a;
+        if !interval.surrounds(root) {
+            root = (-half_b + sqrtd) / a;
+            if !interval.surrounds(root) {
+                return false;
+            }
+        }
+
+        record.t = root;
+        record.p = ray.at(record.t);
+        let outward_normal = (record.p - self.center) / self.radius;
+        record.set_face_normal(ray, outward_normal);
+        (record.u, record.v) = Sphere::uv(&outward_normal);
+        record.tangent = RtVec3::new(outward_normal.z, 0.0, -outward_normal.x); // Around the y axis, zero at the poles
+        record.bitangent = outward_normal.cross(&record.tangent);                // Towards the north pole
+        // u runs once around the circle of latitude, v from pole to pole
+        let half_turn = std::f64::consts::PI * self.radius;
+        record.uv_lengths = (2.0 * half_turn * record.tangent.length(), half_turn);
+        record.material = Rc::clone(&self.material);
+        
+        true
+    }
+
+    fn collect_materials(&self, materials: &mut Vec<Rc<dyn Material>>) {
+        materials.push(Rc::clone(&self.material));
+    }
+
+    fn describe(&self, materials: &mut MaterialRegistry) -> Option<Vec<ObjectDescription>> {
+        Some(vec![ObjectDescription::Sphere {
+            center: self.center,
+            radius: self.radius,
+            material: materials.name(&self.material)?,
+        }])
+    }
+}
+
+// Geometry: Quad
+// Parallelogram with corner q and edges u and v.
+#[derive(Clone)]
+pub struct Quad {
+    pub q: Point3,
+    pub u: RtVec3,
+    pub v: RtVec3,
+    pub material: Rc<dyn Material>,
+    normal: RtVec3,
+    d: f64,
+    w: RtVec3,
+}
+
+impl Quad {
+    pub fn new(q: Point3, u: RtVec3, v: RtVec3, material: Rc<dyn Material>) -> Quad {
+        let n = u.cross(&v);
+        let normal = n.unit_vector();
+        Quad {
+            q,
+            u,
+            v,
+            material,
+            normal,
+            d: normal.dot(&q),
+            w: n / n.dot(&n),
+        }
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(
+        &self,
+        ray: &Ray,
+        interval: Interval,
+        record: &mut HitRecord,
+    ) -> bool {
+        let denominator = self.normal.dot(&ray.direction());
+        // Parallel to the plane
+        if denominator.abs() < 1e-8 {
+            return false;
+        }
+        let t = (self.d - self.normal.dot(&ray.origin())) / denominator;
+        if !interval.contains(t) {
+            return false;
+        }
+
+        // Position on the plane in terms of the edges, inside when both are in [0, 1]
+        let p = ray.at(t);
+        let planar = p - self.q;
+        let alpha = self.w.dot(&planar.cross(&self.v));
+        let beta = self.w.dot(&self.u.cross(&planar));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return false;
+        }
+
+        record.t = t;
+        record.p = p;
+        record.u = alpha;
+        record.v = beta;
+        record.tangent = self.u.unit_vector();
+        record.bitangent = self.v.unit_vector();
+        record.uv_lengths = (self.u.length(), self.v.length());
+        record.set_face_normal(ray, self.normal);
+        record.material = Rc::clone(&self.material);
+        true
+    }
+
+    fn collect_materials(&self, materials: &mut Vec<Rc<dyn Material>>) {
+        materials.push(Rc::clone(&self.material));
+    }
+
+    fn describe(&self, materials: &mut MaterialRegistry) -> Option<Vec<ObjectDescription>> {
+        Some(vec![ObjectDescription::Quad { q: self.q, u: self.u, v: self.v, material: materials.name(&self.material)? }])
+    }
+}
+
+// Closed box with opposite corners a and b, made of six quads.
+pub fn make_box(a: Point3, b: Point3, material: Rc<dyn Material>) -> HittableList {
+    let min = Point3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+    let max = Point3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z));
+    let dx = RtVec3::new(max.x - min.x, 0.0, 0.0);
+    let dy = RtVec3::new(0.0, max.y - min.y, 0.0);
+    let dz = RtVec3::new(0.0, 0.0, max.z - min.z);
+
+    let mut sides = HittableList::new();
+    sides.add(Rc::new(Quad::new(Point3::new(min.x, min.y, max.z), dx, dy, Rc::clone(&material))));  // Front
+    sides.add(Rc::new(Quad::new(Point3::new(max.x, min.y, max.z), -dz, dy, Rc::clone(&material)))); // Right
+    sides.add(Rc::new(Quad::new(Point3::new(max.x, min.y, min.z), -dx, dy, Rc::clone(&material)))); // Back
+    sides.add(Rc::new(Quad::new(Point3::new(min.x, min.y, min.z), dz, dy, Rc::clone(&material))));  // Left
+    sides.add(Rc::new(Quad::new(Point3::new(min.x, max.y, max.z), dx, -dz, Rc::clone(&material)))); // Top
+    sides.add(Rc::new(Quad::new(Point3::new(min.x, min.y, min.z), dx, dz, material)));              // Bottom
+    sides
+}
+
+// Instances: move or turn an object without touching its geometry, by transforming the ray
+// into the object's space and the hit back out of it.
+pub struct Translate {
+    pub object: Rc<dyn Hittable>,
+    pub offset: RtVec3,
+}
+
+impl Translate {
+    pub fn new(object: Rc<dyn Hittable>, offset: RtVec3) -> Self {
+        Translate { object, offset }
+    }
+}
+
+impl Hittable for Translate {
+    fn hit(
+        &self,
+        ray: &Ray,
+        interval: Interval,
+        record: &mut HitRecord,
+    ) -> bool {
+        let moved = Ray::new(ray.origin() - self.offset, ray.direction());
+        if !self.object.hit(&moved, interval, record) {
+            return false;
+        }
+        record.p = record.p + self.offset;
+        true
+    }
+
+    fn collect_materials(&self, materials: &mut Vec<Rc<dyn Material>>) {
+        self.object.collect_materials(materials);
+    }
+
+    // A scene file instance turns before it moves, so a translation can wrap a rotation but
+    // not another translation.
+    fn describe(&self, materials: &mut MaterialRegistry) -> Option<Vec<ObjectDescription>> {
+        let zero = RtVec3::new(0.0, 0.0, 0.0);
+        self.object
+            .describe(materials)?
+            .into_iter()
+            .map(|object| match object {
+                ObjectDescription::Instance { object, rotate_y, translate } if translate == zero => {
+                    Some(ObjectDescription::Instance { object, rotate_y, translate: self.offset })
+                }
+                ObjectDescription::Instance { .. } => None,
+                shape => Some(ObjectDescription::Instance { object: Box::new(shape), rotate_y: 0.0, translate: self.offset }),
+            })
+            .collect()
+    }
+}
+
+// Rotation about the y axis by `angle` degrees.
+pub struct RotateY {
+    pub object: Rc<dyn Hittable>,
+    pub angle: f64,
+    sin_theta: f64,
+    cos_theta: f64,
+}
+
+impl RotateY {
+    pub fn new(object: Rc<dyn Hittable>, angle: f64) -> Self {
+        let radians = crate::degrees_to_radians(angle);
+        RotateY {
+            object,
+            angle,
+            sin_theta: radians.sin(),
+            cos_theta: radians.cos(),
+        }
+    }
+
+    // Rotates `v` by the instance angle, or back by it when `inverse` is set.
+    fn rotate(&self, v: RtVec3, inverse: bool) -> RtVec3 {
+        let sin_theta = if inverse { -self.sin_theta } else { self.sin_theta };
+        RtVec3::new(
+            self.cos_theta * v.x + sin_theta * v.z,
+            v.y,
+            -sin_theta * v.x + self.cos_theta * v.z,
+        )
+    }
+}
+
+impl Hittable for RotateY {
+    fn hit(
+        &self,
+        ray: &Ray,
+        interval: Interval,
+        record: &mut HitRecord,
+    ) -> bool {
+        let rotated = Ray::new(self.rotate(ray.origin(), true), self.rotate(ray.direction(), true));
+        if !self.object.hit(&rotated, interval, record) {
+            return false;
+        }
+        record.p = self.rotate(record.p, false);
+        record.normal = self.rotate(record.normal, false);
+        record.tangent = self.rotate(record.tangent, false);
+        record.bitangent = self.rotate(record.bitangent, false);
+        record.geometric_normal = self.rotate(record.geometric_normal, false);
+        true
+    }
+
+    fn collect_materials(&self, materials: &mut Vec<Rc<dyn Material>>) {
+        self.object.collect_materials(materials);
+    }
+
+    fn describe(&self, materials: &mut MaterialRegistry) -> Option<Vec<ObjectDescription>> {
+        self.object
+            .describe(materials)?
+            .into_iter()
+            .map(|object| match object {
+                ObjectDescription::Instance { .. } => None,
+                shape => Some(ObjectDescription::Instance {
+                    object: Box::new(shape),
+                    rotate_y: self.angle,
+                    translate: RtVec3::new(0.0, 0.0, 0.0),
+                }),
+            })
+            .collect()
+    }
+}
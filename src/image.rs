@@ -0,0 +1,144 @@
+use crate::rtvec3::RtVec3;
+
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+// File format of rendered images.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ImageFormat {
+    #[default]
+    Ppm,       // ASCII P3, tone mapped
+    BinaryPpm, // Binary P6, tone mapped, a fraction of the size
+    Pfm,       // 32 bit float radiance, before exposure and tone mapping
+}
+
+impl ImageFormat {
+    pub const ALL: [ImageFormat; 3] = [ImageFormat::Ppm, ImageFormat::BinaryPpm, ImageFormat::Pfm];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImageFormat::Ppm => "ppm",
+            ImageFormat::BinaryPpm => "ppm-binary",
+            ImageFormat::Pfm => "pfm",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ImageFormat> {
+        ImageFormat::ALL.into_iter().find(|format| format.name() == name)
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Ppm | ImageFormat::BinaryPpm => "ppm",
+            ImageFormat::Pfm => "pfm",
+        }
+    }
+}
+
+// Plain RGB raster loaded from disk, values normalized to [0, 1].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<RtVec3>,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32, pixels: Vec<RtVec3>) -> Self {
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn pixel(&self, i: u32, j: u32) -> RtVec3 {
+        self.pixels[(j * self.width + i) as usize]
+    }
+
+    // Reads ASCII or binary netpbm files: P2 / P5 greyscale and P3 / P6 color.
+    pub fn load_netpbm(path: &Path) -> std::io::Result<Self> {
+        let bytes = fs::read(path)?;
+        let invalid = |message: &str| Error::new(ErrorKind::InvalidData, format!("{:?}: {}", path, message));
+
+        // Header: magic, width, height, max value, separated by whitespace and # comments
+        let mut tokens: Vec<String> = Vec::new();
+        let mut position = 0;
+        while tokens.len() < 4 && position < bytes.len() {
+            match bytes[position] {
+                b'#' => {
+                    while position < bytes.len() && bytes[position] != b'\n' {
+                        position += 1;
+                    }
+                }
+                byte if byte.is_ascii_whitespace() => position += 1,
+                _ => {
+                    let start = position;
+                    while position < bytes.len() && !bytes[position].is_ascii_whitespace() {
+                        position += 1;
+                    }
+                    tokens.push(String::from_utf8_lossy(&bytes[start..position]).to_string());
+                }
+            }
+        }
+        if tokens.len() < 4 {
+            return Err(invalid("truncated header"));
+        }
+        let (channels, binary) = match tokens[0].as_str() {
+            "P2" => (1, false),
+            "P3" => (3, false),
+            "P5" => (1, true),
+            "P6" => (3, true),
+            _ => return Err(invalid("unsupported netpbm format")),
+        };
+        let parse = |token: &str| token.parse::<u32>().map_err(|_| invalid("malformed header"));
+        let width = parse(&tokens[1])?;
+        let height = parse(&tokens[2])?;
+        let max_value = parse(&tokens[3])?;
+        if max_value == 0 || max_value > 65535 {
+            return Err(invalid("max value out of range"));
+        }
+
+        // Checked, a hostile header can ask for more samples than fit in memory addresses
+        let count = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(channels))
+            .ok_or_else(|| invalid("image dimensions too large"))?;
+        let samples: Vec<u32> = if binary {
+            // A single whitespace byte separates the header from the raster
+            let data = bytes.get(position + 1..).ok_or_else(|| invalid("truncated pixel data"))?;
+            let bytes_per_sample = if max_value > 255 { 2 } else { 1 };
+            if count.checked_mul(bytes_per_sample).is_none_or(|needed| data.len() < needed) {
+                return Err(invalid("truncated pixel data"));
+            }
+            (0..count)
+                .map(|index| match bytes_per_sample {
+                    2 => u16::from_be_bytes([data[index * 2], data[index * 2 + 1]]) as u32,
+                    _ => data[index] as u32,
+                })
+                .collect()
+        } else {
+            let samples: Vec<u32> = String::from_utf8_lossy(&bytes[position..])
+                .split_whitespace()
+                .take(count)
+                .map(|token| token.parse::<u32>())
+                .collect::<Result<_, _>>()
+                .map_err(|_| invalid("malformed pixel data"))?;
+            if samples.len() < count {
+                return Err(invalid("truncated pixel data"));
+            }
+            samples
+        };
+
+        let scale = 1.0 / max_value as f64;
+        let pixels = samples
+            .chunks(channels)
+            .map(|pixel| match pixel {
+                [grey] => RtVec3::new(*grey as f64 * scale, *grey as f64 * scale, *grey as f64 * scale),
+                _ => RtVec3::new(pixel[0] as f64 * scale, pixel[1] as f64 * scale, pixel[2] as f64 * scale),
+            })
+            .collect();
+        Ok(Image::new(width, height, pixels))
+    }
+}
@@ -0,0 +1,104 @@
+use crate::degrees_to_radians;
+
+use crate::image::Image;
+
+use crate::random_float;
+
+use crate::rtvec3::RtVec3;
+
+use std::f64::consts::PI;
+use std::path::Path;
+
+// Shape of the lens opening. Out of focus highlights (bokeh) take this shape.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Aperture {
+    #[default]
+    Circular,
+    // Regular polygon formed by `blades` straight iris blades, rotated by `rotation` degrees
+    Polygon { blades: u32, rotation: f64 },
+    // Arbitrary shape from a greyscale image, brighter pixels let more light through
+    Mask(ApertureMask),
+}
+
+impl Aperture {
+    // Random point on the aperture in lens coordinates, scaled so the unit disk is the full
+    // defocus radius (z is always 0). Masks cover the square around that disk.
+    pub fn sample(&self) -> RtVec3 {
+        match self {
+            Aperture::Circular => RtVec3::random_in_unit_disk(),
+            Aperture::Polygon { blades, rotation } => {
+                let blades = (*blades).max(3);
+                // Pick one of the triangles fanning out from the centre, then a point inside it
+                let wedge = f64::min((random_float() * blades as f64).floor(), blades as f64 - 1.0);
+                let angle = |corner: f64| degrees_to_radians(*rotation) + 2.0 * PI * corner / blades as f64;
+                let a = RtVec3::new(f64::cos(angle(wedge)), f64::sin(angle(wedge)), 0.0);
+                let b = RtVec3::new(f64::cos(angle(wedge + 1.0)), f64::sin(angle(wedge + 1.0)), 0.0);
+                let (mut s, mut t) = (random_float(), random_float());
+                if s + t > 1.0 {
+                    s = 1.0 - s;
+                    t = 1.0 - t;
+                }
+                s * a + t * b
+            }
+            Aperture::Mask(mask) => mask.sample(),
+        }
+    }
+}
+
+// Aperture image stretched over the square around the unit disk, sampled in proportion to its brightness.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApertureMask {
+    width: u32,
+    height: u32,
+    cdf: Vec<f64>,
+}
+
+impl ApertureMask {
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let image = Image::load_netpbm(path)?;
+        Ok(ApertureMask::from_image(&image))
+    }
+
+    pub fn from_image(image: &Image) -> Self {
+        let mut cdf = Vec::with_capacity(image.pixels.len());
+        let mut total = 0.0;
+        for pixel in &image.pixels {
+            total += (pixel.x() + pixel.y() + pixel.z()) / 3.0;
+            cdf.push(total);
+        }
+        ApertureMask {
+            width: image.width,
+            height: image.height,
+            cdf,
+        }
+    }
+
+    pub fn sample(&self) -> RtVec3 {
+        let total = self.cdf.last().copied().unwrap_or(0.0);
+        if total <= 0.0 {
+            // Fully opaque mask, fall back to a pinhole
+            return RtVec3::new(0.0, 0.0, 0.0);
+        }
+        let target = random_float() * total;
+        let index = self.cdf.partition_point(|value| *value <= target).min(self.cdf.len() - 1);
+        let i = index as u32 % self.width;
+        let j = index as u32 / self.width;
+        let x = (i as f64 + random_float()) / self.width as f64;
+        let y = (j as f64 + random_float()) / self.height as f64;
+        // Image rows run top to bottom, lens v points up
+        RtVec3::new(2.0 * x - 1.0, 1.0 - 2.0 * y, 0.0)
+    }
+}
+
+// Optical (cat-eye) vignetting: towards the edge of the frame the lens barrel cuts off part of
+// the aperture, seen from the image as a second disk shifted away from the centre. `strength`
+// is how far that disk moves, in aperture radii, at the corners of the frame.
+// `image_position` is the pixel position in [-1, 1] on both axes. Returns false when the lens
+// point is blocked.
+pub fn passes_vignetting(lens_point: RtVec3, image_position: (f64, f64), strength: f64) -> bool {
+    if strength <= 0.0 {
+        return true;
+    }
+    let shift = RtVec3::new(image_position.0, image_position.1, 0.0) * (strength / f64::sqrt(2.0));
+    (lens_point - shift).length_squared() <= 1.0
+}
@@ -1,86 +1,159 @@
-pub mod camera;
-pub mod hit;
-pub mod material; 
-pub mod ray;
-pub mod rtvec3;
-
-// use hit::{Hittable, HittableList, HitRecord};
-// use ray::{Ray, Color};
-// use ray::{write_color_to_pixel, color};
-use rtvec3::{Point3, RtVec3};
-
-use rand::prelude::*;
-
-// Utility functions 
-pub fn degrees_to_radians(
-    degrees: f64,
-) -> f64 {
-    const PI: f64 = 3.14159265358979323846264338327950288_f64;
-    let res = degrees * PI / 180.0;
-    res
-}
-
-pub fn random_float() -> f64 {
-    rand::thread_rng().gen()
-}
-
-pub fn random_float_range(interval: Interval) -> f64 {
-    if interval.min.is_infinite() || interval.max.is_infinite() {
-        panic!("Cannot generate a random value for infinite intervals");
-    }
-    if interval.min >= interval.max {
-        panic!("Invalid interval: min must be less than max");
-    }
-    let mut rng = rand::thread_rng();
-    let y: f64 = rng.gen_range(interval.min..interval.max);
-    y
-}
-
-pub fn sample_square() -> RtVec3 {
-    RtVec3::new(random_float() - 0.5, random_float() - 0.5, 0.0)
-}
-
-#[derive(Clone)]
-pub struct Interval {
-    pub min: f64,
-    pub max: f64,
-}
-
-impl Interval {
-    // Custom New interval
-    pub fn new(min: f64, max: f64) -> Interval {
-        Interval {min, max}
-    }
-
-    pub fn new_empty() -> Interval {
-        Interval {
-            min: f64::INFINITY,
-            max: -f64::INFINITY,
-        }
-    }
-
-    pub fn new_universe() -> Interval {
-        Interval {
-            min: -f64::INFINITY,
-            max: f64::INFINITY,
-        }
-    }
-
-    pub fn size(&self) -> f64 {
-        self.max - self.min
-    }
-
-    pub fn contains(&self, x: f64) -> bool {
-        self.min <= x && x <= self.max
-    }
-
-    pub fn surrounds(&self, x: f64) -> bool {
-        self.min < x && x < self.max
-    }
-
-    pub fn clamp(&self, x:f64) -> f64 {
-        if x < self.min {return self.min};
-        if x > self.max {return self.max};
-        return x;
-    }
+pub mod animation;
+pub mod aov;
+pub mod camera;
+pub mod checkpoint;
+pub mod cli;
+pub mod crop;
+pub mod denoise;
+pub mod film;
+pub mod filter;
+pub mod gltf_import;
+pub mod hit;
+pub mod image;
+pub mod lens;
+pub mod material; 
+pub mod mesh;
+pub mod microfacet;
+pub mod perlin;
+pub mod ply;
+pub mod projection;
+pub mod ray;
+pub mod rtvec3;
+pub mod scene;
+pub mod scenes;
+pub mod spectrum;
+pub mod stereo;
+pub mod stl;
+pub mod texture;
+pub mod tonemap;
+
+// use hit::{Hittable, HittableList, HitRecord};
+// use ray::{Ray, Color};
+// use ray::{write_color_to_pixel, color};
+pub use ray::Ray;
+pub use rtvec3::{Point3, RtVec3};
+
+use rand::prelude::*;
+use rand_chacha::ChaCha12Rng;
+
+use std::cell::RefCell;
+
+// Random number generation
+//
+// Every random draw in the renderer goes through this per-thread generator, so a seeded
+// render is reproducible and its exact position in the random stream can be checkpointed.
+thread_local! {
+    static RNG: RefCell<ChaCha12Rng> = RefCell::new(ChaCha12Rng::from_entropy());
+}
+
+// Snapshot of the random generator, enough to continue the exact same stream later.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RngState {
+    pub seed: [u8; 32],
+    pub stream: u64,
+    pub word_pos: u128,
+}
+
+pub fn seed_rng(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = ChaCha12Rng::seed_from_u64(seed));
+}
+
+// Seeds the generator on one of its independent streams. Rendering gives every scanline its own
+// stream, so rows come out the same whichever thread renders them, and in whatever order.
+pub fn seed_rng_stream(seed: u64, stream: u64) {
+    RNG.with(|rng| {
+        let mut seeded = ChaCha12Rng::seed_from_u64(seed);
+        seeded.set_stream(stream);
+        *rng.borrow_mut() = seeded;
+    });
+}
+
+pub fn rng_state() -> RngState {
+    RNG.with(|rng| {
+        let rng = rng.borrow();
+        RngState {
+            seed: rng.get_seed(),
+            stream: rng.get_stream(),
+            word_pos: rng.get_word_pos(),
+        }
+    })
+}
+
+pub fn restore_rng_state(state: &RngState) {
+    RNG.with(|rng| {
+        let mut restored = ChaCha12Rng::from_seed(state.seed);
+        restored.set_stream(state.stream);
+        restored.set_word_pos(state.word_pos);
+        *rng.borrow_mut() = restored;
+    });
+}
+
+// Utility functions 
+pub fn degrees_to_radians(
+    degrees: f64,
+) -> f64 {
+    degrees * std::f64::consts::PI / 180.0
+}
+
+pub fn random_float() -> f64 {
+    RNG.with(|rng| rng.borrow_mut().gen())
+}
+
+pub fn random_float_range(interval: Interval) -> f64 {
+    if interval.min.is_infinite() || interval.max.is_infinite() {
+        panic!("Cannot generate a random value for infinite intervals");
+    }
+    if interval.min >= interval.max {
+        panic!("Invalid interval: min must be less than max");
+    }
+    RNG.with(|rng| rng.borrow_mut().gen_range(interval.min..interval.max))
+}
+
+pub fn sample_square() -> RtVec3 {
+    RtVec3::new(random_float() - 0.5, random_float() - 0.5, 0.0)
+}
+
+#[derive(Clone)]
+pub struct Interval {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Interval {
+    // Custom New interval
+    pub fn new(min: f64, max: f64) -> Interval {
+        Interval {min, max}
+    }
+
+    pub fn new_empty() -> Interval {
+        Interval {
+            min: f64::INFINITY,
+            max: -f64::INFINITY,
+        }
+    }
+
+    pub fn new_universe() -> Interval {
+        Interval {
+            min: -f64::INFINITY,
+            max: f64::INFINITY,
+        }
+    }
+
+    pub fn size(&self) -> f64 {
+        self.max - self.min
+    }
+
+    pub fn contains(&self, x: f64) -> bool {
+        self.min <= x && x <= self.max
+    }
+
+    pub fn surrounds(&self, x: f64) -> bool {
+        self.min < x && x < self.max
+    }
+
+    pub fn clamp(&self, x:f64) -> f64 {
+        if x < self.min {return self.min};
+        if x > self.max {return self.max};
+        x
+    }
 }
\ No newline at end of file
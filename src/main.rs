@@ -1,123 +1,97 @@
 use raytracing_in_a_weekend::camera::Camera;
-use raytracing_in_a_weekend::rtvec3::{Point3, RtVec3};
-use raytracing_in_a_weekend::hit::{HittableList, Sphere};
-use raytracing_in_a_weekend::ray::Color;
-use raytracing_in_a_weekend::material::{
-    default_material_lambertian,    new_material_lambertian,    new_material_lambertian_color,  new_material_lambertian_color_float, 
-    default_material_metal,         new_material_metal,         new_material_metal_color,       new_material_metal_color_float,
-    default_material_dielectric,    new_material_dielectric,    new_material_dielectric_color,  new_material_dielectric_color_float,
-};
-use::raytracing_in_a_weekend::Interval;
-use::raytracing_in_a_weekend::{random_float, random_float_range};
+use raytracing_in_a_weekend::cli::{parse_args, usage, Command, RenderOptions};
+use raytracing_in_a_weekend::gltf_import::load_gltf;
+use raytracing_in_a_weekend::scene::SceneDescription;
+use raytracing_in_a_weekend::scenes::{preset, PRESETS};
+use::raytracing_in_a_weekend::seed_rng;
 
-use std::rc::Rc;
+use std::env;
+use std::io;
+use std::path::Path;
+use std::process;
 
 fn main() {
-    // World Parameters
-    let mut world_objects: HittableList = HittableList::new();
-
-    // Image Parameters
-    let aspect_ratio: f64 = 16.0 / 9.0;                     // Output image aspect ratio
-    let image_width: u32 = 1920;
-    let samples_per_pixel: u32 = 500;                       // Antialiasing multiplier, rendered x times and then each pixel is averaged
-    let sample_bounce_max: u32 = 50;                        // How many times a ray can bounce
-
-    // Ray Parameters
-    let ray_color: Color = Color::new_rgb(0.0, 0.0, 0.0);   // Default Color is black
-
-    // Camera Parameters
-    let fov: f64 = 20.0;                                    // field of view, smaller zooms in
-    let lookfrom: Point3 = Point3::new(13.0, 2.0, 3.0);     // Point camera is looking from
-    let lookat: Point3 = Point3::new(0.0, 0.0, 0.0);       // Point camera is looking at
-    let vup: RtVec3 = RtVec3::new(0.0, 1.0, 0.0);           // Camera-relative "up" direction
-    let defocus_angle: f64 = 0.6;                           // Variation angle of rays through each pixel
-    let focus_dist: f64 = 10.0;                             // Distance from camera lookfrom point to plane of perfect focus
-
-    // Complex Implementation Demo
-    let material_ground = new_material_lambertian_color_float(0.5, 0.5, 0.5);
-    world_objects.add(Rc::new(Sphere::new(Point3::new(0.0, -1000.0, -1.0), 1000.0, Rc::clone(&material_ground))));
-    
-    for a in -11..11 {
-        for b in -11..11 {
-            let choose_mat: f64 = random_float();
-            let center: Point3 = Point3::new(a as f64 + 0.9 * random_float(), 0.2, b as f64 + 0.9 * random_float());
+    let options = match parse_args(env::args().skip(1), &PRESETS) {
+        Ok(Command::Render(options)) => options,
+        Ok(Command::Help) => {
+            print!("{}", usage(&PRESETS));
+            return;
+        }
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, usage(&PRESETS));
+            process::exit(2);
+        }
+    };
 
-            if (center - Point3::new(4.0, 0.2, 0.0)).length() > 0.9 {
-                if choose_mat < 0.8 {
-                    // diffuse
-                    let albedo = Color::random() * Color::random();
-                    let sphere_material = new_material_lambertian_color(albedo);
-                    world_objects.add(Rc::new(Sphere::new(center, 0.2, Rc::clone(&sphere_material))));
-                } else if choose_mat < 0.95 {
-                    // metal
-                    let albedo = Color::random_range(Interval::new(0.5, 1.0));
-                    let fuzz = random_float_range(Interval::new(0.0, 0.5));
-                    let sphere_material = new_material_metal_color(albedo, fuzz);
-                    world_objects.add(Rc::new(Sphere::new(center, 0.2, Rc::clone(&sphere_material))));
-                } else {
-                    // glass
-                    let sphere_material = new_material_dielectric(1.5);
-                    world_objects.add(Rc::new(Sphere::new(center, 0.2, Rc::clone(&sphere_material))));
-                }
+    // Scene files are read once, every render thread builds its world from the same description
+    let scene_file: Option<SceneDescription> = if options.scene.ends_with(".toml") {
+        match SceneDescription::load(Path::new(&options.scene)) {
+            Ok(scene) => Some(scene),
+            Err(error) => {
+                eprintln!("error: {}", error);
+                process::exit(1);
             }
         }
+    } else {
+        None
+    };
+    // Randomly generated scenes can be saved and re-rendered exactly with --scene <file>
+    if let Some(path) = &options.save_scene {
+        let scene = build_camera(&options, scene_file.as_ref()).and_then(|camera| SceneDescription::capture(&camera));
+        if let Err(error) = scene.and_then(|scene| scene.save(path)) {
+            eprintln!("error: {}", error);
+            process::exit(1);
+        }
+        println!("Scene saved to {:?}", path);
+        return;
     }
 
-    let material1 = new_material_dielectric(1.5);
-    world_objects.add(Rc::new(Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0, Rc::clone(&material1))));
-
-    let material2 = new_material_lambertian_color(Color::new_rgb(0.4, 0.2, 0.1));
-    world_objects.add(Rc::new(Sphere::new(Point3::new(-4.0, 1.0, 0.0), 1.0, Rc::clone(&material2))));
-
-    let material3 = new_material_metal_color_float(0.7, 0.6, 0.5, 0.0);
-    world_objects.add(Rc::new(Sphere::new(Point3::new(4.0, 1.0, 0.0), 1.0, Rc::clone(&material3))));
-
-    
-    // Dev Demo
-/*  
-    // Build Colors (albedo)
-    let albedo_silver = Color::new_rgb(0.8, 0.8, 0.8);
-    let albedo_gold = Color::new_rgb(0.8, 0.6, 0.2);
-    let albedo_blue_gray = Color::new_rgb(0.1, 0.2, 0.5);
-    let albedo_green_mountain_dew = Color::new_rgb(0.8, 0.8, 0.0);
+    // Render Parameters
+    let checkpoint_interval: u32 = 20;                      // Scanlines rendered between checkpoints
 
-    // Build materials, apply colors and other variables
-    let material_center   = new_material_lambertian_color(albedo_blue_gray);
-    let material_ground  = new_material_lambertian_color(albedo_green_mountain_dew);
-    let material_left = new_material_dielectric(1.5);
-    let material_bubble = new_material_dielectric(1.0 / 1.5);
-    let material_right = new_material_metal_color(albedo_gold, 0.0);
+    if let Some(path) = options.checkpoint.as_ref().filter(|path| path.exists()) {
+        println!("Resuming from {:?}", path);
+    }
+    // Checkpoints are written (and resumed from) whatever the thread count
+    // Models are loaded again by each render thread (worlds can't be shared)
+    let make_camera = || {
+        let camera = build_camera(&options, scene_file.as_ref())?;
+        Ok(match &options.checkpoint {
+            Some(path) => camera.with_checkpoint(path, checkpoint_interval),
+            None => camera,
+        })
+    };
+    if let Err(error) = Camera::render_threaded(make_camera, options.threads) {
+        eprintln!("error: {}", error);
+        process::exit(1);
+    }
+}
 
-    // Build spheres and assign materials
-    let sphere_ground = Rc::new(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, Rc::clone(&material_ground)));
-    let sphere_center = Rc::new(Sphere::new(Point3::new(0.0, 0.0, -1.2), 0.5, Rc::clone(&material_center)));
-    let sphere_left = Rc::new(Sphere::new(Point3::new(-1.0, 0.0, -1.0), 0.5, Rc::clone(&material_left)));
-    let sphere_bubble = Rc::new(Sphere::new(Point3::new(-1.0, 0.0, -1.0), 0.4, Rc::clone(&material_bubble)));
-    let sphere_right = Rc::new(Sphere::new(Point3::new(1.0, 0.0, -1.0), 0.5, Rc::clone(&material_right)));
-    
-    // Add the objects to the world
-    world_objects.add(sphere_ground);
-    world_objects.add(sphere_center);
-    world_objects.add(sphere_left);
-    world_objects.add(sphere_bubble);
-    world_objects.add(sphere_right);
-*/
+// Builds the scene and its camera. The seed is applied first, so every call (one per render
+// thread) builds exactly the same random scene.
+fn build_camera(options: &RenderOptions, scene_file: Option<&SceneDescription>) -> io::Result<Camera> {
+    // Image Parameters
+    let image_width: u32 = options.width;
+    let samples_per_pixel: u32 = options.samples_per_pixel; // Antialiasing multiplier, rendered x times and then each pixel is averaged
+    let sample_bounce_max: u32 = options.max_depth;         // How many times a ray can bounce
+    seed_rng(options.seed);                                 // Fixed seed, an interrupted render resumes against the same random scene
 
-    // Start the camera, passing in world and variables
-    let cam: Camera = Camera::new(
-        world_objects, 
-        ray_color, 
-        aspect_ratio, 
-        fov, 
-        defocus_angle,
-        focus_dist,
-        lookfrom, 
-        lookat, 
-        vup, 
-        image_width, 
-        samples_per_pixel, 
-        sample_bounce_max
-    );
+    let camera = match scene_file {
+        Some(scene) => scene.build_camera(image_width, samples_per_pixel, sample_bounce_max),
+        None if is_model(&options.scene) => load_gltf(Path::new(&options.scene))?
+            .build_camera(image_width, samples_per_pixel, sample_bounce_max),
+        // The command line only accepts preset names
+        None => preset(&options.scene)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("unknown scene preset \"{}\"", options.scene)))?
+            .build_camera(image_width, samples_per_pixel, sample_bounce_max),
+    };
+    Ok(camera
+        .with_seed(options.seed)
+        .with_output(&options.output)
+        .with_format(options.format)
+        .with_spectral(options.spectral))
+}
 
-    cam.render();
-}
\ No newline at end of file
+fn is_model(scene: &str) -> bool {
+    scene.ends_with(".gltf") || scene.ends_with(".glb")
+}
@@ -1,192 +1,1390 @@
-use crate::hit::HitRecord; 
-
-use crate::ray::{Ray, Color};
-
-use crate::rtvec3::RtVec3;
-
-use crate::random_float;
-
-use std::rc::Rc;
-
-pub trait Material {
-    fn scatter (
-        &self,
-        r_in: Ray, 
-        rec: HitRecord, 
-    ) -> Option<(Color, Ray)>;
-}
-
-// Lambertian Logic (Perfect diffusion)
-pub struct Lambertian {
-    pub albedo: Color,
-}
-
-impl Lambertian {
-    pub fn new(albedo: Color) -> Self {
-        Lambertian {albedo}
-    }
-}
-
-impl Material for Lambertian {
-    fn scatter(
-        &self,
-        _r_in: Ray, 
-        rec: HitRecord, 
-    ) -> Option<(Color, Ray)> {
-        let mut scatter_direction = rec.normal + RtVec3::random_unit_vector();
-        if scatter_direction.near_zero() {
-            scatter_direction = rec.normal;
-        }
-
-        let attenuation = self.albedo;
-        let scattered = Ray::new(rec.p, scatter_direction);
-
-        Some((attenuation, scattered))
-    }
-}
-
-// Metal Logic
-pub struct Metal {
-    pub albedo: Color,
-    pub fuzz: f64,
-}
-
-impl Metal {
-    pub fn new(albedo: Color, fuzz: f64) -> Self {
-        let fuzz = if fuzz < 1.0 { fuzz } else { 1.0 };
-        Metal {
-            albedo,
-            fuzz,
-        }
-    }
-}
-
-impl Material for Metal {
-    fn scatter(
-        &self,
-        r_in: Ray, 
-        rec: HitRecord, 
-    ) -> Option<(Color, Ray)> {
-        // Reflect Ray
-        let mut reflected = RtVec3::reflect(r_in.direction(), rec.normal);
-        // Apply fuzz 
-        reflected = reflected + self.fuzz * RtVec3::random_unit_vector();
-        
-        // Create the scattered Ray
-        let scattered = Ray::new(rec.p, reflected);
-        let attenuation = self.albedo;
-
-        // Only scatter if the dot product of the scattered direction and normal is positive
-        if scattered.direction().dot(&rec.normal) > 0.0 {
-            Some((attenuation, scattered))
-        } else {
-            None
-        }
-    }
-}
-
-// Metal Logic
-pub struct Dielectric {
-    pub albedo: Color,
-    pub refraction_index: f64,
-}
-
-impl Dielectric {
-    pub fn new(albedo: Color, refraction_index: f64) -> Self {
-        Dielectric {
-            albedo,
-            refraction_index,
-        }
-    }
-
-    pub fn reflectance(cosine: f64, refraction_index: f64) -> f64 {
-        // Schlick's approximation for reflectance.
-        let mut r0: f64 = (1.0 - refraction_index) / (1.0 + refraction_index);
-        r0 = r0 * r0;
-        r0 + (1.0 - r0) * (1.0 - cosine).powf(5.0)
-    }
-}
-
-impl Material for Dielectric {
-    fn scatter(
-        &self,
-        r_in: Ray, 
-        rec: HitRecord, 
-    ) -> Option<(Color, Ray)> {
-        let attenuation = self.albedo;
-        let ri: f64 = if rec.front_face {
-            1.0 / self.refraction_index
-        } else {
-            self.refraction_index
-        };
-
-        let unit_direction: RtVec3 = RtVec3::unit_vector(&r_in.direction());
-        let cos_theta: f64 = f64::min(-unit_direction.dot(&rec.normal), 1.0);
-        let sin_theta: f64 = f64::sqrt(1.0 - cos_theta * cos_theta);
-        let cannot_refract: bool = ri * sin_theta > 1.0;
-
-        let direction: RtVec3 = if cannot_refract || Dielectric::reflectance(cos_theta, ri) > random_float() {
-            RtVec3::reflect(unit_direction, rec.normal)
-        } else {
-            RtVec3::refract(unit_direction, rec.normal, ri)
-        };
-        let scattered = Ray::new(rec.p, direction);
-
-        Some((attenuation, scattered))
-    }
-}
-
-// Material Defaults
-
-    // Material: Lambertian
-pub fn default_material_lambertian() -> Rc<dyn Material> {
-    Rc::new(Lambertian::new(Color::new_rgb(0.5, 0.5, 0.5)))
-}
-
-pub fn new_material_lambertian() -> Rc<dyn Material> {
-    Rc::new(Lambertian::new(Color::new_rgb(0.5, 0.5, 0.5))) 
-}
-
-pub fn new_material_lambertian_color(color: Color) -> Rc<dyn Material> {
-    Rc::new(Lambertian::new(color))
-}
-
-pub fn new_material_lambertian_color_float(r: f64, g: f64, b: f64) -> Rc<dyn Material> {
-    Rc::new(Lambertian::new(Color::new_rgb(r, g, b)))
-}
-
-    // Material Metal
-pub fn default_material_metal() -> Rc<dyn Material> {
-    Rc::new(Metal::new(Color::new_rgb(0.5, 0.5, 0.5), 0.5))
-}
-
-pub fn new_material_metal(fuzz: f64) -> Rc<dyn Material> {
-    Rc::new(Metal::new(Color::new_rgb(0.5, 0.5, 0.5), fuzz))
-}
-
-pub fn new_material_metal_color(color: Color, fuzz: f64) -> Rc<dyn Material> {
-    Rc::new(Metal::new(Color::new_rgb(color.r, color.g, color.b), fuzz))
-}
-
-pub fn new_material_metal_color_float(r: f64, g: f64, b: f64, fuzz: f64) -> Rc<dyn Material> {
-    Rc::new(Metal::new(Color::new_rgb(r, g, b), fuzz))
-}
-
-    // Material Dielectric
-pub fn default_material_dielectric() -> Rc<dyn Material> { // Full refraction
-    Rc::new(Dielectric::new(Color::new_rgb(1.0, 1.0, 1.0), 1.5))
-}
-
-pub fn new_material_dielectric(refraction: f64) -> Rc<dyn Material> {
-    Rc::new(Dielectric::new(Color::new_rgb(1.0, 1.0, 1.0), refraction))
-}
-
-pub fn new_material_dielectric_color(color: Color, refraction: f64) -> Rc<dyn Material> {
-    Rc::new(Dielectric::new(color, refraction))
-}
-
-pub fn new_material_dielectric_color_float(r: f64, g: f64, b: f64, refraction: f64) -> Rc<dyn Material> {
-    Rc::new(Dielectric::new(Color::new_rgb(r, g, b), refraction))
-}
-
-  
\ No newline at end of file
+use crate::hit::HitRecord; 
+
+use crate::microfacet::{fresnel_conductor, fresnel_dielectric, fresnel_schlick, fresnel_thin_film, reflect, Frame, Ggx};
+
+use crate::ray::{Ray, Color};
+
+use crate::rtvec3::RtVec3;
+
+use crate::random_float;
+
+use crate::scene::MaterialDescription;
+
+use crate::spectrum::{Dispersion, SampledWavelengths, D_LINE, WAVELENGTH_SAMPLES};
+
+use crate::texture::{SolidColor, Texture};
+
+use std::rc::Rc;
+
+pub trait Material {
+    fn scatter (
+        &self,
+        r_in: Ray, 
+        rec: HitRecord, 
+    ) -> Option<(Color, Ray)>;
+
+    // Scatter along a path of the spectral renderer. Only materials that bend light differently
+    // per wavelength override it, following the hero wavelength and terminating the others.
+    fn scatter_spectral(&self, r_in: Ray, rec: HitRecord, _wavelengths: &mut SampledWavelengths) -> Option<(Color, Ray)> {
+        self.scatter(r_in, rec)
+    }
+
+    // Surface color used for the albedo output pass, neutral white unless a material overrides it.
+    fn albedo(&self, _rec: &HitRecord) -> Color {
+        Color::new_rgb(1.0, 1.0, 1.0)
+    }
+
+    // Light given off at the hit point, black for everything but lights.
+    fn emitted(&self, _rec: &HitRecord) -> Color {
+        Color::new_rgb(0.0, 0.0, 0.0)
+    }
+
+    // Parameters for saving the material to a scene file, `None` if it can't be written out.
+    fn describe(&self) -> Option<MaterialDescription> {
+        None
+    }
+}
+
+// Lambertian Logic (Perfect diffusion)
+pub struct Lambertian {
+    pub texture: Rc<dyn Texture>,
+}
+
+impl Lambertian {
+    pub fn new(albedo: Color) -> Self {
+        Lambertian::from_texture(Rc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn from_texture(texture: Rc<dyn Texture>) -> Self {
+        Lambertian {texture}
+    }
+}
+
+impl Material for Lambertian {
+    fn scatter(
+        &self,
+        _r_in: Ray, 
+        rec: HitRecord, 
+    ) -> Option<(Color, Ray)> {
+        let mut scatter_direction = rec.normal + RtVec3::random_unit_vector();
+        if scatter_direction.near_zero() {
+            scatter_direction = rec.normal;
+        }
+
+        let attenuation = self.albedo(&rec);
+        let scattered = Ray::new(rec.p, scatter_direction);
+
+        Some((attenuation, scattered))
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Color {
+        texture_albedo(&self.texture, rec)
+    }
+
+    fn describe(&self) -> Option<MaterialDescription> {
+        match self.texture.solid_color() {
+            Some(albedo) => Some(MaterialDescription::Lambertian { albedo }),
+            None => Some(MaterialDescription::LambertianTexture { texture: self.texture.describe()? }),
+        }
+    }
+}
+
+// The texture's color at the hit, tinted by the mesh's vertex color if it has one.
+fn texture_albedo(texture: &Rc<dyn Texture>, rec: &HitRecord) -> Color {
+    let albedo = texture.value(rec.u, rec.v, &rec.p);
+    match rec.vertex_color {
+        Some(tint) => albedo * tint,
+        None => albedo,
+    }
+}
+
+// Cosine distributed direction in the shading frame.
+fn sample_cosine_local() -> RtVec3 {
+    let direction = RtVec3::new(0.0, 0.0, 1.0) + RtVec3::random_unit_vector();
+    if direction.near_zero() {
+        RtVec3::new(0.0, 0.0, 1.0)
+    } else {
+        direction.unit_vector()
+    }
+}
+
+// Oren-Nayar Logic (rough diffuse)
+// Diffuse surface made of tiny V-shaped grooves with Lambertian faces, their slopes spread with
+// standard deviation `sigma` in degrees. Rough surfaces like clay, plaster and fabric look
+// flatter than Lambertian ones and brighter towards the light at grazing angles; sigma 0 is
+// exactly Lambertian.
+pub struct OrenNayar {
+    pub texture: Rc<dyn Texture>,
+    pub sigma: f64,
+}
+
+impl OrenNayar {
+    pub fn new(albedo: Color, sigma: f64) -> Self {
+        OrenNayar::from_texture(Rc::new(SolidColor::new(albedo)), sigma)
+    }
+
+    pub fn from_texture(texture: Rc<dyn Texture>, sigma: f64) -> Self {
+        OrenNayar { texture, sigma }
+    }
+
+    // BRDF times pi, relative to the Lambertian one of the same albedo (the qualitative model).
+    pub fn relative_reflectance(&self, wo: RtVec3, wi: RtVec3) -> f64 {
+        let sigma2 = self.sigma.to_radians().powi(2);
+        let a = 1.0 - sigma2 / (2.0 * (sigma2 + 0.33));
+        let b = 0.45 * sigma2 / (sigma2 + 0.09);
+        let sin_o = (1.0 - wo.z * wo.z).max(0.0).sqrt();
+        let sin_i = (1.0 - wi.z * wi.z).max(0.0).sqrt();
+        if sin_o < 1e-6 || sin_i < 1e-6 {
+            return a;
+        }
+        let cos_phi = ((wi.x * wo.x + wi.y * wo.y) / (sin_i * sin_o)).max(0.0);
+        // sin(alpha) tan(beta), alpha the larger of the two polar angles and beta the smaller
+        let (sin_alpha, tan_beta) = if wi.z.abs() > wo.z.abs() {
+            (sin_o, sin_i / wi.z.abs())
+        } else {
+            (sin_i, sin_o / wo.z.abs())
+        };
+        a + b * cos_phi * sin_alpha * tan_beta
+    }
+}
+
+impl Material for OrenNayar {
+    fn scatter(
+        &self,
+        r_in: Ray,
+        rec: HitRecord,
+    ) -> Option<(Color, Ray)> {
+        let frame = Frame::new(rec.normal, rec.tangent);
+        let wo = frame.to_local(-r_in.direction().unit_vector());
+        let wi = sample_cosine_local();
+        // Cosine sampling cancels the cosine and 1 / pi, leaving the relative reflectance
+        let weight = self.relative_reflectance(wo, wi);
+        Some((Color::from_vec(weight * self.albedo(&rec).to_vec()), Ray::new(rec.p, frame.to_world(wi))))
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Color {
+        texture_albedo(&self.texture, rec)
+    }
+
+    fn describe(&self) -> Option<MaterialDescription> {
+        Some(MaterialDescription::OrenNayar { albedo: self.texture.solid_color()?, sigma: self.sigma })
+    }
+}
+
+// Velvet Logic (diffuse with a cloth sheen)
+// Fibres standing up from the surface catch light at grazing angles, giving velvet, satin and
+// felt their bright rims. A diffuse base with a sheen lobe on top from the "Charlie" fibre
+// distribution (Estevez and Kulla), with `roughness` widening the sheen from a thin rim to a
+// soft glow. The sheen adds to the base, keep it moderate on bright bases.
+pub struct Velvet {
+    pub texture: Rc<dyn Texture>,
+    pub sheen: Color,
+    pub roughness: f64,
+}
+
+impl Velvet {
+    pub fn new(albedo: Color, sheen: Color, roughness: f64) -> Self {
+        Velvet::from_texture(Rc::new(SolidColor::new(albedo)), sheen, roughness)
+    }
+
+    pub fn from_texture(texture: Rc<dyn Texture>, sheen: Color, roughness: f64) -> Self {
+        Velvet {
+            texture,
+            sheen,
+            roughness,
+        }
+    }
+
+    // Sheen BRDF for unit sheen color: fibre distribution with Neubelt and Pettineo's visibility.
+    pub fn sheen_brdf(&self, wo: RtVec3, wi: RtVec3) -> f64 {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+        let alpha = self.roughness.clamp(0.07, 1.0).powi(2);
+        let h = (wo + wi).unit_vector();
+        let sin_h = (1.0 - h.z * h.z).max(0.0).sqrt();
+        let distribution = (2.0 + 1.0 / alpha) * sin_h.powf(1.0 / alpha) / (2.0 * std::f64::consts::PI);
+        let visibility = 1.0 / (4.0 * (wi.z + wo.z - wi.z * wo.z));
+        distribution * visibility
+    }
+}
+
+impl Material for Velvet {
+    fn scatter(
+        &self,
+        r_in: Ray,
+        rec: HitRecord,
+    ) -> Option<(Color, Ray)> {
+        let frame = Frame::new(rec.normal, rec.tangent);
+        let wo = frame.to_local(-r_in.direction().unit_vector());
+        let wi = sample_cosine_local();
+        let sheen = std::f64::consts::PI * self.sheen_brdf(wo, wi);
+        let attenuation = self.albedo(&rec).to_vec() + sheen * self.sheen.to_vec();
+        Some((Color::from_vec(attenuation), Ray::new(rec.p, frame.to_world(wi))))
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Color {
+        texture_albedo(&self.texture, rec)
+    }
+
+    fn describe(&self) -> Option<MaterialDescription> {
+        Some(MaterialDescription::Velvet { albedo: self.texture.solid_color()?, sheen: self.sheen, roughness: self.roughness })
+    }
+}
+
+// Metal Logic (GGX microfacet conductor)
+// Rough reflection from a Cook-Torrance surface: microfacet normals drawn from the visible GGX
+// distribution, Smith masking-shadowing and Fresnel reflectance, either tinted (Schlick, albedo
+// is the head-on color) or from a measured complex index of refraction.
+pub struct Metal {
+    pub reflectance: Reflectance,
+    pub roughness_u: f64, // Along the surface tangent
+    pub roughness_v: f64, // Across it, differing from roughness_u gives brushed metal
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Reflectance {
+    Tint(Color),
+    Conductor { eta: Color, k: Color },
+}
+
+impl Reflectance {
+    pub fn fresnel(&self, cos_theta: f64) -> Color {
+        match self {
+            Reflectance::Tint(albedo) => fresnel_schlick(cos_theta, *albedo),
+            Reflectance::Conductor { eta, k } => fresnel_conductor(cos_theta, *eta, *k),
+        }
+    }
+}
+
+impl Metal {
+    pub fn new(albedo: Color, roughness: f64) -> Self {
+        Metal {
+            reflectance: Reflectance::Tint(albedo),
+            roughness_u: roughness.clamp(0.0, 1.0),
+            roughness_v: roughness.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn conductor(eta: Color, k: Color, roughness: f64) -> Self {
+        Metal {
+            reflectance: Reflectance::Conductor { eta, k },
+            ..Metal::new(Color::new_rgb(1.0, 1.0, 1.0), roughness)
+        }
+    }
+
+    // Complex IOR at roughly 650, 550 and 450 nm
+    pub fn gold(roughness: f64) -> Self {
+        Metal::conductor(Color::new_rgb(0.143, 0.374, 1.442), Color::new_rgb(3.983, 2.385, 1.603), roughness)
+    }
+
+    pub fn copper(roughness: f64) -> Self {
+        Metal::conductor(Color::new_rgb(0.200, 0.924, 1.102), Color::new_rgb(3.912, 2.452, 2.142), roughness)
+    }
+
+    pub fn aluminium(roughness: f64) -> Self {
+        Metal::conductor(Color::new_rgb(1.657, 0.880, 0.521), Color::new_rgb(9.224, 6.270, 4.837), roughness)
+    }
+
+    pub fn with_anisotropy(mut self, roughness_u: f64, roughness_v: f64) -> Self {
+        self.roughness_u = roughness_u.clamp(0.0, 1.0);
+        self.roughness_v = roughness_v.clamp(0.0, 1.0);
+        self
+    }
+}
+
+impl Material for Metal {
+    fn scatter(
+        &self,
+        r_in: Ray, 
+        rec: HitRecord, 
+    ) -> Option<(Color, Ray)> {
+        let frame = Frame::new(rec.normal, rec.tangent);
+        let wo = frame.to_local(-r_in.direction().unit_vector());
+        if wo.z <= 0.0 {
+            return None;
+        }
+
+        let ggx = Ggx::from_roughness(self.roughness_u, self.roughness_v);
+        let (wi, attenuation) = if ggx.is_smooth() {
+            (RtVec3::new(-wo.x, -wo.y, wo.z), self.reflectance.fresnel(wo.z))
+        } else {
+            let h = ggx.sample_visible_normal(wo, random_float(), random_float());
+            let wi = reflect(wo, h);
+            // Reflected below the surface, the energy multiple bounces between facets would return is lost
+            if wi.z <= 0.0 {
+                return None;
+            }
+            let shadowing = ggx.masking_shadowing(wo, wi) / ggx.masking(wo);
+            (wi, Color::from_vec(self.reflectance.fresnel(wo.dot(&h)).to_vec() * shadowing))
+        };
+
+        Some((attenuation, Ray::new(rec.p, frame.to_world(wi))))
+    }
+
+    fn albedo(&self, _rec: &HitRecord) -> Color {
+        self.reflectance.fresnel(1.0)
+    }
+
+    // Scene files hold tinted, isotropic metal only
+    fn describe(&self) -> Option<MaterialDescription> {
+        match self.reflectance {
+            Reflectance::Tint(albedo) if self.roughness_u == self.roughness_v => {
+                Some(MaterialDescription::Metal { albedo, roughness: self.roughness_u })
+            }
+            _ => None,
+        }
+    }
+}
+
+// Dielectric Logic (with Beer-Lambert absorption)
+// Glass and water. `albedo` tints every interface crossing; `absorption` is the coefficient per
+// unit distance of light absorbed inside, so thick glass gets darker than thin glass.
+pub struct Dielectric {
+    pub albedo: Color,
+    pub refraction_index: f64,
+    pub absorption: Color,
+    // Per-wavelength index for the spectral renderer, RGB renders use `refraction_index`
+    pub dispersion: Option<Dispersion>,
+}
+
+impl Dielectric {
+    pub fn new(albedo: Color, refraction_index: f64) -> Self {
+        Dielectric {
+            albedo,
+            refraction_index,
+            absorption: Color::new_rgb(0.0, 0.0, 0.0),
+            dispersion: None,
+        }
+    }
+
+    // Clear glass splitting light into its colors, with its D line index for RGB renders.
+    pub fn dispersive(dispersion: Dispersion) -> Self {
+        Dielectric::new(Color::new_rgb(1.0, 1.0, 1.0), dispersion.index(D_LINE)).with_dispersion(dispersion)
+    }
+
+    // Colored glass that lets through `color` of the light after `distance` inside it.
+    pub fn tinted(color: Color, distance: f64, refraction_index: f64) -> Self {
+        let coefficient = |transmitted: f64| -transmitted.clamp(1e-6, 1.0).ln() / distance;
+        Dielectric::new(Color::new_rgb(1.0, 1.0, 1.0), refraction_index)
+            .with_absorption(Color::new_rgb(coefficient(color.r), coefficient(color.g), coefficient(color.b)))
+    }
+
+    pub fn with_absorption(mut self, absorption: Color) -> Self {
+        self.absorption = absorption;
+        self
+    }
+
+    pub fn with_dispersion(mut self, dispersion: Dispersion) -> Self {
+        self.dispersion = Some(dispersion);
+        self
+    }
+
+    pub fn reflectance(cosine: f64, refraction_index: f64) -> f64 {
+        // Schlick's approximation for reflectance.
+        let mut r0: f64 = (1.0 - refraction_index) / (1.0 + refraction_index);
+        r0 = r0 * r0;
+        r0 + (1.0 - r0) * (1.0 - cosine).powf(5.0)
+    }
+
+    // Reflects or refracts at the surface of glass with the given index.
+    fn scatter_with_index(&self, r_in: Ray, rec: HitRecord, refraction_index: f64) -> Option<(Color, Ray)> {
+        let mut attenuation = self.albedo;
+        // Leaving the medium, the ray has travelled t along its direction inside it
+        if !rec.front_face {
+            let distance = rec.t * r_in.direction().length();
+            let transmittance = |coefficient: f64| (-coefficient * distance).exp();
+            attenuation = attenuation
+                * Color::new_rgb(transmittance(self.absorption.r), transmittance(self.absorption.g), transmittance(self.absorption.b));
+        }
+        let ri: f64 = if rec.front_face {
+            1.0 / refraction_index
+        } else {
+            refraction_index
+        };
+
+        let unit_direction: RtVec3 = RtVec3::unit_vector(&r_in.direction());
+        let cos_theta: f64 = f64::min(-unit_direction.dot(&rec.normal), 1.0);
+        let sin_theta: f64 = f64::sqrt(1.0 - cos_theta * cos_theta);
+        let cannot_refract: bool = ri * sin_theta > 1.0;
+
+        let direction: RtVec3 = if cannot_refract || Dielectric::reflectance(cos_theta, ri) > random_float() {
+            RtVec3::reflect(unit_direction, rec.normal)
+        } else {
+            RtVec3::refract(unit_direction, rec.normal, ri)
+        };
+        let scattered = Ray::new(rec.p, direction);
+
+        Some((attenuation, scattered))
+    }
+}
+
+impl Material for Dielectric {
+    fn scatter(
+        &self,
+        r_in: Ray, 
+        rec: HitRecord, 
+    ) -> Option<(Color, Ray)> {
+        self.scatter_with_index(r_in, rec, self.refraction_index)
+    }
+
+    fn scatter_spectral(&self, r_in: Ray, rec: HitRecord, wavelengths: &mut SampledWavelengths) -> Option<(Color, Ray)> {
+        match &self.dispersion {
+            Some(dispersion) => {
+                wavelengths.terminate_secondary();
+                self.scatter_with_index(r_in, rec, dispersion.index(wavelengths.hero()))
+            }
+            None => self.scatter(r_in, rec),
+        }
+    }
+
+    fn albedo(&self, _rec: &HitRecord) -> Color {
+        self.albedo
+    }
+
+    fn describe(&self) -> Option<MaterialDescription> {
+        Some(MaterialDescription::Dielectric {
+            albedo: self.albedo,
+            refraction_index: self.refraction_index,
+            absorption: self.absorption,
+            dispersion: self.dispersion,
+        })
+    }
+}
+
+// Subsurface Logic (random walk through a scattering interior)
+// Wax, marble, skin and milk: light refracts into the object like glass, then wanders inside,
+// scattering off particles every `mean_free_path` on average, until it finds its way out again
+// or is absorbed. `albedo` is the chance of surviving each scattering event, per channel, and a
+// longer free path for red than blue makes red light bleed further. The walk uses the object's
+// own surface as the boundary, so any closed object works, and each step inside is one bounce.
+pub struct Subsurface {
+    pub albedo: Color,
+    pub mean_free_path: Color,
+    pub refraction_index: f64,
+    pub anisotropy: f64,           // Henyey-Greenstein g, positive scatters forwards
+}
+
+impl Subsurface {
+    pub fn new(albedo: Color, mean_free_path: Color, refraction_index: f64) -> Self {
+        Subsurface {
+            albedo,
+            mean_free_path,
+            refraction_index,
+            anisotropy: 0.0,
+        }
+    }
+
+    pub fn with_anisotropy(mut self, anisotropy: f64) -> Self {
+        self.anisotropy = anisotropy.clamp(-0.99, 0.99);
+        self
+    }
+
+    // New direction after scattering off a particle while travelling along `direction`.
+    fn sample_phase(&self, direction: RtVec3) -> RtVec3 {
+        let g = self.anisotropy;
+        let u = random_float();
+        let cos_theta = if g.abs() < 1e-3 {
+            1.0 - 2.0 * u
+        } else {
+            let term = (1.0 - g * g) / (1.0 + g - 2.0 * g * u);
+            ((1.0 + g * g - term * term) / (2.0 * g)).clamp(-1.0, 1.0)
+        };
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * std::f64::consts::PI * random_float();
+        let frame = Frame::new(direction, RtVec3::new(0.0, 0.0, 0.0));
+        frame.to_world(RtVec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta))
+    }
+}
+
+impl Material for Subsurface {
+    fn scatter(
+        &self,
+        r_in: Ray,
+        rec: HitRecord,
+    ) -> Option<(Color, Ray)> {
+        let unit_direction = r_in.direction().unit_vector();
+        let mut attenuation = Color::new_rgb(1.0, 1.0, 1.0);
+
+        // Coming from inside: the walk either scatters before reaching the surface or gets there.
+        // The flight length is sampled for one channel picked at random, and the weights follow
+        // the average density over all three.
+        if !rec.front_face {
+            let paths = [self.mean_free_path.r, self.mean_free_path.g, self.mean_free_path.b];
+            let extinction = paths.map(|path| 1.0 / path.max(1e-9));
+            let channel = (random_float() * 3.0).min(2.0) as usize;
+            let flight = -(1.0 - random_float()).ln() / extinction[channel];
+            let distance = rec.t * r_in.direction().length();
+            if flight < distance {
+                let transmittance = extinction.map(|sigma| (-sigma * flight).exp());
+                let density = (0..3).map(|c| extinction[c] * transmittance[c]).sum::<f64>() / 3.0;
+                let albedo = [self.albedo.r, self.albedo.g, self.albedo.b];
+                let weight = |c: usize| albedo[c] * extinction[c] * transmittance[c] / density;
+                let inside = r_in.origin() + flight * unit_direction;
+                return Some((Color::new_rgb(weight(0), weight(1), weight(2)), Ray::new(inside, self.sample_phase(unit_direction))));
+            }
+            let transmittance = extinction.map(|sigma| (-sigma * distance).exp());
+            let chance = transmittance.iter().sum::<f64>() / 3.0;
+            attenuation = Color::new_rgb(transmittance[0] / chance, transmittance[1] / chance, transmittance[2] / chance);
+        }
+
+        // At the surface: refract in or out like glass, or reflect
+        let ri = if rec.front_face { 1.0 / self.refraction_index } else { self.refraction_index };
+        let cos_theta = f64::min(-unit_direction.dot(&rec.normal), 1.0);
+        let sin_theta = f64::sqrt(1.0 - cos_theta * cos_theta);
+        let direction = if ri * sin_theta > 1.0 || Dielectric::reflectance(cos_theta, ri) > random_float() {
+            RtVec3::reflect(unit_direction, rec.normal)
+        } else {
+            RtVec3::refract(unit_direction, rec.normal, ri)
+        };
+        Some((attenuation, Ray::new(rec.p, direction)))
+    }
+
+    fn albedo(&self, _rec: &HitRecord) -> Color {
+        self.albedo
+    }
+
+    fn describe(&self) -> Option<MaterialDescription> {
+        Some(MaterialDescription::Subsurface {
+            albedo: self.albedo,
+            mean_free_path: self.mean_free_path,
+            refraction_index: self.refraction_index,
+            anisotropy: self.anisotropy,
+        })
+    }
+}
+
+// Principled Logic (Disney-style uber-material)
+// One material covering the common surfaces, each scatter picks a single layer at random in
+// proportion to how much light it carries: a clearcoat varnish, then metal, then the dielectric
+// base's specular reflection, transmission or diffuse (with sheen) lobes.
+pub struct Principled {
+    pub base_color: Rc<dyn Texture>,
+    pub parameters: PrincipledParameters,
+}
+
+// All in [0, 1] apart from the index of refraction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PrincipledParameters {
+    pub metallic: f64,
+    pub roughness: f64,
+    pub specular: f64,             // 0.5 gives the reflectance `ior` implies, 0 and 1 none and double
+    pub specular_tint: f64,        // Tints dielectric reflections towards the base color
+    pub sheen: f64,                // Extra grazing-angle reflection for cloth
+    pub sheen_tint: f64,
+    pub clearcoat: f64,
+    pub clearcoat_roughness: f64,
+    pub transmission: f64,         // Share of the dielectric base that refracts instead of diffusing
+    pub ior: f64,
+}
+
+impl Default for PrincipledParameters {
+    fn default() -> Self {
+        PrincipledParameters {
+            metallic: 0.0,
+            roughness: 0.5,
+            specular: 0.5,
+            specular_tint: 0.0,
+            sheen: 0.0,
+            sheen_tint: 0.5,
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.03,
+            transmission: 0.0,
+            ior: 1.5,
+        }
+    }
+}
+
+impl PrincipledParameters {
+    // Scene file keys with their values
+    pub fn fields(&self) -> [(&'static str, f64); 10] {
+        [
+            ("metallic", self.metallic),
+            ("roughness", self.roughness),
+            ("specular", self.specular),
+            ("specular_tint", self.specular_tint),
+            ("sheen", self.sheen),
+            ("sheen_tint", self.sheen_tint),
+            ("clearcoat", self.clearcoat),
+            ("clearcoat_roughness", self.clearcoat_roughness),
+            ("transmission", self.transmission),
+            ("ior", self.ior),
+        ]
+    }
+
+    pub fn fields_mut(&mut self) -> [(&'static str, &mut f64); 10] {
+        [
+            ("metallic", &mut self.metallic),
+            ("roughness", &mut self.roughness),
+            ("specular", &mut self.specular),
+            ("specular_tint", &mut self.specular_tint),
+            ("sheen", &mut self.sheen),
+            ("sheen_tint", &mut self.sheen_tint),
+            ("clearcoat", &mut self.clearcoat),
+            ("clearcoat_roughness", &mut self.clearcoat_roughness),
+            ("transmission", &mut self.transmission),
+            ("ior", &mut self.ior),
+        ]
+    }
+}
+
+impl Principled {
+    pub fn new(base_color: Color, parameters: PrincipledParameters) -> Self {
+        Principled::from_texture(Rc::new(SolidColor::new(base_color)), parameters)
+    }
+
+    pub fn from_texture(base_color: Rc<dyn Texture>, parameters: PrincipledParameters) -> Self {
+        Principled {
+            base_color,
+            parameters,
+        }
+    }
+}
+
+// Reflection off a sampled microfacet `h`, weighted by the masking the sample didn't cover.
+fn microfacet_reflection(ggx: &Ggx, wo: RtVec3, h: RtVec3, fresnel: Color) -> Option<(Color, RtVec3)> {
+    let wi = reflect(wo, h);
+    if wi.z <= 0.0 {
+        return None;
+    }
+    let shadowing = if ggx.is_smooth() { 1.0 } else { ggx.masking_shadowing(wo, wi) / ggx.masking(wo) };
+    Some((Color::from_vec(fresnel.to_vec() * shadowing), wi))
+}
+
+fn sample_microfacet(ggx: &Ggx, wo: RtVec3) -> RtVec3 {
+    if ggx.is_smooth() {
+        RtVec3::new(0.0, 0.0, 1.0)
+    } else {
+        ggx.sample_visible_normal(wo, random_float(), random_float())
+    }
+}
+
+fn mix(a: Color, b: Color, t: f64) -> Color {
+    Color::from_vec((1.0 - t) * a.to_vec() + t * b.to_vec())
+}
+
+// The color scaled to unit luminance, white for black.
+fn hue(color: Color) -> Color {
+    let luminance = 0.2126 * color.r + 0.7152 * color.g + 0.0722 * color.b;
+    if luminance > 0.0 {
+        Color::from_vec(color.to_vec() / luminance)
+    } else {
+        Color::new_rgb(1.0, 1.0, 1.0)
+    }
+}
+
+impl Material for Principled {
+    fn scatter(
+        &self,
+        r_in: Ray,
+        rec: HitRecord,
+    ) -> Option<(Color, Ray)> {
+        let p = &self.parameters;
+        let frame = Frame::new(rec.normal, rec.tangent);
+        let wo = frame.to_local(-r_in.direction().unit_vector());
+        if wo.z <= 0.0 {
+            return None;
+        }
+        let white = Color::new_rgb(1.0, 1.0, 1.0);
+        let base = self.albedo(&rec);
+        let scattered = |(attenuation, wi): (Color, RtVec3)| (attenuation, Ray::new(rec.p, frame.to_world(wi)));
+
+        // Clearcoat: colorless varnish on top, chosen as often as it reflects
+        if p.clearcoat > 0.0 {
+            let coat = Ggx::from_roughness(p.clearcoat_roughness, p.clearcoat_roughness);
+            let h = sample_microfacet(&coat, wo);
+            let reflectance = p.clearcoat * fresnel_schlick(wo.dot(&h), Color::new_rgb(0.04, 0.04, 0.04)).r;
+            if random_float() < reflectance {
+                return microfacet_reflection(&coat, wo, h, white).map(scattered);
+            }
+        }
+
+        let ggx = Ggx::from_roughness(p.roughness, p.roughness);
+        let h = sample_microfacet(&ggx, wo);
+        let cos_theta = wo.dot(&h);
+
+        // Metal: Schlick reflectance tinted by the base color
+        if random_float() < p.metallic {
+            return microfacet_reflection(&ggx, wo, h, fresnel_schlick(cos_theta, base)).map(scattered);
+        }
+
+        // Dielectric specular, chosen with its Fresnel probability. From inside, rays past the
+        // critical angle are always reflected.
+        let ior_ratio = if rec.front_face { 1.0 / p.ior } else { p.ior };
+        let r0 = ((1.0 - p.ior) / (1.0 + p.ior)).powi(2);
+        let f0 = (2.0 * p.specular * r0).min(1.0);
+        let total_internal = ior_ratio * (1.0 - cos_theta * cos_theta).max(0.0).sqrt() > 1.0;
+        let reflectance = if total_internal { 1.0 } else { fresnel_schlick(cos_theta, Color::new_rgb(f0, f0, f0)).r };
+        if random_float() < reflectance {
+            return microfacet_reflection(&ggx, wo, h, mix(white, hue(base), p.specular_tint)).map(scattered);
+        }
+
+        // Transmission through the sampled microfacet, tinted by the base color
+        if random_float() < p.transmission {
+            let wi = RtVec3::refract(-wo, h, ior_ratio);
+            if wi.z >= 0.0 {
+                return None;
+            }
+            return Some(scattered((base, wi)));
+        }
+
+        // Diffuse, cosine distributed, with the sheen brightening grazing reflections
+        let mut wi = RtVec3::new(0.0, 0.0, 1.0) + RtVec3::random_unit_vector();
+        if wi.near_zero() {
+            wi = RtVec3::new(0.0, 0.0, 1.0);
+        }
+        let wi = wi.unit_vector();
+        let mut attenuation = base;
+        if p.sheen > 0.0 {
+            let half = (wo + wi).unit_vector();
+            let weight = p.sheen * (1.0 - wi.dot(&half).clamp(0.0, 1.0)).powi(5);
+            attenuation = Color::from_vec(base.to_vec() + weight * mix(white, hue(base), p.sheen_tint).to_vec());
+        }
+        Some(scattered((attenuation, wi)))
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Color {
+        texture_albedo(&self.base_color, rec)
+    }
+
+    // Only plain colors can be saved, scene files have no textures
+    fn describe(&self) -> Option<MaterialDescription> {
+        Some(MaterialDescription::Principled { base_color: self.base_color.solid_color()?, parameters: self.parameters })
+    }
+}
+
+// Thin-film Logic (interference in a layer about a wavelength thick)
+// Light reflected off the top and the bottom of the film interferes, so reflections take on
+// colors that shift with the viewing angle and the thickness: soap bubbles, oil on water, the
+// mica flakes of pearlescent paint. Free standing, like a bubble, whatever isn't reflected
+// passes straight through; over a base it goes on to scatter off the base.
+pub struct ThinFilm {
+    pub thickness: f64,                    // Nanometres
+    pub film_ior: f64,
+    pub base: Option<Rc<dyn Material>>,
+    pub base_ior: f64,                     // Index under the film, air when free standing
+    pub roughness: f64,
+}
+
+impl ThinFilm {
+    // Soap bubble: a film in air on both sides.
+    pub fn bubble(thickness: f64, film_ior: f64) -> Self {
+        ThinFilm {
+            thickness,
+            film_ior,
+            base: None,
+            base_ior: 1.0,
+            roughness: 0.0,
+        }
+    }
+
+    // Film coating `base`, a surface with index `base_ior`.
+    pub fn over(base: Rc<dyn Material>, thickness: f64, film_ior: f64, base_ior: f64) -> Self {
+        ThinFilm {
+            base: Some(base),
+            base_ior,
+            ..ThinFilm::bubble(thickness, film_ior)
+        }
+    }
+
+    // Spreads the film's reflection, for flakes that don't all lie flat.
+    pub fn with_roughness(mut self, roughness: f64) -> Self {
+        self.roughness = roughness;
+        self
+    }
+
+    pub fn reflectance(&self, cos_theta: f64, wavelength: f64) -> f64 {
+        fresnel_thin_film(cos_theta, wavelength, self.thickness, self.film_ior, self.base_ior)
+    }
+
+    // What RGB renders see: the reflectance spectrum at 16 wavelengths across the visible range,
+    // converted like the spectral renderer's radiance.
+    pub fn reflectance_rgb(&self, cos_theta: f64) -> Color {
+        let sets = 4;
+        let mut total = RtVec3::new(0.0, 0.0, 0.0);
+        for set in 0..sets {
+            let wavelengths = SampledWavelengths::sample((set as f64 + 0.5) / (sets * WAVELENGTH_SAMPLES) as f64);
+            total = total + wavelengths.to_rgb(wavelengths.lambda.map(|wavelength| self.reflectance(cos_theta, wavelength)));
+        }
+        let rgb = total / sets as f64;
+        // Saturated interference colors can fall outside of sRGB
+        Color::new_rgb(rgb.x.clamp(0.0, 1.0), rgb.y.clamp(0.0, 1.0), rgb.z.clamp(0.0, 1.0))
+    }
+
+    // Reflects off the film with probability `chance`, otherwise carries on through it. The
+    // weights make up for the choice.
+    fn scatter_with<F>(&self, r_in: Ray, rec: HitRecord, reflectance: impl Fn(f64) -> Color, chance: impl Fn(&Color) -> f64, through: F) -> Option<(Color, Ray)>
+    where
+        F: FnOnce(Ray, HitRecord) -> Option<(Color, Ray)>,
+    {
+        let frame = Frame::new(rec.normal, rec.tangent);
+        let wo = frame.to_local(-r_in.direction().unit_vector());
+        let ggx = Ggx::from_roughness(self.roughness, self.roughness);
+        let h = sample_microfacet(&ggx, wo);
+        let fresnel = reflectance(wo.dot(&h).abs());
+        let probability = chance(&fresnel).clamp(0.0, 1.0);
+        if random_float() < probability {
+            let weight = Color::from_vec(fresnel.to_vec() / probability);
+            return microfacet_reflection(&ggx, wo, h, weight).map(|(attenuation, wi)| (attenuation, Ray::new(rec.p, frame.to_world(wi))));
+        }
+        let transmitted = Color::from_vec((RtVec3::new(1.0, 1.0, 1.0) - fresnel.to_vec()) / (1.0 - probability));
+        let (attenuation, scattered) = through(r_in, rec)?;
+        Some((transmitted * attenuation, scattered))
+    }
+}
+
+impl Material for ThinFilm {
+    fn scatter(
+        &self,
+        r_in: Ray,
+        rec: HitRecord,
+    ) -> Option<(Color, Ray)> {
+        let reflectance = |cos_theta: f64| self.reflectance_rgb(cos_theta);
+        let chance = |fresnel: &Color| (fresnel.r + fresnel.g + fresnel.b) / 3.0;
+        self.scatter_with(r_in, rec, reflectance, chance, |r_in, rec| match &self.base {
+            Some(base) => base.scatter(r_in, rec),
+            None => Some((Color::new_rgb(1.0, 1.0, 1.0), Ray::new(rec.p, r_in.direction()))),
+        })
+    }
+
+    // The reflectance is only known per wavelength, so the path carries on with the hero alone
+    // and the film is chosen exactly as often as it reflects.
+    fn scatter_spectral(&self, r_in: Ray, rec: HitRecord, wavelengths: &mut SampledWavelengths) -> Option<(Color, Ray)> {
+        wavelengths.terminate_secondary();
+        let hero = wavelengths.hero();
+        let reflectance = |cos_theta: f64| {
+            let value = self.reflectance(cos_theta, hero);
+            Color::new_rgb(value, value, value)
+        };
+        let chance = |fresnel: &Color| fresnel.r;
+        self.scatter_with(r_in, rec, reflectance, chance, |r_in, rec| match &self.base {
+            Some(base) => base.scatter_spectral(r_in, rec, wavelengths),
+            None => Some((Color::new_rgb(1.0, 1.0, 1.0), Ray::new(rec.p, r_in.direction()))),
+        })
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Color {
+        match &self.base {
+            Some(base) => base.albedo(rec),
+            None => Color::new_rgb(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+// Coated Logic (clear varnish over any material)
+// A smooth or slightly rough dielectric layer on top of `base`, like the clear coat of car paint
+// or lacquered wood. The coat reflects as often as its Fresnel term says, everything else goes
+// through to the base, tinted on the way in and out by `tint` (the color let through head on).
+pub struct Coated {
+    pub base: Rc<dyn Material>,
+    pub ior: f64,
+    pub roughness: f64,
+    pub tint: Color,
+}
+
+impl Coated {
+    pub fn new(base: Rc<dyn Material>, ior: f64, roughness: f64) -> Self {
+        Coated {
+            base,
+            ior,
+            roughness,
+            tint: Color::new_rgb(1.0, 1.0, 1.0),
+        }
+    }
+
+    pub fn with_tint(mut self, tint: Color) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    fn scatter_with<F>(&self, r_in: Ray, rec: HitRecord, base: F) -> Option<(Color, Ray)>
+    where
+        F: FnOnce(Ray, HitRecord) -> Option<(Color, Ray)>,
+    {
+        // The coat is on the outside only
+        if !rec.front_face {
+            return base(r_in, rec);
+        }
+        let frame = Frame::new(rec.normal, rec.tangent);
+        let wo = frame.to_local(-r_in.direction().unit_vector());
+        let ggx = Ggx::from_roughness(self.roughness, self.roughness);
+        let h = sample_microfacet(&ggx, wo);
+        if random_float() < fresnel_dielectric(wo.dot(&h), self.ior) {
+            let white = Color::new_rgb(1.0, 1.0, 1.0);
+            return microfacet_reflection(&ggx, wo, h, white).map(|(attenuation, wi)| (attenuation, Ray::new(rec.p, frame.to_world(wi))));
+        }
+
+        let (attenuation, scattered) = base(r_in, rec)?;
+        // Grazing paths cross more of the coat, so they come out more strongly tinted
+        let cos_out = frame.to_local(scattered.direction().unit_vector()).z.abs();
+        let crossings = 0.5 * (1.0 / wo.z.max(1e-3) + 1.0 / cos_out.max(1e-3));
+        let transmitted = |tint: f64| tint.powf(crossings);
+        Some((attenuation * Color::new_rgb(transmitted(self.tint.r), transmitted(self.tint.g), transmitted(self.tint.b)), scattered))
+    }
+}
+
+impl Material for Coated {
+    fn scatter(
+        &self,
+        r_in: Ray,
+        rec: HitRecord,
+    ) -> Option<(Color, Ray)> {
+        self.scatter_with(r_in, rec, |r_in, rec| self.base.scatter(r_in, rec))
+    }
+
+    fn scatter_spectral(&self, r_in: Ray, rec: HitRecord, wavelengths: &mut SampledWavelengths) -> Option<(Color, Ray)> {
+        self.scatter_with(r_in, rec, |r_in, rec| self.base.scatter_spectral(r_in, rec, wavelengths))
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Color {
+        self.base.albedo(rec) * self.tint
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Color {
+        self.base.emitted(rec)
+    }
+}
+
+// Mix Logic (one of two materials, picked per scatter)
+// Blends two materials by choosing one at random on every hit, `weight` being the chance of
+// `second`. Rust patches on steel, dirt on paint, or a diffuse base that turns glossy at
+// grazing angles with a Fresnel weight.
+pub struct MixMaterial {
+    pub first: Rc<dyn Material>,
+    pub second: Rc<dyn Material>,
+    pub weight: MixWeight,
+}
+
+#[derive(Clone)]
+pub enum MixWeight {
+    Constant(f64),
+    // Dielectric reflectance of a surface with this index, rising towards grazing angles
+    Fresnel(f64),
+    // Mean of the texture's channels at the hit, white picks `second`
+    Mask(Rc<dyn Texture>),
+}
+
+impl MixMaterial {
+    pub fn new(first: Rc<dyn Material>, second: Rc<dyn Material>, weight: MixWeight) -> Self {
+        MixMaterial { first, second, weight }
+    }
+
+    // Chance of `second` for a ray arriving with `cos_theta` to the normal.
+    pub fn weight(&self, rec: &HitRecord, cos_theta: f64) -> f64 {
+        let weight = match &self.weight {
+            MixWeight::Constant(weight) => *weight,
+            MixWeight::Fresnel(ior) => fresnel_dielectric(cos_theta, *ior),
+            MixWeight::Mask(mask) => {
+                let value = mask.value(rec.u, rec.v, &rec.p);
+                (value.r + value.g + value.b) / 3.0
+            }
+        };
+        weight.clamp(0.0, 1.0)
+    }
+
+    fn choose(&self, r_in: &Ray, rec: &HitRecord) -> &Rc<dyn Material> {
+        let cos_theta = -r_in.direction().unit_vector().dot(&rec.normal);
+        if random_float() < self.weight(rec, cos_theta) {
+            &self.second
+        } else {
+            &self.first
+        }
+    }
+
+    // Without a ray, the blend as seen head on.
+    fn blend(&self, rec: &HitRecord, first: Color, second: Color) -> Color {
+        mix(first, second, self.weight(rec, 1.0))
+    }
+}
+
+impl Material for MixMaterial {
+    fn scatter(
+        &self,
+        r_in: Ray,
+        rec: HitRecord,
+    ) -> Option<(Color, Ray)> {
+        self.choose(&r_in, &rec).scatter(r_in, rec)
+    }
+
+    fn scatter_spectral(&self, r_in: Ray, rec: HitRecord, wavelengths: &mut SampledWavelengths) -> Option<(Color, Ray)> {
+        self.choose(&r_in, &rec).scatter_spectral(r_in, rec, wavelengths)
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Color {
+        self.blend(rec, self.first.albedo(rec), self.second.albedo(rec))
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Color {
+        self.blend(rec, self.first.emitted(rec), self.second.emitted(rec))
+    }
+}
+
+// Cutout Logic (alpha masking)
+// Holes in a surface: where the opacity texture is dark, rays carry on straight through as if
+// nothing was hit, elsewhere `material` scatters them. Leaves, fences and lace on plain quads.
+// In between values pass that fraction of rays, so soft mask edges blend. Every pass through
+// counts as a bounce.
+pub struct Cutout {
+    pub material: Rc<dyn Material>,
+    pub opacity: Rc<dyn Texture>,
+}
+
+impl Cutout {
+    pub fn new(material: Rc<dyn Material>, opacity: Rc<dyn Texture>) -> Self {
+        Cutout { material, opacity }
+    }
+
+    // Mean of the opacity texture's channels at the hit.
+    pub fn opacity(&self, rec: &HitRecord) -> f64 {
+        let value = self.opacity.value(rec.u, rec.v, &rec.p);
+        ((value.r + value.g + value.b) / 3.0).clamp(0.0, 1.0)
+    }
+
+    fn passes(&self, rec: &HitRecord) -> bool {
+        random_float() >= self.opacity(rec)
+    }
+}
+
+impl Material for Cutout {
+    fn scatter(
+        &self,
+        r_in: Ray,
+        rec: HitRecord,
+    ) -> Option<(Color, Ray)> {
+        if self.passes(&rec) {
+            return Some((Color::new_rgb(1.0, 1.0, 1.0), Ray::new(rec.p, r_in.direction())));
+        }
+        self.material.scatter(r_in, rec)
+    }
+
+    fn scatter_spectral(&self, r_in: Ray, rec: HitRecord, wavelengths: &mut SampledWavelengths) -> Option<(Color, Ray)> {
+        if self.passes(&rec) {
+            return Some((Color::new_rgb(1.0, 1.0, 1.0), Ray::new(rec.p, r_in.direction())));
+        }
+        self.material.scatter_spectral(r_in, rec, wavelengths)
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Color {
+        self.material.albedo(rec)
+    }
+
+    // Holes don't glow
+    fn emitted(&self, rec: &HitRecord) -> Color {
+        Color::from_vec(self.opacity(rec) * self.material.emitted(rec).to_vec())
+    }
+}
+
+// Normal Map Logic (tangent space normals from a texture)
+// Detail without geometry: the texture's colors are normals in the surface's tangent frame,
+// red along u, green along v and blue out of the surface, each mapped from [0, 1] to [-1, 1]
+// (the usual flat blue (0.5, 0.5, 1) leaves the normal alone). `strength` scales the tilt.
+pub struct NormalMap {
+    pub material: Rc<dyn Material>,
+    pub map: Rc<dyn Texture>,
+    pub strength: f64,
+}
+
+impl NormalMap {
+    pub fn new(material: Rc<dyn Material>, map: Rc<dyn Texture>) -> Self {
+        NormalMap {
+            material,
+            map,
+            strength: 1.0,
+        }
+    }
+
+    pub fn with_strength(mut self, strength: f64) -> Self {
+        self.strength = strength;
+        self
+    }
+
+    // The shading normal the map gives at the hit.
+    pub fn normal(&self, rec: &HitRecord) -> RtVec3 {
+        let (tangent, bitangent, normal) = tangent_frame(rec);
+        let (u, v) = rec.normal_uv.unwrap_or((rec.u, rec.v));
+        let texel = self.map.value(u, v, &rec.p).to_vec() * 2.0 - RtVec3::new(1.0, 1.0, 1.0);
+        (self.strength * (texel.x * tangent + texel.y * bitangent) + texel.z * normal).unit_vector()
+    }
+}
+
+impl Material for NormalMap {
+    fn scatter(
+        &self,
+        r_in: Ray,
+        rec: HitRecord,
+    ) -> Option<(Color, Ray)> {
+        let normal = self.normal(&rec);
+        scatter_with_normal(r_in, rec, normal, |r_in, rec| self.material.scatter(r_in, rec))
+    }
+
+    fn scatter_spectral(&self, r_in: Ray, rec: HitRecord, wavelengths: &mut SampledWavelengths) -> Option<(Color, Ray)> {
+        let normal = self.normal(&rec);
+        scatter_with_normal(r_in, rec, normal, |r_in, rec| self.material.scatter_spectral(r_in, rec, wavelengths))
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Color {
+        self.material.albedo(rec)
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Color {
+        self.material.emitted(rec)
+    }
+}
+
+// Bump Map Logic (shading normals from a height field)
+// The brightness of `height` (mean of its channels) raises the surface by up to `scale` world
+// units, and the shading normal follows the slopes. Slopes are measured a small world distance
+// along the tangent and bitangent, moving the point and the texture coordinates by as much as
+// the surface's parameterization says that distance covers. Image and solid (3D) textures both
+// work, and a given `scale` looks the same on any surface. Surfaces that don't report their
+// parameterization are taken to cover one world unit per unit of u and v.
+pub struct BumpMap {
+    pub material: Rc<dyn Material>,
+    pub height: Rc<dyn Texture>,
+    pub scale: f64,
+}
+
+impl BumpMap {
+    pub fn new(material: Rc<dyn Material>, height: Rc<dyn Texture>, scale: f64) -> Self {
+        BumpMap { material, height, scale }
+    }
+
+    // The shading normal the height field gives at the hit.
+    pub fn normal(&self, rec: &HitRecord) -> RtVec3 {
+        let (tangent, bitangent, normal) = tangent_frame(rec);
+        let (u, v) = rec.normal_uv.unwrap_or((rec.u, rec.v));
+        let step = 1e-3;
+        let uv_step = |length: f64| if length > 0.0 { step / length } else { step };
+        let (du, dv) = (uv_step(rec.uv_lengths.0), uv_step(rec.uv_lengths.1));
+        let height = |du: f64, dv: f64, offset: RtVec3| {
+            let value = self.height.value(u + du, v + dv, &(rec.p + offset));
+            self.scale * (value.r + value.g + value.b) / 3.0
+        };
+        let here = height(0.0, 0.0, RtVec3::new(0.0, 0.0, 0.0));
+        let slope_u = (height(du, 0.0, step * tangent) - here) / step;
+        let slope_v = (height(0.0, dv, step * bitangent) - here) / step;
+        (normal - slope_u * tangent - slope_v * bitangent).unit_vector()
+    }
+}
+
+impl Material for BumpMap {
+    fn scatter(
+        &self,
+        r_in: Ray,
+        rec: HitRecord,
+    ) -> Option<(Color, Ray)> {
+        let normal = self.normal(&rec);
+        scatter_with_normal(r_in, rec, normal, |r_in, rec| self.material.scatter(r_in, rec))
+    }
+
+    fn scatter_spectral(&self, r_in: Ray, rec: HitRecord, wavelengths: &mut SampledWavelengths) -> Option<(Color, Ray)> {
+        let normal = self.normal(&rec);
+        scatter_with_normal(r_in, rec, normal, |r_in, rec| self.material.scatter_spectral(r_in, rec, wavelengths))
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Color {
+        self.material.albedo(rec)
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Color {
+        self.material.emitted(rec)
+    }
+}
+
+// Tangent, bitangent and normal at the hit, orthonormal. The bitangent points along increasing
+// v, so mirrored texture coordinates flip it.
+fn tangent_frame(rec: &HitRecord) -> (RtVec3, RtVec3, RtVec3) {
+    let frame = Frame::new(rec.normal, rec.tangent);
+    let bitangent = if rec.bitangent.dot(&frame.bitangent) < 0.0 { -frame.bitangent } else { frame.bitangent };
+    (frame.tangent, bitangent, frame.normal)
+}
+
+// Scatters off `rec` with its shading normal swapped for `normal`. The geometric normal still
+// decides which side of the surface is which: the normal is kept facing the ray, and rays the
+// bent normal would send through the surface (reflections below it, refractions above it) are
+// dropped rather than leaking light.
+fn scatter_with_normal<F>(r_in: Ray, mut rec: HitRecord, normal: RtVec3, scatter: F) -> Option<(Color, Ray)>
+where
+    F: FnOnce(Ray, HitRecord) -> Option<(Color, Ray)>,
+{
+    let towards_ray = -r_in.direction().unit_vector();
+    let facing = normal.dot(&towards_ray);
+    rec.normal = if facing < 1e-3 {
+        (normal + (1e-3 - facing) * towards_ray).unit_vector()
+    } else {
+        normal
+    };
+    let shading = rec.normal;
+    let geometric = rec.geometric_normal;
+    let (attenuation, scattered) = scatter(r_in, rec)?;
+    let direction = scattered.direction();
+    if (direction.dot(&shading) > 0.0) != (direction.dot(&geometric) > 0.0) {
+        return None;
+    }
+    Some((attenuation, scattered))
+}
+
+// Diffuse Light Logic (Emits, never scatters)
+pub struct DiffuseLight {
+    pub texture: Rc<dyn Texture>,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> Self {
+        DiffuseLight::from_texture(Rc::new(SolidColor::new(emit)))
+    }
+
+    pub fn from_texture(texture: Rc<dyn Texture>) -> Self {
+        DiffuseLight {texture}
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(
+        &self,
+        _r_in: Ray,
+        _rec: HitRecord,
+    ) -> Option<(Color, Ray)> {
+        None
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Color {
+        self.texture.value(rec.u, rec.v, &rec.p)
+    }
+
+    fn describe(&self) -> Option<MaterialDescription> {
+        Some(MaterialDescription::DiffuseLight { emit: self.texture.solid_color()? })
+    }
+}
+
+// Material Defaults
+
+    // Material: Lambertian
+pub fn default_material_lambertian() -> Rc<dyn Material> {
+    Rc::new(Lambertian::new(Color::new_rgb(0.5, 0.5, 0.5)))
+}
+
+pub fn new_material_lambertian() -> Rc<dyn Material> {
+    Rc::new(Lambertian::new(Color::new_rgb(0.5, 0.5, 0.5))) 
+}
+
+pub fn new_material_lambertian_color(color: Color) -> Rc<dyn Material> {
+    Rc::new(Lambertian::new(color))
+}
+
+pub fn new_material_lambertian_color_float(r: f64, g: f64, b: f64) -> Rc<dyn Material> {
+    Rc::new(Lambertian::new(Color::new_rgb(r, g, b)))
+}
+
+pub fn new_material_lambertian_texture(texture: Rc<dyn Texture>) -> Rc<dyn Material> {
+    Rc::new(Lambertian::from_texture(texture))
+}
+
+pub fn new_material_oren_nayar(color: Color, sigma: f64) -> Rc<dyn Material> {
+    Rc::new(OrenNayar::new(color, sigma))
+}
+
+pub fn new_material_velvet(color: Color, sheen: Color, roughness: f64) -> Rc<dyn Material> {
+    Rc::new(Velvet::new(color, sheen, roughness))
+}
+
+    // Material Metal
+pub fn default_material_metal() -> Rc<dyn Material> {
+    Rc::new(Metal::new(Color::new_rgb(0.5, 0.5, 0.5), 0.5))
+}
+
+pub fn new_material_metal(roughness: f64) -> Rc<dyn Material> {
+    Rc::new(Metal::new(Color::new_rgb(0.5, 0.5, 0.5), roughness))
+}
+
+pub fn new_material_metal_color(color: Color, roughness: f64) -> Rc<dyn Material> {
+    Rc::new(Metal::new(Color::new_rgb(color.r, color.g, color.b), roughness))
+}
+
+pub fn new_material_metal_color_float(r: f64, g: f64, b: f64, roughness: f64) -> Rc<dyn Material> {
+    Rc::new(Metal::new(Color::new_rgb(r, g, b), roughness))
+}
+
+    // Material Dielectric
+pub fn default_material_dielectric() -> Rc<dyn Material> { // Full refraction
+    Rc::new(Dielectric::new(Color::new_rgb(1.0, 1.0, 1.0), 1.5))
+}
+
+pub fn new_material_dielectric(refraction: f64) -> Rc<dyn Material> {
+    Rc::new(Dielectric::new(Color::new_rgb(1.0, 1.0, 1.0), refraction))
+}
+
+pub fn new_material_dielectric_color(color: Color, refraction: f64) -> Rc<dyn Material> {
+    Rc::new(Dielectric::new(color, refraction))
+}
+
+pub fn new_material_dielectric_color_float(r: f64, g: f64, b: f64, refraction: f64) -> Rc<dyn Material> {
+    Rc::new(Dielectric::new(Color::new_rgb(r, g, b), refraction))
+}
+
+pub fn new_material_dielectric_absorbing(absorption: Color, refraction: f64) -> Rc<dyn Material> {
+    Rc::new(Dielectric::new(Color::new_rgb(1.0, 1.0, 1.0), refraction).with_absorption(absorption))
+}
+
+pub fn new_material_dielectric_dispersive(dispersion: Dispersion) -> Rc<dyn Material> {
+    Rc::new(Dielectric::dispersive(dispersion))
+}
+
+  
+
+    // Material Diffuse Light
+pub fn new_material_diffuse_light_color(color: Color) -> Rc<dyn Material> {
+    Rc::new(DiffuseLight::new(color))
+}
+
+pub fn new_material_diffuse_light_texture(texture: Rc<dyn Texture>) -> Rc<dyn Material> {
+    Rc::new(DiffuseLight::from_texture(texture))
+}
+
+    // Material: Principled
+pub fn new_material_principled(color: Color, parameters: PrincipledParameters) -> Rc<dyn Material> {
+    Rc::new(Principled::new(color, parameters))
+}
+
+pub fn new_material_principled_texture(texture: Rc<dyn Texture>, parameters: PrincipledParameters) -> Rc<dyn Material> {
+    Rc::new(Principled::from_texture(texture, parameters))
+}
+
+    // Material: Subsurface
+pub fn new_material_subsurface(albedo: Color, mean_free_path: Color, refraction_index: f64) -> Rc<dyn Material> {
+    Rc::new(Subsurface::new(albedo, mean_free_path, refraction_index))
+}
+
+    // Material: Layers
+pub fn new_material_thin_film_bubble(thickness: f64, film_ior: f64) -> Rc<dyn Material> {
+    Rc::new(ThinFilm::bubble(thickness, film_ior))
+}
+
+pub fn new_material_coated(base: Rc<dyn Material>, ior: f64, roughness: f64) -> Rc<dyn Material> {
+    Rc::new(Coated::new(base, ior, roughness))
+}
+
+// Pearlescent car paint: mica flakes with a titanium dioxide film over a colored base, under a
+// glossy clear coat. The flakes shift the color towards the film's interference hues with angle.
+pub fn new_material_pearlescent_paint(color: Color) -> Rc<dyn Material> {
+    let flakes = ThinFilm::over(Rc::new(Lambertian::new(color)), 380.0, 2.4, 1.5).with_roughness(0.35);
+    Rc::new(Coated::new(Rc::new(flakes), 1.5, 0.05))
+}
+
+    // Material: Combinations
+pub fn new_material_mix(first: Rc<dyn Material>, second: Rc<dyn Material>, weight: MixWeight) -> Rc<dyn Material> {
+    Rc::new(MixMaterial::new(first, second, weight))
+}
+
+pub fn new_material_cutout(material: Rc<dyn Material>, opacity: Rc<dyn Texture>) -> Rc<dyn Material> {
+    Rc::new(Cutout::new(material, opacity))
+}
+
+    // Material: Surface detail
+pub fn new_material_normal_map(material: Rc<dyn Material>, map: Rc<dyn Texture>) -> Rc<dyn Material> {
+    Rc::new(NormalMap::new(material, map))
+}
+
+pub fn new_material_bump_map(material: Rc<dyn Material>, height: Rc<dyn Texture>, scale: f64) -> Rc<dyn Material> {
+    Rc::new(BumpMap::new(material, height, scale))
+}
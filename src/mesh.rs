@@ -0,0 +1,307 @@
+use crate::Interval;
+
+use crate::hit::{HitRecord, Hittable};
+
+use crate::material::Material;
+
+use crate::ray::{Color, Ray};
+
+use crate::rtvec3::{Point3, RtVec3};
+
+use std::rc::Rc;
+
+// Geometry: Triangle mesh
+// Indexed triangles sharing one material. Hits are found through a bounding volume hierarchy
+// built over the triangles, so meshes with many thousands of faces stay fast.
+pub struct Mesh {
+    pub positions: Vec<Point3>,
+    pub normals: Option<Vec<RtVec3>>,     // Per vertex, interpolated for smooth shading
+    pub uvs: Option<Vec<(f64, f64)>>,     // Per vertex texture coordinates, v = 0 at the bottom
+    pub normal_uvs: Option<Vec<(f64, f64)>>, // Separate coordinates for a normal map, they orient the tangents
+    pub colors: Option<Vec<Color>>,       // Per vertex colors, multiplied into diffuse albedo
+    pub triangles: Vec<[usize; 3]>,
+    pub material: Rc<dyn Material>,
+    nodes: Vec<BvhNode>,
+    order: Vec<usize>, // Triangle indices, each leaf covers a contiguous range
+}
+
+// Axis aligned bounding box
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Point3,
+    pub max: Point3,
+}
+
+impl Aabb {
+    pub fn empty() -> Self {
+        Aabb {
+            min: Point3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            max: Point3::new(-f64::INFINITY, -f64::INFINITY, -f64::INFINITY),
+        }
+    }
+
+    pub fn grow(&mut self, p: Point3) {
+        self.min = Point3::new(self.min.x.min(p.x), self.min.y.min(p.y), self.min.z.min(p.z));
+        self.max = Point3::new(self.max.x.max(p.x), self.max.y.max(p.y), self.max.z.max(p.z));
+    }
+
+    pub fn merge(&mut self, other: &Aabb) {
+        self.grow(other.min);
+        self.grow(other.max);
+    }
+
+    pub fn centre(&self) -> Point3 {
+        0.5 * (self.min + self.max)
+    }
+
+    // Slab test, true when the ray passes through the box within `interval`.
+    fn hit(&self, origin: &Point3, inverse_direction: &RtVec3, interval: &Interval) -> bool {
+        let mut t_min = interval.min;
+        let mut t_max = interval.max;
+        for (min, max, o, inv) in [
+            (self.min.x, self.max.x, origin.x, inverse_direction.x),
+            (self.min.y, self.max.y, origin.y, inverse_direction.y),
+            (self.min.z, self.max.z, origin.z, inverse_direction.z),
+        ] {
+            let t0 = (min - o) * inv;
+            let t1 = (max - o) * inv;
+            let (near, far) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+            t_min = t_min.max(near);
+            t_max = t_max.min(far);
+            if t_max < t_min {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+struct BvhNode {
+    bounds: Aabb,
+    // Leaves: `count` triangles starting at `first` in `order`. Inner nodes: count is 0 and the
+    // children are `first` and `first + 1` in `nodes`.
+    first: usize,
+    count: usize,
+}
+
+const LEAF_SIZE: usize = 4;
+
+impl Mesh {
+    pub fn new(
+        positions: Vec<Point3>,
+        normals: Option<Vec<RtVec3>>,
+        uvs: Option<Vec<(f64, f64)>>,
+        triangles: Vec<[usize; 3]>,
+        material: Rc<dyn Material>,
+    ) -> Self {
+        let mut mesh = Mesh {
+            positions,
+            normals,
+            uvs,
+            normal_uvs: None,
+            colors: None,
+            triangles,
+            material,
+            nodes: Vec::new(),
+            order: Vec::new(),
+        };
+        mesh.build_bvh();
+        mesh
+    }
+
+    pub fn with_colors(mut self, colors: Vec<Color>) -> Self {
+        self.colors = Some(colors);
+        self
+    }
+
+    pub fn with_normal_uvs(mut self, normal_uvs: Vec<(f64, f64)>) -> Self {
+        self.normal_uvs = Some(normal_uvs);
+        self
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        self.nodes.first().map(|node| node.bounds).unwrap_or_else(Aabb::empty)
+    }
+
+    fn triangle_bounds(&self, triangle: usize) -> Aabb {
+        let mut bounds = Aabb::empty();
+        for vertex in self.triangles[triangle] {
+            bounds.grow(self.positions[vertex]);
+        }
+        bounds
+    }
+
+    fn build_bvh(&mut self) {
+        self.order = (0..self.triangles.len()).collect();
+        self.nodes.clear();
+        if self.triangles.is_empty() {
+            return;
+        }
+        let centres: Vec<Point3> = (0..self.triangles.len()).map(|triangle| self.triangle_bounds(triangle).centre()).collect();
+        self.nodes.push(BvhNode { bounds: Aabb::empty(), first: 0, count: 0 });
+        // Nodes still to split: (node index, first triangle, triangle count)
+        let mut pending = vec![(0, 0, self.triangles.len())];
+        while let Some((node, first, count)) = pending.pop() {
+            let mut bounds = Aabb::empty();
+            let mut centre_bounds = Aabb::empty();
+            for &triangle in &self.order[first..first + count] {
+                bounds.merge(&self.triangle_bounds(triangle));
+                centre_bounds.grow(centres[triangle]);
+            }
+            self.nodes[node].bounds = bounds;
+            if count <= LEAF_SIZE {
+                self.nodes[node].first = first;
+                self.nodes[node].count = count;
+                continue;
+            }
+
+            // Median split along the axis the triangle centres spread furthest on
+            let extent = centre_bounds.max - centre_bounds.min;
+            let axis_value = |p: &Point3| {
+                if extent.x >= extent.y && extent.x >= extent.z {
+                    p.x
+                } else if extent.y >= extent.z {
+                    p.y
+                } else {
+                    p.z
+                }
+            };
+            let half = count / 2;
+            self.order[first..first + count]
+                .select_nth_unstable_by(half, |a, b| axis_value(&centres[*a]).total_cmp(&axis_value(&centres[*b])));
+
+            let children = self.nodes.len();
+            self.nodes.push(BvhNode { bounds: Aabb::empty(), first: 0, count: 0 });
+            self.nodes.push(BvhNode { bounds: Aabb::empty(), first: 0, count: 0 });
+            self.nodes[node].first = children;
+            pending.push((children, first, half));
+            pending.push((children + 1, first + half, count - half));
+        }
+    }
+
+    // Rates of change of position with u and v across the triangle, along its first and second
+    // edges without texture coordinates (matching the barycentric ones used instead). A normal
+    // map's own coordinates take precedence, its texels are relative to them.
+    fn tangents(&self, triangle: usize) -> (RtVec3, RtVec3) {
+        let [a, b, c] = self.triangles[triangle];
+        let edge1 = self.positions[b] - self.positions[a];
+        let edge2 = self.positions[c] - self.positions[a];
+        if let Some(uvs) = self.normal_uvs.as_ref().or(self.uvs.as_ref()) {
+            let (du1, dv1) = (uvs[b].0 - uvs[a].0, uvs[b].1 - uvs[a].1);
+            let (du2, dv2) = (uvs[c].0 - uvs[a].0, uvs[c].1 - uvs[a].1);
+            let determinant = du1 * dv2 - du2 * dv1;
+            if determinant.abs() > 1e-12 {
+                let tangent = (dv2 * edge1 - dv1 * edge2) / determinant;
+                let bitangent = (du1 * edge2 - du2 * edge1) / determinant;
+                return (tangent, bitangent);
+            }
+        }
+        (edge1, edge2)
+    }
+
+    // Möller-Trumbore: distance along the ray and barycentric coordinates of the hit.
+    fn hit_triangle(&self, triangle: usize, ray: &Ray, interval: &Interval) -> Option<(f64, f64, f64)> {
+        let [a, b, c] = self.triangles[triangle];
+        let (p0, p1, p2) = (self.positions[a], self.positions[b], self.positions[c]);
+        let edge1 = p1 - p0;
+        let edge2 = p2 - p0;
+        let direction = ray.direction();
+        let pvec = direction.cross(&edge2);
+        let determinant = edge1.dot(&pvec);
+        if determinant.abs() < 1e-12 {
+            return None;
+        }
+        let inverse_determinant = 1.0 / determinant;
+        let tvec = ray.origin() - p0;
+        let u = tvec.dot(&pvec) * inverse_determinant;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let qvec = tvec.cross(&edge1);
+        let v = direction.dot(&qvec) * inverse_determinant;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = edge2.dot(&qvec) * inverse_determinant;
+        if !interval.surrounds(t) {
+            return None;
+        }
+        Some((t, u, v))
+    }
+}
+
+impl Hittable for Mesh {
+    fn hit(
+        &self,
+        ray: &Ray,
+        interval: Interval,
+        record: &mut HitRecord,
+    ) -> bool {
+        if self.nodes.is_empty() {
+            return false;
+        }
+        let origin = ray.origin();
+        let direction = ray.direction();
+        let inverse_direction = RtVec3::new(1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z);
+
+        let mut closest: Option<(usize, f64, f64, f64)> = None;
+        let mut search = Interval::new(interval.min, interval.max);
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            if !node.bounds.hit(&origin, &inverse_direction, &search) {
+                continue;
+            }
+            if node.count == 0 {
+                stack.push(node.first);
+                stack.push(node.first + 1);
+                continue;
+            }
+            for &triangle in &self.order[node.first..node.first + node.count] {
+                if let Some((t, u, v)) = self.hit_triangle(triangle, ray, &search) {
+                    search.max = t;
+                    closest = Some((triangle, t, u, v));
+                }
+            }
+        }
+
+        let Some((triangle, t, u, v)) = closest else {
+            return false;
+        };
+        let [a, b, c] = self.triangles[triangle];
+        let w = 1.0 - u - v;
+        let geometric_normal = (self.positions[b] - self.positions[a]).cross(&(self.positions[c] - self.positions[a])).unit_vector();
+
+        record.t = t;
+        record.p = ray.at(t);
+        record.set_face_normal(ray, geometric_normal);
+        if let Some(normals) = &self.normals {
+            // Smooth shading normal, kept on the side the ray arrived from
+            let shading = (w * normals[a] + u * normals[b] + v * normals[c]).unit_vector();
+            record.normal = if shading.dot(&record.normal) < 0.0 { -shading } else { shading };
+        }
+        (record.u, record.v) = match &self.uvs {
+            Some(uvs) => (
+                w * uvs[a].0 + u * uvs[b].0 + v * uvs[c].0,
+                w * uvs[a].1 + u * uvs[b].1 + v * uvs[c].1,
+            ),
+            None => (u, v),
+        };
+        record.normal_uv = self.normal_uvs.as_ref().map(|uvs| {
+            (w * uvs[a].0 + u * uvs[b].0 + v * uvs[c].0, w * uvs[a].1 + u * uvs[b].1 + v * uvs[c].1)
+        });
+        let (tangent, bitangent) = self.tangents(triangle);
+        (record.tangent, record.bitangent) = (tangent.unit_vector(), bitangent.unit_vector());
+        record.uv_lengths = (tangent.length(), bitangent.length());
+        record.vertex_color = self
+            .colors
+            .as_ref()
+            .map(|colors| Color::from_vec(w * colors[a].to_vec() + u * colors[b].to_vec() + v * colors[c].to_vec()));
+        record.material = Rc::clone(&self.material);
+        true
+    }
+
+    fn collect_materials(&self, materials: &mut Vec<Rc<dyn Material>>) {
+        materials.push(Rc::clone(&self.material));
+    }
+}
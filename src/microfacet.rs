@@ -0,0 +1,189 @@
+use crate::ray::Color;
+
+use crate::rtvec3::RtVec3;
+
+use std::f64::consts::PI;
+
+// Orthonormal shading frame: x along the tangent, y along the bitangent, z along the normal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frame {
+    pub tangent: RtVec3,
+    pub bitangent: RtVec3,
+    pub normal: RtVec3,
+}
+
+impl Frame {
+    // Builds the frame around `normal`, keeping the part of `tangent` that lies in the surface.
+    // Any perpendicular direction is used when the tangent is zero or along the normal.
+    pub fn new(normal: RtVec3, tangent: RtVec3) -> Self {
+        let normal = normal.unit_vector();
+        let mut tangent = tangent - tangent.dot(&normal) * normal;
+        if tangent.length_squared() < 1e-12 {
+            let axis = if normal.x.abs() > 0.9 { RtVec3::new(0.0, 1.0, 0.0) } else { RtVec3::new(1.0, 0.0, 0.0) };
+            tangent = axis - axis.dot(&normal) * normal;
+        }
+        let tangent = tangent.unit_vector();
+        Frame {
+            tangent,
+            bitangent: normal.cross(&tangent),
+            normal,
+        }
+    }
+
+    pub fn to_local(&self, v: RtVec3) -> RtVec3 {
+        RtVec3::new(v.dot(&self.tangent), v.dot(&self.bitangent), v.dot(&self.normal))
+    }
+
+    pub fn to_world(&self, v: RtVec3) -> RtVec3 {
+        v.x * self.tangent + v.y * self.bitangent + v.z * self.normal
+    }
+}
+
+// Trowbridge-Reitz (GGX) microfacet distribution with Smith masking-shadowing, anisotropic
+// through separate widths along the tangent (alpha_x) and bitangent (alpha_y). Directions are in
+// the local shading frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ggx {
+    pub alpha_x: f64,
+    pub alpha_y: f64,
+}
+
+impl Ggx {
+    // Perceptual roughness in [0, 1], squared to get the distribution width.
+    pub fn from_roughness(roughness_x: f64, roughness_y: f64) -> Self {
+        Ggx {
+            alpha_x: roughness_x.clamp(0.0, 1.0).powi(2),
+            alpha_y: roughness_y.clamp(0.0, 1.0).powi(2),
+        }
+    }
+
+    // Narrow enough to treat as a perfect mirror, sampling would only add float noise.
+    pub fn is_smooth(&self) -> bool {
+        self.alpha_x.max(self.alpha_y) < 1e-4
+    }
+
+    // Density of microfacet normals `h`.
+    pub fn distribution(&self, h: RtVec3) -> f64 {
+        if h.z <= 0.0 {
+            return 0.0;
+        }
+        let (x, y) = (h.x / self.alpha_x, h.y / self.alpha_y);
+        let denominator = x * x + y * y + h.z * h.z;
+        1.0 / (PI * self.alpha_x * self.alpha_y * denominator * denominator)
+    }
+
+    fn lambda(&self, w: RtVec3) -> f64 {
+        if w.z.abs() < 1e-12 {
+            return f64::INFINITY;
+        }
+        let tan2 = ((self.alpha_x * w.x).powi(2) + (self.alpha_y * w.y).powi(2)) / (w.z * w.z);
+        0.5 * ((1.0 + tan2).sqrt() - 1.0)
+    }
+
+    // Fraction of microfacets facing `w` that are visible from it.
+    pub fn masking(&self, w: RtVec3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    // Fraction visible from both directions (height-correlated).
+    pub fn masking_shadowing(&self, wo: RtVec3, wi: RtVec3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    // Samples a microfacet normal in proportion to how much of it `wo` sees (Heitz 2018).
+    // Reflecting about it gives a direction whose weight is F * G2 / G1, without D or the pdf.
+    pub fn sample_visible_normal(&self, wo: RtVec3, u1: f64, u2: f64) -> RtVec3 {
+        // Stretch to the hemisphere configuration
+        let vh = RtVec3::new(self.alpha_x * wo.x, self.alpha_y * wo.y, wo.z).unit_vector();
+        let length_squared = vh.x * vh.x + vh.y * vh.y;
+        let t1 = if length_squared > 0.0 {
+            RtVec3::new(-vh.y, vh.x, 0.0) / length_squared.sqrt()
+        } else {
+            RtVec3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = vh.cross(&t1);
+
+        // Point on the projected disk, warped towards the visible half
+        let r = u1.sqrt();
+        let phi = 2.0 * PI * u2;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z);
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * r * phi.sin();
+        let nh = p1 * t1 + p2 * t2 + (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt() * vh;
+
+        // Unstretch
+        RtVec3::new(self.alpha_x * nh.x, self.alpha_y * nh.y, nh.z.max(0.0)).unit_vector()
+    }
+}
+
+// Mirror direction of `wo` about `h`.
+pub fn reflect(wo: RtVec3, h: RtVec3) -> RtVec3 {
+    2.0 * wo.dot(&h) * h - wo
+}
+
+// Schlick's approximation for a surface reflecting `f0` head on.
+pub fn fresnel_schlick(cos_theta: f64, f0: Color) -> Color {
+    let weight = (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5);
+    let channel = |f0: f64| f0 + (1.0 - f0) * weight;
+    Color::new_rgb(channel(f0.r), channel(f0.g), channel(f0.b))
+}
+
+// Exact unpolarized Fresnel reflectance of a conductor with complex index of refraction
+// eta + i k, per color channel.
+pub fn fresnel_conductor(cos_theta: f64, eta: Color, k: Color) -> Color {
+    let cos2 = cos_theta.clamp(0.0, 1.0).powi(2);
+    let sin2 = 1.0 - cos2;
+    let channel = |eta: f64, k: f64| {
+        let t0 = eta * eta - k * k - sin2;
+        let a2_plus_b2 = (t0 * t0 + 4.0 * eta * eta * k * k).sqrt();
+        let t1 = a2_plus_b2 + cos2;
+        let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+        let t2 = 2.0 * cos_theta.clamp(0.0, 1.0) * a;
+        let rs = (t1 - t2) / (t1 + t2);
+        let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+        let t4 = t2 * sin2;
+        let rp = rs * (t3 - t4) / (t3 + t4);
+        0.5 * (rs + rp)
+    };
+    Color::new_rgb(channel(eta.r, k.r), channel(eta.g, k.g), channel(eta.b, k.b))
+}
+
+// Exact unpolarized Fresnel reflectance of a smooth boundary into a medium `eta` times as dense,
+// 1 past the critical angle.
+pub fn fresnel_dielectric(cos_theta: f64, eta: f64) -> f64 {
+    let cos_i = cos_theta.clamp(0.0, 1.0);
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let rs = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    let rp = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    0.5 * (rs * rs + rp * rp)
+}
+
+// Reflectance at `wavelength` nm of a film `thickness` nm thick with index `film_ior`, lying in
+// air over a medium with index `base_ior`. Light reflected off the top of the film and off the
+// bottom interferes, summed over every internal bounce (the Airy formula), per polarization.
+pub fn fresnel_thin_film(cos_theta: f64, wavelength: f64, thickness: f64, film_ior: f64, base_ior: f64) -> f64 {
+    let cos_0 = cos_theta.clamp(0.0, 1.0);
+    let sin2_0 = 1.0 - cos_0 * cos_0;
+    let cos_1 = (1.0 - sin2_0 / (film_ior * film_ior)).max(0.0).sqrt();
+    let sin2_2 = sin2_0 / (base_ior * base_ior);
+    if sin2_2 >= 1.0 {
+        return 1.0;
+    }
+    let cos_2 = (1.0 - sin2_2).sqrt();
+
+    // Phase the bottom reflection picks up crossing the film twice
+    let phase = 4.0 * std::f64::consts::PI * film_ior * thickness * cos_1 / wavelength;
+    let airy = |r01: f64, r12: f64| {
+        let cross = 2.0 * r01 * r12 * phase.cos();
+        (r01 * r01 + r12 * r12 + cross) / (1.0 + r01 * r01 * r12 * r12 + cross)
+    };
+    let s = |n_i: f64, cos_i: f64, n_t: f64, cos_t: f64| (n_i * cos_i - n_t * cos_t) / (n_i * cos_i + n_t * cos_t);
+    let p = |n_i: f64, cos_i: f64, n_t: f64, cos_t: f64| (n_t * cos_i - n_i * cos_t) / (n_t * cos_i + n_i * cos_t);
+    let reflectance_s = airy(s(1.0, cos_0, film_ior, cos_1), s(film_ior, cos_1, base_ior, cos_2));
+    let reflectance_p = airy(p(1.0, cos_0, film_ior, cos_1), p(film_ior, cos_1, base_ior, cos_2));
+    (0.5 * (reflectance_s + reflectance_p)).clamp(0.0, 1.0)
+}
@@ -0,0 +1,90 @@
+use crate::rtvec3::{Point3, RtVec3};
+
+use crate::random_float;
+
+const POINT_COUNT: usize = 256;
+
+// Perlin noise: random gradients on a lattice, smoothly interpolated between lattice points.
+// Built from the shared random generator, so a seeded scene always gets the same noise.
+pub struct Perlin {
+    gradients: Vec<RtVec3>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Default for Perlin {
+    fn default() -> Self {
+        Perlin::new()
+    }
+}
+
+impl Perlin {
+    pub fn new() -> Self {
+        let gradients = (0..POINT_COUNT)
+            .map(|_| RtVec3::new(2.0 * random_float() - 1.0, 2.0 * random_float() - 1.0, 2.0 * random_float() - 1.0).unit_vector())
+            .collect();
+        Perlin {
+            gradients,
+            perm_x: Perlin::generate_perm(),
+            perm_y: Perlin::generate_perm(),
+            perm_z: Perlin::generate_perm(),
+        }
+    }
+
+    fn generate_perm() -> Vec<usize> {
+        let mut perm: Vec<usize> = (0..POINT_COUNT).collect();
+        // Fisher-Yates shuffle
+        for i in (1..POINT_COUNT).rev() {
+            let target = ((random_float() * (i + 1) as f64) as usize).min(i);
+            perm.swap(i, target);
+        }
+        perm
+    }
+
+    // Noise in roughly [-1, 1].
+    pub fn noise(&self, p: &Point3) -> f64 {
+        let u = p.x - p.x.floor();
+        let v = p.y - p.y.floor();
+        let w = p.z - p.z.floor();
+        let i = p.x.floor() as i64;
+        let j = p.y.floor() as i64;
+        let k = p.z.floor() as i64;
+
+        // Hermite smoothing hides the lattice
+        let uu = u * u * (3.0 - 2.0 * u);
+        let vv = v * v * (3.0 - 2.0 * v);
+        let ww = w * w * (3.0 - 2.0 * w);
+
+        let mut accumulated = 0.0;
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let index = self.perm_x[((i + di) & 255) as usize]
+                        ^ self.perm_y[((j + dj) & 255) as usize]
+                        ^ self.perm_z[((k + dk) & 255) as usize];
+                    let (fi, fj, fk) = (di as f64, dj as f64, dk as f64);
+                    let weight = RtVec3::new(u - fi, v - fj, w - fk);
+                    accumulated += (fi * uu + (1.0 - fi) * (1.0 - uu))
+                        * (fj * vv + (1.0 - fj) * (1.0 - vv))
+                        * (fk * ww + (1.0 - fk) * (1.0 - ww))
+                        * self.gradients[index].dot(&weight);
+                }
+            }
+        }
+        accumulated
+    }
+
+    // Sum of `depth` octaves of noise, each at twice the frequency and half the weight.
+    pub fn turbulence(&self, p: &Point3, depth: u32) -> f64 {
+        let mut accumulated = 0.0;
+        let mut point = *p;
+        let mut weight = 1.0;
+        for _ in 0..depth {
+            accumulated += weight * self.noise(&point);
+            weight *= 0.5;
+            point = point * 2.0;
+        }
+        accumulated.abs()
+    }
+}
@@ -0,0 +1,241 @@
+use crate::material::Material;
+
+use crate::mesh::Mesh;
+
+use crate::ray::Color;
+
+use crate::rtvec3::{Point3, RtVec3};
+
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+use std::rc::Rc;
+
+// Loads an ASCII or binary little-endian PLY file as a mesh with one material.
+//
+// Vertices need x, y and z; normals (nx, ny, nz), texture coordinates (u, v or s, t) and colors
+// (red, green, blue, 0-255 for integers or 0-1 for floats) are used when present. Faces with more
+// than three vertices are split into a triangle fan. Other elements are skipped.
+pub fn load_ply(path: &Path, material: Rc<dyn Material>) -> std::io::Result<Mesh> {
+    let bytes = fs::read(path)?;
+    let invalid = |message: &str| Error::new(ErrorKind::InvalidData, format!("{:?}: {}", path, message));
+    let (header, body) = parse_header(&bytes).map_err(|message| invalid(&message))?;
+    let mut reader = match header.format {
+        PlyFormat::Ascii => Reader::Ascii(String::from_utf8_lossy(body).split_ascii_whitespace().map(String::from).collect(), 0),
+        PlyFormat::BinaryLittleEndian => Reader::Binary(body, 0),
+    };
+
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut colors = Vec::new();
+    let mut triangles = Vec::new();
+    for element in &header.elements {
+        for _ in 0..element.count {
+            let mut values: Vec<(&str, Vec<f64>)> = Vec::with_capacity(element.properties.len());
+            for property in &element.properties {
+                let read = match property.list_count {
+                    Some(count_type) => {
+                        let count = reader.read(count_type).ok_or_else(|| invalid("truncated element data"))?;
+                        (0..count as usize).map(|_| reader.read(property.kind)).collect::<Option<Vec<f64>>>()
+                    }
+                    None => reader.read(property.kind).map(|value| vec![value]),
+                };
+                values.push((property.name.as_str(), read.ok_or_else(|| invalid("truncated element data"))?));
+            }
+            let scalar = |names: &[&str]| {
+                values.iter().find(|(name, _)| names.contains(name)).and_then(|(_, value)| value.first().copied())
+            };
+
+            match element.name.as_str() {
+                "vertex" => {
+                    let (Some(x), Some(y), Some(z)) = (scalar(&["x"]), scalar(&["y"]), scalar(&["z"])) else {
+                        return Err(invalid("vertex without x, y and z"));
+                    };
+                    positions.push(Point3::new(x, y, z));
+                    if let (Some(x), Some(y), Some(z)) = (scalar(&["nx"]), scalar(&["ny"]), scalar(&["nz"])) {
+                        normals.push(RtVec3::new(x, y, z).unit_vector());
+                    }
+                    if let (Some(u), Some(v)) = (scalar(&["u", "s", "texture_u"]), scalar(&["v", "t", "texture_v"])) {
+                        uvs.push((u, v));
+                    }
+                    if let (Some(r), Some(g), Some(b)) = (scalar(&["red"]), scalar(&["green"]), scalar(&["blue"])) {
+                        let scale = match header.vertex_property("red").map(|property| property.kind) {
+                            Some(PlyType::Float32 | PlyType::Float64) => 1.0,
+                            _ => 1.0 / 255.0,
+                        };
+                        colors.push(Color::new_rgb(r * scale, g * scale, b * scale));
+                    }
+                }
+                "face" => {
+                    let Some((_, indices)) = values.iter().find(|(name, _)| *name == "vertex_indices" || *name == "vertex_index") else {
+                        return Err(invalid("face without vertex_indices"));
+                    };
+                    for k in 1..indices.len().saturating_sub(1) {
+                        triangles.push([indices[0] as usize, indices[k] as usize, indices[k + 1] as usize]);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if triangles.iter().flatten().any(|&index| index >= positions.len()) {
+        return Err(invalid("face refers to a missing vertex"));
+    }
+    // Attributes only count when every vertex has them
+    let complete = |count: usize| count == positions.len();
+    let normals = complete(normals.len()).then_some(normals);
+    let uvs = complete(uvs.len()).then_some(uvs);
+    let colors = complete(colors.len()).then_some(colors);
+    let mesh = Mesh::new(positions, normals, uvs, triangles, material);
+    Ok(match colors {
+        Some(colors) => mesh.with_colors(colors),
+        None => mesh,
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PlyFormat {
+    Ascii,
+    BinaryLittleEndian,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PlyType {
+    Int8,
+    Uint8,
+    Int16,
+    Uint16,
+    Int32,
+    Uint32,
+    Float32,
+    Float64,
+}
+
+impl PlyType {
+    fn from_name(name: &str) -> Option<PlyType> {
+        match name {
+            "char" | "int8" => Some(PlyType::Int8),
+            "uchar" | "uint8" => Some(PlyType::Uint8),
+            "short" | "int16" => Some(PlyType::Int16),
+            "ushort" | "uint16" => Some(PlyType::Uint16),
+            "int" | "int32" => Some(PlyType::Int32),
+            "uint" | "uint32" => Some(PlyType::Uint32),
+            "float" | "float32" => Some(PlyType::Float32),
+            "double" | "float64" => Some(PlyType::Float64),
+            _ => None,
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            PlyType::Int8 | PlyType::Uint8 => 1,
+            PlyType::Int16 | PlyType::Uint16 => 2,
+            PlyType::Int32 | PlyType::Uint32 | PlyType::Float32 => 4,
+            PlyType::Float64 => 8,
+        }
+    }
+}
+
+struct Property {
+    name: String,
+    kind: PlyType,
+    list_count: Option<PlyType>, // Type of the length prefix for list properties
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+struct Header {
+    format: PlyFormat,
+    elements: Vec<Element>,
+}
+
+impl Header {
+    fn vertex_property(&self, name: &str) -> Option<&Property> {
+        self.elements
+            .iter()
+            .find(|element| element.name == "vertex")
+            .and_then(|element| element.properties.iter().find(|property| property.name == name))
+    }
+}
+
+// Splits the file into its header and the element data following "end_header".
+fn parse_header(bytes: &[u8]) -> Result<(Header, &[u8]), String> {
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut position = 0;
+    let mut first = true;
+    loop {
+        let end = bytes[position..].iter().position(|&byte| byte == b'\n').ok_or("missing end_header")?;
+        let line = String::from_utf8_lossy(&bytes[position..position + end]).trim().to_string();
+        position += end + 1;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if first {
+            if line != "ply" {
+                return Err("not a PLY file".to_string());
+            }
+            first = false;
+            continue;
+        }
+        let type_of = |name: &str| PlyType::from_name(name).ok_or(format!("unknown property type \"{}\"", name));
+        match words.as_slice() {
+            ["format", "ascii", _] => format = Some(PlyFormat::Ascii),
+            ["format", "binary_little_endian", _] => format = Some(PlyFormat::BinaryLittleEndian),
+            ["format", other, ..] => return Err(format!("unsupported format \"{}\"", other)),
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count.parse().map_err(|_| format!("bad element count \"{}\"", count))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count_type, kind, name] => {
+                let property = Property { name: name.to_string(), kind: type_of(kind)?, list_count: Some(type_of(count_type)?) };
+                elements.last_mut().ok_or("property before any element")?.properties.push(property);
+            }
+            ["property", kind, name] => {
+                let property = Property { name: name.to_string(), kind: type_of(kind)?, list_count: None };
+                elements.last_mut().ok_or("property before any element")?.properties.push(property);
+            }
+            ["end_header"] => break,
+            ["comment", ..] | ["obj_info", ..] | [] => {}
+            _ => return Err(format!("unexpected header line \"{}\"", line)),
+        }
+    }
+    let format = format.ok_or("missing format line")?;
+    Ok((Header { format, elements }, &bytes[position..]))
+}
+
+enum Reader<'a> {
+    Ascii(Vec<String>, usize),
+    Binary(&'a [u8], usize),
+}
+
+impl Reader<'_> {
+    fn read(&mut self, kind: PlyType) -> Option<f64> {
+        match self {
+            Reader::Ascii(tokens, position) => {
+                let value = tokens.get(*position)?.parse().ok()?;
+                *position += 1;
+                Some(value)
+            }
+            Reader::Binary(bytes, position) => {
+                let data = bytes.get(*position..*position + kind.size())?;
+                *position += kind.size();
+                Some(match kind {
+                    PlyType::Int8 => data[0] as i8 as f64,
+                    PlyType::Uint8 => data[0] as f64,
+                    PlyType::Int16 => i16::from_le_bytes([data[0], data[1]]) as f64,
+                    PlyType::Uint16 => u16::from_le_bytes([data[0], data[1]]) as f64,
+                    PlyType::Int32 => i32::from_le_bytes([data[0], data[1], data[2], data[3]]) as f64,
+                    PlyType::Uint32 => u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as f64,
+                    PlyType::Float32 => f32::from_le_bytes([data[0], data[1], data[2], data[3]]) as f64,
+                    PlyType::Float64 => f64::from_le_bytes(data.try_into().ok()?),
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use crate::degrees_to_radians;
+
+use crate::rtvec3::RtVec3;
+
+use std::f64::consts::PI;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FisheyeMapping {
+    Equidistant, // Image radius grows linearly with the angle off axis
+    Equisolid,   // Equal areas on the image cover equal solid angles
+}
+
+// How image positions map to camera rays. Every projection works in the camera's own
+// u (right), v (up), w (backwards) frame built from lookfrom, lookat and vup.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Projection {
+    // Thin lens perspective driven by the camera fov, defocus angle and focus distance
+    #[default]
+    Perspective,
+    // Parallel rays, `height` is the world space height of the view
+    Orthographic { height: f64 },
+    // Circular image covering `fov` degrees, pixels outside the circle stay black
+    Fisheye { fov: f64, mapping: FisheyeMapping },
+    // Full 360 x 180 degree latitude / longitude panorama, best rendered at a 2:1 aspect ratio
+    Equirectangular,
+    // Six 90 degree faces laid out 3 x 2: right, left, up / down, front, back
+    Cubemap,
+}
+
+impl Projection {
+    // Ray origin offset and direction in camera space (x right, y up, z forward) for normalized
+    // image coordinates s, t in [0, 1] measured from the top left corner. `aspect_ratio` is the
+    // image width over height. `None` means the position isn't covered by the projection.
+    // Perspective rays are built by the camera itself.
+    pub fn local_ray(&self, s: f64, t: f64, aspect_ratio: f64) -> Option<(RtVec3, RtVec3)> {
+        match *self {
+            Projection::Perspective => None,
+            Projection::Orthographic { height } => {
+                let offset = RtVec3::new((s - 0.5) * height * aspect_ratio, (0.5 - t) * height, 0.0);
+                Some((offset, RtVec3::new(0.0, 0.0, 1.0)))
+            }
+            Projection::Fisheye { fov, mapping } => {
+                // Image circle inscribed in the shorter side of the frame
+                let (scale_x, scale_y) = if aspect_ratio >= 1.0 { (aspect_ratio, 1.0) } else { (1.0, 1.0 / aspect_ratio) };
+                let x = (2.0 * s - 1.0) * scale_x;
+                let y = (1.0 - 2.0 * t) * scale_y;
+                let r = f64::sqrt(x * x + y * y);
+                if r > 1.0 {
+                    return None;
+                }
+                let theta_max = degrees_to_radians(fov / 2.0);
+                let theta = match mapping {
+                    FisheyeMapping::Equidistant => r * theta_max,
+                    FisheyeMapping::Equisolid => 2.0 * f64::asin(r * f64::sin(theta_max / 2.0)),
+                };
+                let phi = f64::atan2(y, x);
+                let direction = RtVec3::new(
+                    f64::sin(theta) * f64::cos(phi),
+                    f64::sin(theta) * f64::sin(phi),
+                    f64::cos(theta),
+                );
+                Some((RtVec3::new(0.0, 0.0, 0.0), direction))
+            }
+            Projection::Equirectangular => {
+                let longitude = (s - 0.5) * 2.0 * PI;
+                let latitude = (0.5 - t) * PI;
+                let direction = RtVec3::new(
+                    f64::cos(latitude) * f64::sin(longitude),
+                    f64::sin(latitude),
+                    f64::cos(latitude) * f64::cos(longitude),
+                );
+                Some((RtVec3::new(0.0, 0.0, 0.0), direction))
+            }
+            Projection::Cubemap => {
+                let column = f64::min((s * 3.0).floor(), 2.0);
+                let row = f64::min((t * 2.0).floor(), 1.0);
+                // Position on the face in [-1, 1], a to the right and b downwards
+                let a = (s * 3.0 - column) * 2.0 - 1.0;
+                let b = (t * 2.0 - row) * 2.0 - 1.0;
+                let (forward, right, up) = match (row as u32, column as u32) {
+                    (0, 0) => (RtVec3::new(1.0, 0.0, 0.0), RtVec3::new(0.0, 0.0, -1.0), RtVec3::new(0.0, 1.0, 0.0)),  // Right
+                    (0, 1) => (RtVec3::new(-1.0, 0.0, 0.0), RtVec3::new(0.0, 0.0, 1.0), RtVec3::new(0.0, 1.0, 0.0)),  // Left
+                    (0, _) => (RtVec3::new(0.0, 1.0, 0.0), RtVec3::new(1.0, 0.0, 0.0), RtVec3::new(0.0, 0.0, -1.0)),  // Up
+                    (_, 0) => (RtVec3::new(0.0, -1.0, 0.0), RtVec3::new(1.0, 0.0, 0.0), RtVec3::new(0.0, 0.0, 1.0)),  // Down
+                    (_, 1) => (RtVec3::new(0.0, 0.0, 1.0), RtVec3::new(1.0, 0.0, 0.0), RtVec3::new(0.0, 1.0, 0.0)),   // Front
+                    (_, _) => (RtVec3::new(0.0, 0.0, -1.0), RtVec3::new(-1.0, 0.0, 0.0), RtVec3::new(0.0, 1.0, 0.0)), // Back
+                };
+                Some((RtVec3::new(0.0, 0.0, 0.0), forward + a * right - b * up))
+            }
+        }
+    }
+}
@@ -1,194 +1,266 @@
-use crate::Interval;
-
-use crate::hit::{Hittable, HittableList, HitRecord};
-
-use crate::material::default_material_lambertian;
-
-use crate::rtvec3::{RtVec3, Point3};
-
-use crate::random_float_range;
-
-use std::fs::File;
-use std::io::Write;
-use std::ops::Mul;
-use std::rc::Rc;
-
-#[derive(Clone, Copy, Debug)]
-pub struct Ray {
-    pub origin: Point3,
-    pub direction: RtVec3,
-}
-
-impl Ray {
-    pub fn new(origin: Point3, direction: RtVec3) -> Self {
-        Ray {
-            origin,
-            direction,
-        }
-    }
-    
-    // getter functions
-    pub fn origin(&self) -> Point3 {
-        self.origin
-    }
-
-    pub fn direction(&self) -> RtVec3 {
-        self.direction
-    }
-
-    pub fn at(&self, t: f64) -> RtVec3 {
-        self.origin + t * self.direction
-    }
-}
-
-#[derive(Clone, Copy, Debug)]
-pub struct Color {
-    pub r: f64,
-    pub g: f64,
-    pub b: f64,
-}
-
-impl Color {
-    pub fn random() -> Self {
-        let r: f64 = random_float_range(Interval::new(0.0, 1.0));
-        let g: f64 = random_float_range(Interval::new(0.0, 1.0));
-        let b: f64 = random_float_range(Interval::new(0.0, 1.0));
-        Color {
-            r,
-            g,
-            b,
-        }
-    }
-    
-    pub fn random_range(interval: Interval) -> Self {
-        let r: f64 = random_float_range(interval.clone());
-        let g: f64 = random_float_range(interval.clone());
-        let b: f64 = random_float_range(interval.clone());
-        Color {
-            r,
-            g,
-            b,
-        }
-    }
-    
-    pub fn new_rgb(r: f64, g: f64, b: f64) -> Self {
-        Color {
-            r,
-            g,
-            b,
-        }
-    }
-
-    pub fn from_vec(color: RtVec3) -> Self {
-        Color {
-            r: color.x(),
-            g: color.y(),
-            b: color.z(),
-        }
-    }
-}
-
-impl Mul<RtVec3> for Color {
-    type Output = RtVec3;
-
-    fn mul(self, other: RtVec3) -> RtVec3 {
-        RtVec3 {
-            x: self.r * other.x,
-            y: self.g * other.y,
-            z: self.b * other.z,
-        }
-    }
-}
-
-impl Mul<Color> for Color {
-    type Output = Color;
-
-    fn mul(self, other: Color) -> Color {
-        Color {
-            r: self.r * other.r,
-            g: self.g * other.g,
-            b: self.b * other.b,
-        }
-    }
-}
-
-pub fn hit_sphere(
-    center: Point3,
-    radius: f64,
-    ray: Ray,
-) -> f64 {
-    // Compute the vector from the ray's origin to the sphere center.
-    let oc = center - ray.origin();
-
-    // Quadratic formula coefficients.
-    let a = ray.direction().length_squared();
-    let h = ray.direction().dot(&oc);
-    let c = oc.length_squared()- radius * radius;
-
-    // Discriminant calculation.
-    let discriminant = h * h - a * c;
-
-    if discriminant < 0.0 {
-        return -1.0;
-    } else {
-        return (h - discriminant.sqrt()) / a;
-    }
-}
-
-pub fn color(
-    ray: Ray,
-    world: &HittableList,
-    sample_bounce_max: u32,
-) -> RtVec3 {
-    if sample_bounce_max <= 0 {
-        return RtVec3::new(0.0, 0.0, 0.0);
-    }
-    // let reflectance = 0.5;
-    let default_material = default_material_lambertian();
-    let mut record: HitRecord = HitRecord::new(
-        RtVec3::new(0.0, 0.0, 0.0),
-        RtVec3::new(0.0, 0.0, 0.0),
-        0.0,
-        false,
-        Rc::clone(&default_material),
-    );
-    if world.hit(&ray, Interval::new(0.001, f64::INFINITY), &mut record) {
-        if let Some((attenuation, scattered)) = record.material.clone().scatter(ray, record) {
-            return attenuation * color(scattered, world, sample_bounce_max - 1);
-        }
-        return RtVec3::new(0.0, 0.0, 0.0);
-    }
-    let unit_direction = ray.direction().unit_vector();
-    let a = 0.5 * (unit_direction.y() + 1.0);
-    let lerp_res = (1.0 - a) * RtVec3::new(1.0, 1.0, 1.0) + a * RtVec3::new(0.5, 0.7, 1.0);
-    lerp_res
-}
-
-// corrects colors to consider gamma space alterations
-pub fn linear_to_gamma(linear_component: f64) -> f64
-{
-    if linear_component > 0.0 {
-        return linear_component.sqrt();
-    }
-    return 0.0;
-}
-
-pub fn write_color_to_pixel(
-    color: RtVec3,
-    file: &mut File,
-) -> std::io::Result<()> {
-    // Pixel Algo
-    let r = linear_to_gamma(color.x());
-    let g = linear_to_gamma(color.y());
-    let b = linear_to_gamma(color.z());
-
-    let intensity: Interval = Interval::new(0.0, 0.999);
-    let ir: u32 = (255.999 * intensity.clamp(r)) as u32;
-    let ig: u32 = (255.999 * intensity.clamp(g)) as u32;
-    let ib: u32 = (255.999 * intensity.clamp(b)) as u32;
-
-    let pixel_triplets = format!("{} {} {} \n",ir , ig, ib);
-    file.write_all(pixel_triplets.as_bytes())?;
-    
-    Ok(())
-}
\ No newline at end of file
+use crate::Interval;
+
+use crate::hit::{Hittable, HittableList, HitRecord};
+
+use crate::material::default_material_lambertian;
+
+use crate::rtvec3::{RtVec3, Point3};
+
+use crate::spectrum::{rgb_to_spectrum, SampledWavelengths, WAVELENGTH_SAMPLES};
+
+use crate::tonemap::ToneMapper;
+
+use crate::random_float_range;
+
+use std::io::Write;
+use std::ops::Mul;
+use std::rc::Rc;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Ray {
+    pub origin: Point3,
+    pub direction: RtVec3,
+}
+
+impl Ray {
+    pub fn new(origin: Point3, direction: RtVec3) -> Self {
+        Ray {
+            origin,
+            direction,
+        }
+    }
+    
+    // getter functions
+    pub fn origin(&self) -> Point3 {
+        self.origin
+    }
+
+    pub fn direction(&self) -> RtVec3 {
+        self.direction
+    }
+
+    pub fn at(&self, t: f64) -> RtVec3 {
+        self.origin + t * self.direction
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Color {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+}
+
+impl Color {
+    pub fn random() -> Self {
+        let r: f64 = random_float_range(Interval::new(0.0, 1.0));
+        let g: f64 = random_float_range(Interval::new(0.0, 1.0));
+        let b: f64 = random_float_range(Interval::new(0.0, 1.0));
+        Color {
+            r,
+            g,
+            b,
+        }
+    }
+    
+    pub fn random_range(interval: Interval) -> Self {
+        let r: f64 = random_float_range(interval.clone());
+        let g: f64 = random_float_range(interval.clone());
+        let b: f64 = random_float_range(interval.clone());
+        Color {
+            r,
+            g,
+            b,
+        }
+    }
+    
+    pub fn new_rgb(r: f64, g: f64, b: f64) -> Self {
+        Color {
+            r,
+            g,
+            b,
+        }
+    }
+
+    pub fn from_vec(color: RtVec3) -> Self {
+        Color {
+            r: color.x(),
+            g: color.y(),
+            b: color.z(),
+        }
+    }
+
+    pub fn to_vec(self) -> RtVec3 {
+        RtVec3::new(self.r, self.g, self.b)
+    }
+}
+
+// What rays that leave the scene see.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Background {
+    // White at the horizon blending to light blue overhead
+    #[default]
+    Sky,
+    // One color all around, black for scenes lit only by their lights
+    Solid(Color),
+}
+
+impl Background {
+    pub fn value(&self, direction: RtVec3) -> RtVec3 {
+        match self {
+            Background::Sky => {
+                let unit_direction = direction.unit_vector();
+                let a = 0.5 * (unit_direction.y() + 1.0);
+                (1.0 - a) * RtVec3::new(1.0, 1.0, 1.0) + a * RtVec3::new(0.5, 0.7, 1.0)
+            }
+            Background::Solid(color) => color.to_vec(),
+        }
+    }
+}
+
+impl Mul<RtVec3> for Color {
+    type Output = RtVec3;
+
+    fn mul(self, other: RtVec3) -> RtVec3 {
+        RtVec3 {
+            x: self.r * other.x,
+            y: self.g * other.y,
+            z: self.b * other.z,
+        }
+    }
+}
+
+impl Mul<Color> for Color {
+    type Output = Color;
+
+    fn mul(self, other: Color) -> Color {
+        Color {
+            r: self.r * other.r,
+            g: self.g * other.g,
+            b: self.b * other.b,
+        }
+    }
+}
+
+pub fn hit_sphere(
+    center: Point3,
+    radius: f64,
+    ray: Ray,
+) -> f64 {
+    // Compute the vector from the ray's origin to the sphere center.
+    let oc = center - ray.origin();
+
+    // Quadratic formula coefficients.
+    let a = ray.direction().length_squared();
+    let h = ray.direction().dot(&oc);
+    let c = oc.length_squared()- radius * radius;
+
+    // Discriminant calculation.
+    let discriminant = h * h - a * c;
+
+    if discriminant < 0.0 {
+        -1.0
+    } else {
+        (h - discriminant.sqrt()) / a
+    }
+}
+
+pub fn color(
+    ray: Ray,
+    world: &HittableList,
+    sample_bounce_max: u32,
+    background: &Background,
+) -> RtVec3 {
+    if sample_bounce_max == 0 {
+        return RtVec3::new(0.0, 0.0, 0.0);
+    }
+    // let reflectance = 0.5;
+    let default_material = default_material_lambertian();
+    let mut record: HitRecord = HitRecord::new(
+        RtVec3::new(0.0, 0.0, 0.0),
+        RtVec3::new(0.0, 0.0, 0.0),
+        0.0,
+        false,
+        Rc::clone(&default_material),
+    );
+    if world.hit(&ray, Interval::new(0.001, f64::INFINITY), &mut record) {
+        let emitted = record.material.emitted(&record).to_vec();
+        if let Some((attenuation, scattered)) = record.material.clone().scatter(ray, record) {
+            return emitted + attenuation * color(scattered, world, sample_bounce_max - 1, background);
+        }
+        return emitted;
+    }
+    background.value(ray.direction())
+}
+
+// Radiance along `ray` at each of the path's wavelengths, the spectral counterpart of `color`.
+// Material colors, lights and the background are turned into spectra where they're met.
+pub fn color_spectral(
+    ray: Ray,
+    world: &HittableList,
+    sample_bounce_max: u32,
+    background: &Background,
+    wavelengths: &mut SampledWavelengths,
+) -> [f64; WAVELENGTH_SAMPLES] {
+    let mut radiance = [0.0; WAVELENGTH_SAMPLES];
+    if sample_bounce_max == 0 {
+        return radiance;
+    }
+    let default_material = default_material_lambertian();
+    let mut record: HitRecord = HitRecord::new(
+        RtVec3::new(0.0, 0.0, 0.0),
+        RtVec3::new(0.0, 0.0, 0.0),
+        0.0,
+        false,
+        Rc::clone(&default_material),
+    );
+    if !world.hit(&ray, Interval::new(0.001, f64::INFINITY), &mut record) {
+        let sky = Color::from_vec(background.value(ray.direction()));
+        for (value, wavelength) in radiance.iter_mut().zip(wavelengths.lambda) {
+            *value = rgb_to_spectrum(sky, wavelength);
+        }
+        return radiance;
+    }
+    let emitted = record.material.emitted(&record);
+    let scattered = record.material.clone().scatter_spectral(ray, record, wavelengths);
+    let incoming = match &scattered {
+        Some((_, scattered)) => color_spectral(*scattered, world, sample_bounce_max - 1, background, wavelengths),
+        None => radiance,
+    };
+    for (index, value) in radiance.iter_mut().enumerate() {
+        let wavelength = wavelengths.lambda[index];
+        *value = rgb_to_spectrum(emitted, wavelength);
+        if let Some((attenuation, _)) = &scattered {
+            *value += rgb_to_spectrum(*attenuation, wavelength) * incoming[index];
+        }
+    }
+    radiance
+}
+
+// Tone mapped 8 bit display value of a linear radiance.
+pub fn color_to_rgb8(
+    color: RtVec3,
+    tone_mapper: &ToneMapper,
+) -> [u8; 3] {
+    let display = tone_mapper.map(color);
+
+    let intensity: Interval = Interval::new(0.0, 1.0);
+    let ir: u8 = (255.999 * intensity.clamp(display.x())) as u8;
+    let ig: u8 = (255.999 * intensity.clamp(display.y())) as u8;
+    let ib: u8 = (255.999 * intensity.clamp(display.z())) as u8;
+    [ir, ig, ib]
+}
+
+pub fn write_color_to_pixel(
+    color: RtVec3,
+    tone_mapper: &ToneMapper,
+    file: &mut impl Write,
+) -> std::io::Result<()> {
+    // Pixel Algo
+    let [ir, ig, ib] = color_to_rgb8(color, tone_mapper);
+
+    let pixel_triplets = format!("{} {} {} \n",ir , ig, ib);
+    file.write_all(pixel_triplets.as_bytes())?;
+    
+    Ok(())
+}
@@ -0,0 +1,803 @@
+use crate::camera::Camera;
+
+use crate::hit::{Hittable, HittableList, Quad, RotateY, Sphere, Translate};
+
+use crate::lens::Aperture;
+
+use crate::material::{Dielectric, Material, Subsurface};
+use crate::material::{
+    new_material_diffuse_light_color, new_material_lambertian_color, new_material_lambertian_texture,
+    new_material_metal_color, new_material_oren_nayar, new_material_principled, new_material_velvet,
+    PrincipledParameters,
+};
+
+use crate::ray::{Background, Color};
+
+use crate::rtvec3::{Point3, RtVec3};
+
+use crate::spectrum::{Dispersion, D_LINE};
+
+use crate::texture::{CheckerTexture, ImageTexture, NoiseTexture, Texture};
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+// Scene files
+//
+// Scenes can be written by hand in a small subset of TOML: a [camera] table, one
+// [material.<name>] table per material and one [[object]] table per object, referring to its
+// material by name. Values are numbers, "strings" or [x, y, z] lists, # starts a comment.
+//
+//     [camera]
+//     fov = 20
+//     lookfrom = [13, 2, 3]
+//     lookat = [0, 0, 0]
+//
+//     [material.ground]
+//     type = "lambertian"
+//     albedo = [0.5, 0.5, 0.5]
+//
+//     [[object]]
+//     type = "sphere"
+//     center = [0, -1000, 0]
+//     radius = 1000
+//     material = "ground"
+//
+// Objects are spheres or quads (corner `q`, edges `u` and `v`), any of them turned by
+// `rotate_y` degrees and then moved by `translate`. Lambertian materials take a `texture`
+// ("checker", "noise" or "image") instead of an albedo, and "light" materials `emit` light.
+//
+// Image size, sample count and bounce limit are render settings and stay on the command line.
+
+// Where the camera is and how it sees, everything in [camera] is optional.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CameraSettings {
+    pub aspect_ratio: f64,
+    pub fov: f64,
+    pub lookfrom: Point3,
+    pub lookat: Point3,
+    pub vup: RtVec3,
+    pub defocus_angle: f64,
+    pub focus_dist: f64,
+    // Circular, or polygonal with aperture_blades and aperture_rotation set. Image masks can't
+    // be stored in scene files.
+    pub aperture: Aperture,
+    // The sky gradient unless `background = [r, g, b]` is given
+    pub background: Background,
+}
+
+impl CameraSettings {
+    pub fn build_camera(&self, world: HittableList, image_width: u32, samples_per_pixel: u32, sample_bounce_max: u32) -> Camera {
+        Camera::new(
+            world,
+            Color::new_rgb(0.0, 0.0, 0.0),
+            self.aspect_ratio,
+            self.fov,
+            self.defocus_angle,
+            self.focus_dist,
+            self.lookfrom,
+            self.lookat,
+            self.vup,
+            image_width,
+            samples_per_pixel,
+            sample_bounce_max,
+        )
+        .with_aperture(self.aperture.clone())
+        .with_background(self.background)
+    }
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        CameraSettings {
+            aspect_ratio: 16.0 / 9.0,
+            fov: 90.0,
+            lookfrom: Point3::new(0.0, 0.0, 0.0),
+            lookat: Point3::new(0.0, 0.0, -1.0),
+            vup: RtVec3::new(0.0, 1.0, 0.0),
+            defocus_angle: 0.0,
+            focus_dist: 1.0,
+            aperture: Aperture::Circular,
+            background: Background::Sky,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MaterialDescription {
+    Lambertian { albedo: Color },
+    OrenNayar { albedo: Color, sigma: f64 },
+    Velvet { albedo: Color, sheen: Color, roughness: f64 },
+    Metal { albedo: Color, roughness: f64 },
+    Dielectric { albedo: Color, refraction_index: f64, absorption: Color, dispersion: Option<Dispersion> },
+    Principled { base_color: Color, parameters: PrincipledParameters },
+    Subsurface { albedo: Color, mean_free_path: Color, refraction_index: f64, anisotropy: f64 },
+    LambertianTexture { texture: TextureDescription },
+    DiffuseLight { emit: Color },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TextureDescription {
+    Checker { scale: f64, even: Color, odd: Color },
+    Noise { scale: f64 },
+    Image { path: PathBuf },
+}
+
+impl TextureDescription {
+    fn build(&self) -> Rc<dyn Texture> {
+        match self {
+            TextureDescription::Checker { scale, even, odd } => Rc::new(CheckerTexture::from_colors(*scale, *even, *odd)),
+            TextureDescription::Noise { scale } => Rc::new(NoiseTexture::new(*scale)),
+            TextureDescription::Image { path } => Rc::new(ImageTexture::load(path)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ObjectDescription {
+    Sphere { center: Point3, radius: f64, material: String },
+    Quad { q: Point3, u: RtVec3, v: RtVec3, material: String },
+    // A sphere or quad turned about the y axis by `rotate_y` degrees, then moved by `translate`
+    Instance { object: Box<ObjectDescription>, rotate_y: f64, translate: RtVec3 },
+}
+
+impl ObjectDescription {
+    pub fn material(&self) -> &str {
+        match self {
+            ObjectDescription::Sphere { material, .. } | ObjectDescription::Quad { material, .. } => material,
+            ObjectDescription::Instance { object, .. } => object.material(),
+        }
+    }
+
+    fn build(&self, materials: &HashMap<&str, Rc<dyn Material>>) -> Rc<dyn Hittable> {
+        match self {
+            ObjectDescription::Sphere { center, radius, material } => {
+                Rc::new(Sphere::new(*center, *radius, Rc::clone(&materials[material.as_str()])))
+            }
+            ObjectDescription::Quad { q, u, v, material } => Rc::new(Quad::new(*q, *u, *v, Rc::clone(&materials[material.as_str()]))),
+            ObjectDescription::Instance { object, rotate_y, translate } => {
+                let mut built = object.build(materials);
+                if *rotate_y != 0.0 {
+                    built = Rc::new(RotateY::new(built, *rotate_y));
+                }
+                if *translate != RtVec3::new(0.0, 0.0, 0.0) {
+                    built = Rc::new(Translate::new(built, *translate));
+                }
+                built
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SceneDescription {
+    pub camera: CameraSettings,
+    pub materials: Vec<(String, MaterialDescription)>, // In file order, names are unique
+    pub objects: Vec<ObjectDescription>,
+}
+
+// Names the materials of a world being written out. Objects sharing a material instance share
+// its name, so they still share it once the scene is loaded again.
+#[derive(Default)]
+pub struct MaterialRegistry {
+    names: HashMap<usize, String>,
+    pub materials: Vec<(String, MaterialDescription)>,
+}
+
+impl MaterialRegistry {
+    pub fn new() -> Self {
+        MaterialRegistry::default()
+    }
+
+    // Name of `material` in the scene file, `None` if it can't be written out.
+    pub fn name(&mut self, material: &Rc<dyn Material>) -> Option<String> {
+        let key = Rc::as_ptr(material) as *const () as usize;
+        if let Some(name) = self.names.get(&key) {
+            return Some(name.clone());
+        }
+        let name = format!("material_{}", self.materials.len() + 1);
+        self.materials.push((name.clone(), material.describe()?));
+        self.names.insert(key, name.clone());
+        Some(name)
+    }
+}
+
+// Problem in a scene file, `line` counts from 1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SceneError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl SceneError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        SceneError {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl SceneDescription {
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        SceneDescription::parse(&text).map_err(|error| Error::new(ErrorKind::InvalidData, format!("{:?}: {}", path, error)))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, SceneError> {
+        let mut scene = SceneDescription::default();
+        let mut seen_camera = false;
+        for table in read_tables(text)? {
+            match &table.kind {
+                TableKind::Camera if seen_camera => return Err(SceneError::new(table.line, "[camera] is defined twice")),
+                TableKind::Camera => {
+                    seen_camera = true;
+                    scene.camera = parse_camera(table)?;
+                }
+                TableKind::Material(name) => {
+                    if scene.materials.iter().any(|(existing, _)| existing == name) {
+                        return Err(SceneError::new(table.line, format!("material \"{}\" is defined twice", name)));
+                    }
+                    let name = name.clone();
+                    scene.materials.push((name, parse_material(table)?));
+                }
+                TableKind::Object => {
+                    let line = table.line;
+                    let object = parse_object(table)?;
+                    let material = object.material();
+                    if !scene.materials.iter().any(|(name, _)| name == material) {
+                        return Err(SceneError::new(line, format!("unknown material \"{}\", materials must be defined before use", material)));
+                    }
+                    scene.objects.push(object);
+                }
+            }
+        }
+        Ok(scene)
+    }
+
+    // Scene file contents for what `camera` renders. Fails if the world holds an object or
+    // material that can't be written out.
+    pub fn capture(camera: &Camera) -> std::io::Result<Self> {
+        let mut materials = MaterialRegistry::new();
+        let objects = camera.world().describe(&mut materials).ok_or_else(|| {
+            Error::new(ErrorKind::Unsupported, "the scene holds an object or material that can't be saved to a scene file")
+        })?;
+        if let Aperture::Mask(_) = camera.settings().aperture {
+            return Err(Error::new(ErrorKind::Unsupported, "image aperture masks can't be saved to a scene file"));
+        }
+        Ok(SceneDescription {
+            camera: camera.settings(),
+            materials: materials.materials,
+            objects,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    // Builds the world, objects sharing a material name share one material instance.
+    pub fn build_world(&self) -> HittableList {
+        let mut materials: HashMap<&str, Rc<dyn Material>> = HashMap::new();
+        for (name, material) in &self.materials {
+            let built = match material {
+                MaterialDescription::Lambertian { albedo } => new_material_lambertian_color(*albedo),
+                MaterialDescription::OrenNayar { albedo, sigma } => new_material_oren_nayar(*albedo, *sigma),
+                MaterialDescription::Velvet { albedo, sheen, roughness } => new_material_velvet(*albedo, *sheen, *roughness),
+                MaterialDescription::Metal { albedo, roughness } => new_material_metal_color(*albedo, *roughness),
+                MaterialDescription::Dielectric { albedo, refraction_index, absorption, dispersion } => {
+                    let mut dielectric = Dielectric::new(*albedo, *refraction_index).with_absorption(*absorption);
+                    dielectric.dispersion = *dispersion;
+                    Rc::new(dielectric)
+                }
+                MaterialDescription::Principled { base_color, parameters } => new_material_principled(*base_color, *parameters),
+                MaterialDescription::Subsurface { albedo, mean_free_path, refraction_index, anisotropy } => {
+                    Rc::new(Subsurface::new(*albedo, *mean_free_path, *refraction_index).with_anisotropy(*anisotropy))
+                }
+                MaterialDescription::LambertianTexture { texture } => new_material_lambertian_texture(texture.build()),
+                MaterialDescription::DiffuseLight { emit } => new_material_diffuse_light_color(*emit),
+            };
+            materials.insert(name.as_str(), built);
+        }
+
+        let mut world = HittableList::new();
+        for object in &self.objects {
+            world.add(object.build(&materials));
+        }
+        world
+    }
+
+    pub fn build_camera(&self, image_width: u32, samples_per_pixel: u32, sample_bounce_max: u32) -> Camera {
+        self.camera.build_camera(self.build_world(), image_width, samples_per_pixel, sample_bounce_max)
+    }
+}
+
+// The scene in file form. Numbers are written with every digit needed to read back the exact
+// same value, so a saved scene renders exactly like the original.
+impl fmt::Display for SceneDescription {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let vector = |v: &RtVec3| format!("[{}, {}, {}]", v.x, v.y, v.z);
+        let color = |c: &Color| format!("[{}, {}, {}]", c.r, c.g, c.b);
+        let camera = &self.camera;
+        writeln!(f, "[camera]")?;
+        writeln!(f, "aspect_ratio = {}", camera.aspect_ratio)?;
+        writeln!(f, "fov = {}", camera.fov)?;
+        writeln!(f, "lookfrom = {}", vector(&camera.lookfrom))?;
+        writeln!(f, "lookat = {}", vector(&camera.lookat))?;
+        writeln!(f, "vup = {}", vector(&camera.vup))?;
+        writeln!(f, "defocus_angle = {}", camera.defocus_angle)?;
+        writeln!(f, "focus_dist = {}", camera.focus_dist)?;
+        if let Aperture::Polygon { blades, rotation } = camera.aperture {
+            writeln!(f, "aperture_blades = {}", blades)?;
+            writeln!(f, "aperture_rotation = {}", rotation)?;
+        }
+        if let Background::Solid(background) = camera.background {
+            writeln!(f, "background = {}", color(&background))?;
+        }
+
+        for (name, material) in &self.materials {
+            writeln!(f)?;
+            writeln!(f, "[material.{}]", table_name(name))?;
+            match material {
+                MaterialDescription::Lambertian { albedo } => {
+                    writeln!(f, "type = \"lambertian\"")?;
+                    writeln!(f, "albedo = {}", color(albedo))?;
+                }
+                MaterialDescription::OrenNayar { albedo, sigma } => {
+                    writeln!(f, "type = \"oren_nayar\"")?;
+                    writeln!(f, "albedo = {}", color(albedo))?;
+                    writeln!(f, "sigma = {}", sigma)?;
+                }
+                MaterialDescription::Velvet { albedo, sheen, roughness } => {
+                    writeln!(f, "type = \"velvet\"")?;
+                    writeln!(f, "albedo = {}", color(albedo))?;
+                    writeln!(f, "sheen = {}", color(sheen))?;
+                    writeln!(f, "roughness = {}", roughness)?;
+                }
+                MaterialDescription::Metal { albedo, roughness } => {
+                    writeln!(f, "type = \"metal\"")?;
+                    writeln!(f, "albedo = {}", color(albedo))?;
+                    writeln!(f, "roughness = {}", roughness)?;
+                }
+                MaterialDescription::Dielectric { albedo, refraction_index, absorption, dispersion } => {
+                    writeln!(f, "type = \"dielectric\"")?;
+                    writeln!(f, "albedo = {}", color(albedo))?;
+                    writeln!(f, "refraction_index = {}", refraction_index)?;
+                    if *absorption != Color::new_rgb(0.0, 0.0, 0.0) {
+                        writeln!(f, "absorption = {}", color(absorption))?;
+                    }
+                    match dispersion {
+                        Some(Dispersion::Cauchy { a, b }) => writeln!(f, "cauchy = [{}, {}]", a, b)?,
+                        Some(Dispersion::Sellmeier { b, c }) => {
+                            writeln!(f, "sellmeier = [{}, {}, {}, {}, {}, {}]", b[0], b[1], b[2], c[0], c[1], c[2])?
+                        }
+                        None => {}
+                    }
+                }
+                MaterialDescription::Principled { base_color, parameters } => {
+                    writeln!(f, "type = \"principled\"")?;
+                    writeln!(f, "base_color = {}", color(base_color))?;
+                    for (key, value) in parameters.fields() {
+                        writeln!(f, "{} = {}", key, value)?;
+                    }
+                }
+                MaterialDescription::Subsurface { albedo, mean_free_path, refraction_index, anisotropy } => {
+                    writeln!(f, "type = \"subsurface\"")?;
+                    writeln!(f, "albedo = {}", color(albedo))?;
+                    writeln!(f, "mean_free_path = {}", color(mean_free_path))?;
+                    writeln!(f, "refraction_index = {}", refraction_index)?;
+                    if *anisotropy != 0.0 {
+                        writeln!(f, "anisotropy = {}", anisotropy)?;
+                    }
+                }
+                MaterialDescription::LambertianTexture { texture } => {
+                    writeln!(f, "type = \"lambertian\"")?;
+                    match texture {
+                        TextureDescription::Checker { scale, even, odd } => {
+                            writeln!(f, "texture = \"checker\"")?;
+                            writeln!(f, "scale = {}", scale)?;
+                            writeln!(f, "even = {}", color(even))?;
+                            writeln!(f, "odd = {}", color(odd))?;
+                        }
+                        TextureDescription::Noise { scale } => {
+                            writeln!(f, "texture = \"noise\"")?;
+                            writeln!(f, "scale = {}", scale)?;
+                        }
+                        TextureDescription::Image { path } => {
+                            writeln!(f, "texture = \"image\"")?;
+                            writeln!(f, "path = \"{}\"", path.display())?;
+                        }
+                    }
+                }
+                MaterialDescription::DiffuseLight { emit } => {
+                    writeln!(f, "type = \"light\"")?;
+                    writeln!(f, "emit = {}", color(emit))?;
+                }
+            }
+        }
+
+        for object in &self.objects {
+            writeln!(f)?;
+            writeln!(f, "[[object]]")?;
+            let (shape, rotate_y, translate) = match object {
+                ObjectDescription::Instance { object, rotate_y, translate } => (object.as_ref(), *rotate_y, *translate),
+                shape => (shape, 0.0, RtVec3::new(0.0, 0.0, 0.0)),
+            };
+            match shape {
+                ObjectDescription::Sphere { center, radius, material } => {
+                    writeln!(f, "type = \"sphere\"")?;
+                    writeln!(f, "center = {}", vector(center))?;
+                    writeln!(f, "radius = {}", radius)?;
+                    writeln!(f, "material = \"{}\"", material)?;
+                }
+                ObjectDescription::Quad { q, u, v, material } => {
+                    writeln!(f, "type = \"quad\"")?;
+                    writeln!(f, "q = {}", vector(q))?;
+                    writeln!(f, "u = {}", vector(u))?;
+                    writeln!(f, "v = {}", vector(v))?;
+                    writeln!(f, "material = \"{}\"", material)?;
+                }
+                // Instances only ever hold a sphere or quad
+                ObjectDescription::Instance { .. } => return Err(fmt::Error),
+            }
+            if rotate_y != 0.0 {
+                writeln!(f, "rotate_y = {}", rotate_y)?;
+            }
+            if translate != RtVec3::new(0.0, 0.0, 0.0) {
+                writeln!(f, "translate = {}", vector(&translate))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Reading tables
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Number(f64),
+    Text(String),
+    List(Vec<f64>),
+}
+
+enum TableKind {
+    Camera,
+    Material(String),
+    Object,
+}
+
+// One [table] and its `key = value` lines, consumed field by field.
+struct Table {
+    kind: TableKind,
+    line: usize,
+    entries: Vec<(String, Value, usize)>,
+}
+
+fn read_tables(text: &str) -> Result<Vec<Table>, SceneError> {
+    let mut tables: Vec<Table> = Vec::new();
+    for (index, raw_line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let line = strip_comment(raw_line).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with('[') {
+            let kind = if line == "[[object]]" {
+                TableKind::Object
+            } else if line == "[camera]" {
+                TableKind::Camera
+            } else if let Some(name) = line.strip_prefix("[material.").and_then(|rest| rest.strip_suffix(']')) {
+                let name = name.trim().trim_matches('"');
+                if name.is_empty() {
+                    return Err(SceneError::new(line_number, "material tables need a name, like [material.ground]"));
+                }
+                TableKind::Material(name.to_string())
+            } else {
+                return Err(SceneError::new(line_number, format!("unknown table {}, expected [camera], [material.<name>] or [[object]]", line)));
+            };
+            tables.push(Table {
+                kind,
+                line: line_number,
+                entries: Vec::new(),
+            });
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| SceneError::new(line_number, format!("expected `key = value`, found \"{}\"", line)))?;
+        let key = key.trim();
+        let value = parse_value(value.trim(), line_number)?;
+        let table = tables
+            .last_mut()
+            .ok_or_else(|| SceneError::new(line_number, format!("\"{}\" is outside of any table", key)))?;
+        if table.entries.iter().any(|(existing, _, _)| existing == key) {
+            return Err(SceneError::new(line_number, format!("\"{}\" is set twice", key)));
+        }
+        table.entries.push((key.to_string(), value, line_number));
+    }
+    Ok(tables)
+}
+
+// Material names that aren't plain words are quoted, like [material."steel #2"].
+fn table_name(name: &str) -> String {
+    if name.chars().all(|character| character.is_ascii_alphanumeric() || character == '_' || character == '-') {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name)
+    }
+}
+
+// Everything before a # that isn't inside a string.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (index, character) in line.char_indices() {
+        match character {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..index],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn parse_value(text: &str, line: usize) -> Result<Value, SceneError> {
+    if let Some(inner) = text.strip_prefix('"') {
+        return match inner.strip_suffix('"') {
+            Some(inner) if !inner.contains('"') => Ok(Value::Text(inner.to_string())),
+            _ => Err(SceneError::new(line, format!("unterminated string {}", text))),
+        };
+    }
+    if let Some(inner) = text.strip_prefix('[') {
+        let inner = inner
+            .strip_suffix(']')
+            .ok_or_else(|| SceneError::new(line, format!("unterminated list {}", text)))?;
+        let numbers = inner
+            .split(',')
+            .map(|item| item.trim())
+            .filter(|item| !item.is_empty())
+            .map(|item| item.parse::<f64>().map_err(|_| SceneError::new(line, format!("expected a number in list, found \"{}\"", item))))
+            .collect::<Result<Vec<f64>, SceneError>>()?;
+        return Ok(Value::List(numbers));
+    }
+    text.parse::<f64>()
+        .map(Value::Number)
+        .map_err(|_| SceneError::new(line, format!("expected a number, \"string\" or [list], found \"{}\"", text)))
+}
+
+impl Table {
+    fn take(&mut self, key: &str) -> Option<(Value, usize)> {
+        let index = self.entries.iter().position(|(existing, _, _)| existing == key)?;
+        let (_, value, line) = self.entries.remove(index);
+        Some((value, line))
+    }
+
+    fn missing(&self, key: &str) -> SceneError {
+        SceneError::new(self.line, format!("missing \"{}\"", key))
+    }
+
+    fn number(&mut self, key: &str) -> Result<Option<f64>, SceneError> {
+        match self.take(key) {
+            Some((Value::Number(number), _)) => Ok(Some(number)),
+            Some((_, line)) => Err(SceneError::new(line, format!("\"{}\" must be a number", key))),
+            None => Ok(None),
+        }
+    }
+
+    fn text(&mut self, key: &str) -> Result<Option<String>, SceneError> {
+        match self.take(key) {
+            Some((Value::Text(text), _)) => Ok(Some(text)),
+            Some((_, line)) => Err(SceneError::new(line, format!("\"{}\" must be a \"string\"", key))),
+            None => Ok(None),
+        }
+    }
+
+    fn vector(&mut self, key: &str) -> Result<Option<RtVec3>, SceneError> {
+        match self.take(key) {
+            Some((Value::List(list), _)) if list.len() == 3 => Ok(Some(RtVec3::new(list[0], list[1], list[2]))),
+            Some((_, line)) => Err(SceneError::new(line, format!("\"{}\" must be a list of three numbers", key))),
+            None => Ok(None),
+        }
+    }
+
+    fn numbers(&mut self, key: &str, count: usize) -> Result<Option<Vec<f64>>, SceneError> {
+        match self.take(key) {
+            Some((Value::List(list), _)) if list.len() == count => Ok(Some(list)),
+            Some((_, line)) => Err(SceneError::new(line, format!("\"{}\" must be a list of {} numbers", key, count))),
+            None => Ok(None),
+        }
+    }
+
+    fn color(&mut self, key: &str) -> Result<Option<Color>, SceneError> {
+        Ok(self.vector(key)?.map(Color::from_vec))
+    }
+
+    // Fails on the first key nothing asked for, typos shouldn't be silently ignored.
+    fn finish(self, what: &str) -> Result<(), SceneError> {
+        match self.entries.first() {
+            Some((key, _, line)) => Err(SceneError::new(*line, format!("unknown key \"{}\" for {}", key, what))),
+            None => Ok(()),
+        }
+    }
+}
+
+fn parse_camera(mut table: Table) -> Result<CameraSettings, SceneError> {
+    let defaults = CameraSettings::default();
+    let mut camera = CameraSettings {
+        aspect_ratio: table.number("aspect_ratio")?.unwrap_or(defaults.aspect_ratio),
+        fov: table.number("fov")?.unwrap_or(defaults.fov),
+        lookfrom: table.vector("lookfrom")?.unwrap_or(defaults.lookfrom),
+        lookat: table.vector("lookat")?.unwrap_or(defaults.lookat),
+        vup: table.vector("vup")?.unwrap_or(defaults.vup),
+        defocus_angle: table.number("defocus_angle")?.unwrap_or(defaults.defocus_angle),
+        focus_dist: table.number("focus_dist")?.unwrap_or(defaults.focus_dist),
+        aperture: defaults.aperture,
+        background: table.color("background")?.map(Background::Solid).unwrap_or(defaults.background),
+    };
+    let rotation = table.number("aperture_rotation")?.unwrap_or(0.0);
+    match table.take("aperture_blades") {
+        Some((Value::Number(blades), _)) if blades >= 3.0 && blades.fract() == 0.0 => {
+            camera.aperture = Aperture::Polygon { blades: blades as u32, rotation };
+        }
+        Some((_, line)) => return Err(SceneError::new(line, "\"aperture_blades\" must be a whole number of at least 3")),
+        None => {}
+    }
+    if camera.aspect_ratio <= 0.0 || camera.focus_dist <= 0.0 {
+        return Err(SceneError::new(table.line, "aspect_ratio and focus_dist must be positive"));
+    }
+    table.finish("[camera]")?;
+    Ok(camera)
+}
+
+fn parse_material(mut table: Table) -> Result<MaterialDescription, SceneError> {
+    let white = Color::new_rgb(1.0, 1.0, 1.0);
+    let kind = table.text("type")?.ok_or_else(|| table.missing("type"))?;
+    let material = match kind.as_str() {
+        "lambertian" => match table.text("texture")? {
+            Some(texture) => MaterialDescription::LambertianTexture { texture: parse_texture(&mut table, &texture)? },
+            None => MaterialDescription::Lambertian {
+                albedo: table.color("albedo")?.ok_or_else(|| table.missing("albedo"))?,
+            },
+        },
+        "oren_nayar" => MaterialDescription::OrenNayar {
+            albedo: table.color("albedo")?.ok_or_else(|| table.missing("albedo"))?,
+            sigma: table.number("sigma")?.unwrap_or(20.0),
+        },
+        "velvet" => MaterialDescription::Velvet {
+            albedo: table.color("albedo")?.ok_or_else(|| table.missing("albedo"))?,
+            sheen: table.color("sheen")?.unwrap_or(white),
+            roughness: table.number("roughness")?.unwrap_or(0.5),
+        },
+        "metal" => {
+            let albedo = table.color("albedo")?.ok_or_else(|| table.missing("albedo"))?;
+            // Older files give the roughness as "fuzz"
+            let fuzz = table.number("fuzz")?;
+            let roughness = table.number("roughness")?.or(fuzz).unwrap_or(0.0);
+            MaterialDescription::Metal { albedo, roughness }
+        }
+        "dielectric" => {
+            let cauchy = table.numbers("cauchy", 2)?.map(|n| Dispersion::Cauchy { a: n[0], b: n[1] });
+            let sellmeier = table.numbers("sellmeier", 6)?.map(|n| Dispersion::Sellmeier { b: [n[0], n[1], n[2]], c: [n[3], n[4], n[5]] });
+            if cauchy.is_some() && sellmeier.is_some() {
+                return Err(SceneError::new(table.line, "give either \"cauchy\" or \"sellmeier\", not both"));
+            }
+            let dispersion = cauchy.or(sellmeier);
+            // Dispersive glass defaults to its D line index
+            let refraction_index = match (table.number("refraction_index")?, dispersion) {
+                (Some(refraction_index), _) => refraction_index,
+                (None, Some(dispersion)) => dispersion.index(D_LINE),
+                (None, None) => return Err(table.missing("refraction_index")),
+            };
+            MaterialDescription::Dielectric {
+                albedo: table.color("albedo")?.unwrap_or(white),
+                refraction_index,
+                absorption: table.color("absorption")?.unwrap_or(Color::new_rgb(0.0, 0.0, 0.0)),
+                dispersion,
+            }
+        }
+        "principled" => {
+            let base_color = table.color("base_color")?.ok_or_else(|| table.missing("base_color"))?;
+            // Anything left out keeps its default
+            let mut parameters = PrincipledParameters::default();
+            for (key, value) in parameters.fields_mut() {
+                if let Some(given) = table.number(key)? {
+                    *value = given;
+                }
+            }
+            MaterialDescription::Principled { base_color, parameters }
+        }
+        "light" => MaterialDescription::DiffuseLight {
+            emit: table.color("emit")?.ok_or_else(|| table.missing("emit"))?,
+        },
+        "subsurface" => {
+            let anisotropy = table.number("anisotropy")?.unwrap_or(0.0);
+            if anisotropy.abs() >= 1.0 {
+                return Err(SceneError::new(table.line, "\"anisotropy\" must be between -1 and 1"));
+            }
+            let mean_free_path = table.color("mean_free_path")?.ok_or_else(|| table.missing("mean_free_path"))?;
+            if mean_free_path.r <= 0.0 || mean_free_path.g <= 0.0 || mean_free_path.b <= 0.0 {
+                return Err(SceneError::new(table.line, "\"mean_free_path\" must be positive"));
+            }
+            MaterialDescription::Subsurface {
+                albedo: table.color("albedo")?.ok_or_else(|| table.missing("albedo"))?,
+                mean_free_path,
+                refraction_index: table.number("refraction_index")?.unwrap_or(1.4),
+                anisotropy,
+            }
+        }
+        _ => {
+            return Err(SceneError::new(
+                table.line,
+                format!("unknown material type \"{}\", expected lambertian, oren_nayar, velvet, metal, dielectric, principled, subsurface or light", kind),
+            ))
+        }
+    };
+    table.finish(&format!("{} materials", kind))?;
+    Ok(material)
+}
+
+fn parse_object(mut table: Table) -> Result<ObjectDescription, SceneError> {
+    let kind = table.text("type")?.ok_or_else(|| table.missing("type"))?;
+    let object = match kind.as_str() {
+        "sphere" => ObjectDescription::Sphere {
+            center: table.vector("center")?.ok_or_else(|| table.missing("center"))?,
+            radius: table.number("radius")?.ok_or_else(|| table.missing("radius"))?,
+            material: table.text("material")?.ok_or_else(|| table.missing("material"))?,
+        },
+        "quad" => ObjectDescription::Quad {
+            q: table.vector("q")?.ok_or_else(|| table.missing("q"))?,
+            u: table.vector("u")?.ok_or_else(|| table.missing("u"))?,
+            v: table.vector("v")?.ok_or_else(|| table.missing("v"))?,
+            material: table.text("material")?.ok_or_else(|| table.missing("material"))?,
+        },
+        _ => return Err(SceneError::new(table.line, format!("unknown object type \"{}\", expected sphere or quad", kind))),
+    };
+    let rotate_y = table.number("rotate_y")?;
+    let translate = table.vector("translate")?;
+    table.finish(&format!("{} objects", kind))?;
+    if rotate_y.is_none() && translate.is_none() {
+        return Ok(object);
+    }
+    Ok(ObjectDescription::Instance {
+        object: Box::new(object),
+        rotate_y: rotate_y.unwrap_or(0.0),
+        translate: translate.unwrap_or(RtVec3::new(0.0, 0.0, 0.0)),
+    })
+}
+
+// The `texture` of a lambertian material and the keys that go with it.
+fn parse_texture(table: &mut Table, kind: &str) -> Result<TextureDescription, SceneError> {
+    let texture = match kind {
+        "checker" => TextureDescription::Checker {
+            scale: table.number("scale")?.ok_or_else(|| table.missing("scale"))?,
+            even: table.color("even")?.ok_or_else(|| table.missing("even"))?,
+            odd: table.color("odd")?.ok_or_else(|| table.missing("odd"))?,
+        },
+        "noise" => TextureDescription::Noise {
+            scale: table.number("scale")?.ok_or_else(|| table.missing("scale"))?,
+        },
+        "image" => TextureDescription::Image {
+            path: PathBuf::from(table.text("path")?.ok_or_else(|| table.missing("path"))?),
+        },
+        _ => {
+            return Err(SceneError::new(
+                table.line,
+                format!("unknown texture \"{}\", expected checker, noise or image", kind),
+            ))
+        }
+    };
+    Ok(texture)
+}
@@ -0,0 +1,356 @@
+use crate::camera::Camera;
+
+use crate::hit::{make_box, HittableList, Quad, RotateY, Sphere, Translate};
+
+use crate::lens::Aperture;
+
+use crate::mesh::Mesh;
+
+use crate::material::{
+    new_material_coated, new_material_dielectric, new_material_dielectric_dispersive, new_material_diffuse_light_color,
+    new_material_lambertian_color, new_material_lambertian_color_float, new_material_lambertian_texture,
+    new_material_metal_color, new_material_metal_color_float, new_material_pearlescent_paint,
+    new_material_thin_film_bubble, new_material_cutout, new_material_mix, Coated, Metal, MixWeight,
+};
+
+use crate::ray::{Background, Color};
+
+use crate::rtvec3::{Point3, RtVec3};
+
+use crate::scene::CameraSettings;
+
+use crate::spectrum::Dispersion;
+
+use crate::texture::{CheckerTexture, ImageTexture, NoiseTexture};
+
+use crate::{random_float, random_float_range, Interval};
+
+use std::path::Path;
+use std::rc::Rc;
+
+// Built-in scenes, the standard benchmarks and smoke tests. Names as accepted by `preset`.
+pub const PRESETS: [&str; 10] = [
+    "final-scene",
+    "three-spheres",
+    "checkered-spheres",
+    "earth",
+    "perlin-spheres",
+    "simple-light",
+    "cornell-box",
+    "prism",
+    "coatings",
+    "mix",
+];
+
+// Where the earth preset looks for its texture, an equirectangular map in any netpbm format.
+pub const EARTH_TEXTURE: &str = "textures/earthmap.ppm";
+
+// A ready-made world and the camera placement that shows it off.
+pub struct Preset {
+    pub world: HittableList,
+    pub camera: CameraSettings,
+}
+
+impl Preset {
+    pub fn build_camera(self, image_width: u32, samples_per_pixel: u32, sample_bounce_max: u32) -> Camera {
+        self.camera.build_camera(self.world, image_width, samples_per_pixel, sample_bounce_max)
+    }
+}
+
+// Builds the named preset. Random scenes draw from the shared generator, seed it first to get
+// the same scene every time.
+pub fn preset(name: &str) -> Option<Preset> {
+    match name {
+        "final-scene" => Some(final_scene()),
+        "three-spheres" => Some(three_spheres()),
+        "checkered-spheres" => Some(checkered_spheres()),
+        "earth" => Some(earth()),
+        "perlin-spheres" => Some(perlin_spheres()),
+        "simple-light" => Some(simple_light()),
+        "cornell-box" => Some(cornell_box()),
+        "prism" => Some(prism()),
+        "coatings" => Some(coatings()),
+        "mix" => Some(mix()),
+        _ => None,
+    }
+}
+
+// The book's cover: a field of small random spheres around three large ones.
+fn final_scene() -> Preset {
+    let mut world = HittableList::new();
+    let material_ground = new_material_lambertian_color_float(0.5, 0.5, 0.5);
+    world.add(Rc::new(Sphere::new(Point3::new(0.0, -1000.0, -1.0), 1000.0, material_ground)));
+
+    for a in -11..11 {
+        for b in -11..11 {
+            let choose_mat: f64 = random_float();
+            let center: Point3 = Point3::new(a as f64 + 0.9 * random_float(), 0.2, b as f64 + 0.9 * random_float());
+
+            if (center - Point3::new(4.0, 0.2, 0.0)).length() > 0.9 {
+                let sphere_material = if choose_mat < 0.8 {
+                    // diffuse
+                    let albedo = Color::random() * Color::random();
+                    new_material_lambertian_color(albedo)
+                } else if choose_mat < 0.95 {
+                    // metal
+                    let albedo = Color::random_range(Interval::new(0.5, 1.0));
+                    let fuzz = random_float_range(Interval::new(0.0, 0.5));
+                    new_material_metal_color(albedo, fuzz)
+                } else {
+                    // glass
+                    new_material_dielectric(1.5)
+                };
+                world.add(Rc::new(Sphere::new(center, 0.2, sphere_material)));
+            }
+        }
+    }
+
+    world.add(Rc::new(Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0, new_material_dielectric(1.5))));
+    world.add(Rc::new(Sphere::new(Point3::new(-4.0, 1.0, 0.0), 1.0, new_material_lambertian_color(Color::new_rgb(0.4, 0.2, 0.1)))));
+    world.add(Rc::new(Sphere::new(Point3::new(4.0, 1.0, 0.0), 1.0, new_material_metal_color_float(0.7, 0.6, 0.5, 0.0))));
+
+    Preset {
+        world,
+        camera: CameraSettings {
+            fov: 20.0,
+            lookfrom: Point3::new(13.0, 2.0, 3.0),
+            lookat: Point3::new(0.0, 0.0, 0.0),
+            defocus_angle: 0.6,
+            focus_dist: 10.0,
+            aperture: Aperture::Polygon { blades: 6, rotation: 0.0 }, // Six iris blades give hexagonal bokeh
+            ..CameraSettings::default()
+        },
+    }
+}
+
+// Diffuse, glass (with an air bubble inside) and gold spheres on a yellow-green ground.
+fn three_spheres() -> Preset {
+    let material_ground = new_material_lambertian_color(Color::new_rgb(0.8, 0.8, 0.0));
+    let material_center = new_material_lambertian_color(Color::new_rgb(0.1, 0.2, 0.5));
+    let material_left = new_material_dielectric(1.5);
+    let material_bubble = new_material_dielectric(1.0 / 1.5);
+    let material_right = new_material_metal_color(Color::new_rgb(0.8, 0.6, 0.2), 0.0);
+
+    let mut world = HittableList::new();
+    world.add(Rc::new(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, material_ground)));
+    world.add(Rc::new(Sphere::new(Point3::new(0.0, 0.0, -1.2), 0.5, material_center)));
+    world.add(Rc::new(Sphere::new(Point3::new(-1.0, 0.0, -1.0), 0.5, material_left)));
+    world.add(Rc::new(Sphere::new(Point3::new(-1.0, 0.0, -1.0), 0.4, material_bubble)));
+    world.add(Rc::new(Sphere::new(Point3::new(1.0, 0.0, -1.0), 0.5, material_right)));
+
+    Preset {
+        world,
+        camera: CameraSettings::default(),
+    }
+}
+
+fn checkered_spheres() -> Preset {
+    let checker = Rc::new(CheckerTexture::from_colors(0.32, Color::new_rgb(0.2, 0.3, 0.1), Color::new_rgb(0.9, 0.9, 0.9)));
+    let material = new_material_lambertian_texture(checker);
+
+    let mut world = HittableList::new();
+    world.add(Rc::new(Sphere::new(Point3::new(0.0, -10.0, 0.0), 10.0, Rc::clone(&material))));
+    world.add(Rc::new(Sphere::new(Point3::new(0.0, 10.0, 0.0), 10.0, material)));
+
+    Preset {
+        world,
+        camera: CameraSettings {
+            fov: 20.0,
+            lookfrom: Point3::new(13.0, 2.0, 3.0),
+            lookat: Point3::new(0.0, 0.0, 0.0),
+            focus_dist: 10.0,
+            ..CameraSettings::default()
+        },
+    }
+}
+
+// A globe textured with EARTH_TEXTURE, cyan when the file isn't there.
+fn earth() -> Preset {
+    let earth_texture = Rc::new(ImageTexture::load(Path::new(EARTH_TEXTURE)));
+    let mut world = HittableList::new();
+    world.add(Rc::new(Sphere::new(Point3::new(0.0, 0.0, 0.0), 2.0, new_material_lambertian_texture(earth_texture))));
+
+    Preset {
+        world,
+        camera: CameraSettings {
+            fov: 20.0,
+            lookfrom: Point3::new(0.0, 0.0, 12.0),
+            lookat: Point3::new(0.0, 0.0, 0.0),
+            focus_dist: 10.0,
+            ..CameraSettings::default()
+        },
+    }
+}
+
+fn perlin_spheres() -> Preset {
+    let marble = new_material_lambertian_texture(Rc::new(NoiseTexture::new(4.0)));
+    let mut world = HittableList::new();
+    world.add(Rc::new(Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, Rc::clone(&marble))));
+    world.add(Rc::new(Sphere::new(Point3::new(0.0, 2.0, 0.0), 2.0, marble)));
+
+    Preset {
+        world,
+        camera: CameraSettings {
+            fov: 20.0,
+            lookfrom: Point3::new(13.0, 2.0, 3.0),
+            lookat: Point3::new(0.0, 0.0, 0.0),
+            focus_dist: 10.0,
+            ..CameraSettings::default()
+        },
+    }
+}
+
+// The Perlin spheres lit only by a rectangular and a spherical light.
+fn simple_light() -> Preset {
+    let mut world = perlin_spheres().world;
+    let light = new_material_diffuse_light_color(Color::new_rgb(4.0, 4.0, 4.0));
+    world.add(Rc::new(Sphere::new(Point3::new(0.0, 7.0, 0.0), 2.0, Rc::clone(&light))));
+    world.add(Rc::new(Quad::new(Point3::new(3.0, 1.0, -2.0), RtVec3::new(2.0, 0.0, 0.0), RtVec3::new(0.0, 2.0, 0.0), light)));
+
+    Preset {
+        world,
+        camera: CameraSettings {
+            fov: 20.0,
+            lookfrom: Point3::new(26.0, 3.0, 6.0),
+            lookat: Point3::new(0.0, 2.0, 0.0),
+            focus_dist: 10.0,
+            background: Background::Solid(Color::new_rgb(0.0, 0.0, 0.0)),
+            ..CameraSettings::default()
+        },
+    }
+}
+
+// 555 unit box with red and green side walls, a ceiling light and two turned white boxes.
+fn cornell_box() -> Preset {
+    let red = new_material_lambertian_color_float(0.65, 0.05, 0.05);
+    let white = new_material_lambertian_color_float(0.73, 0.73, 0.73);
+    let green = new_material_lambertian_color_float(0.12, 0.45, 0.15);
+    let light = new_material_diffuse_light_color(Color::new_rgb(15.0, 15.0, 15.0));
+
+    let mut world = HittableList::new();
+    let quad = |q: Point3, u: RtVec3, v: RtVec3, material| Rc::new(Quad::new(q, u, v, material));
+    world.add(quad(Point3::new(555.0, 0.0, 0.0), RtVec3::new(0.0, 555.0, 0.0), RtVec3::new(0.0, 0.0, 555.0), green));
+    world.add(quad(Point3::new(0.0, 0.0, 0.0), RtVec3::new(0.0, 555.0, 0.0), RtVec3::new(0.0, 0.0, 555.0), red));
+    world.add(quad(Point3::new(343.0, 554.0, 332.0), RtVec3::new(-130.0, 0.0, 0.0), RtVec3::new(0.0, 0.0, -105.0), light));
+    world.add(quad(Point3::new(0.0, 0.0, 0.0), RtVec3::new(555.0, 0.0, 0.0), RtVec3::new(0.0, 0.0, 555.0), Rc::clone(&white)));
+    world.add(quad(Point3::new(555.0, 555.0, 555.0), RtVec3::new(-555.0, 0.0, 0.0), RtVec3::new(0.0, 0.0, -555.0), Rc::clone(&white)));
+    world.add(quad(Point3::new(0.0, 0.0, 555.0), RtVec3::new(555.0, 0.0, 0.0), RtVec3::new(0.0, 555.0, 0.0), Rc::clone(&white)));
+
+    let tall_box = make_box(Point3::new(0.0, 0.0, 0.0), Point3::new(165.0, 330.0, 165.0), Rc::clone(&white));
+    let tall_box = RotateY::new(Rc::new(tall_box), 15.0);
+    world.add(Rc::new(Translate::new(Rc::new(tall_box), RtVec3::new(265.0, 0.0, 295.0))));
+
+    let short_box = make_box(Point3::new(0.0, 0.0, 0.0), Point3::new(165.0, 165.0, 165.0), white);
+    let short_box = RotateY::new(Rc::new(short_box), -18.0);
+    world.add(Rc::new(Translate::new(Rc::new(short_box), RtVec3::new(130.0, 0.0, 65.0))));
+
+    Preset {
+        world,
+        camera: CameraSettings {
+            aspect_ratio: 1.0,
+            fov: 40.0,
+            lookfrom: Point3::new(278.0, 278.0, -800.0),
+            lookat: Point3::new(278.0, 278.0, 0.0),
+            focus_dist: 10.0,
+            background: Background::Solid(Color::new_rgb(0.0, 0.0, 0.0)),
+            ..CameraSettings::default()
+        },
+    }
+}
+
+// A flint glass prism and spheres under one small, bright light. Render with --spectral to see
+// the light split into a rainbow on the floor.
+fn prism() -> Preset {
+    let flint = new_material_dielectric_dispersive(Dispersion::dense_flint());
+    let crown = new_material_dielectric_dispersive(Dispersion::bk7());
+    let white = new_material_lambertian_color_float(0.8, 0.8, 0.8);
+    let light = new_material_diffuse_light_color(Color::new_rgb(8.0, 8.0, 8.0));
+
+    let mut world = HittableList::new();
+    world.add(Rc::new(Quad::new(Point3::new(-20.0, 0.0, -20.0), RtVec3::new(40.0, 0.0, 0.0), RtVec3::new(0.0, 0.0, 40.0), white)));
+
+    // Triangular cross-section standing on one face, extruded along z, wound outwards
+    let (half_length, height) = (1.5, 1.7);
+    let mut positions = Vec::new();
+    for z in [-half_length, half_length] {
+        positions.extend([Point3::new(-1.0, 0.0, z), Point3::new(1.0, 0.0, z), Point3::new(0.0, height, z)]);
+    }
+    let triangles = vec![[3, 4, 5], [0, 2, 1], [0, 1, 4], [0, 4, 3], [1, 2, 5], [1, 5, 4], [2, 0, 3], [2, 3, 5]];
+    world.add(Rc::new(Mesh::new(positions, None, None, triangles, Rc::clone(&flint))));
+
+    world.add(Rc::new(Sphere::new(Point3::new(2.2, 0.7, 2.0), 0.7, flint)));
+    world.add(Rc::new(Sphere::new(Point3::new(-2.4, 0.7, 1.8), 0.7, crown)));
+    world.add(Rc::new(Sphere::new(Point3::new(-6.0, 2.5, 0.0), 1.5, light)));
+
+    Preset {
+        world,
+        camera: CameraSettings {
+            fov: 35.0,
+            lookfrom: Point3::new(3.0, 7.0, 10.0),
+            lookat: Point3::new(0.5, 0.5, 0.0),
+            focus_dist: 10.0,
+            background: Background::Solid(Color::new_rgb(0.0, 0.0, 0.0)),
+            ..CameraSettings::default()
+        },
+    }
+}
+
+// Layered materials on a checkered floor: soap bubbles, pearlescent paint, candy-apple red over
+// aluminium and lacquered wood-brown.
+fn coatings() -> Preset {
+    let checker = Rc::new(CheckerTexture::from_colors(0.5, Color::new_rgb(0.2, 0.2, 0.22), Color::new_rgb(0.85, 0.85, 0.85)));
+    let mut world = HittableList::new();
+    world.add(Rc::new(Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, new_material_lambertian_texture(checker))));
+
+    let candy = Coated::new(Rc::new(Metal::aluminium(0.25)), 1.5, 0.0).with_tint(Color::new_rgb(0.75, 0.05, 0.05));
+    let lacquer = new_material_coated(new_material_lambertian_color(Color::new_rgb(0.35, 0.18, 0.08)), 1.5, 0.1);
+    world.add(Rc::new(Sphere::new(Point3::new(-3.3, 1.0, 0.0), 1.0, new_material_pearlescent_paint(Color::new_rgb(0.85, 0.85, 0.88)))));
+    world.add(Rc::new(Sphere::new(Point3::new(-1.1, 1.0, 0.0), 1.0, new_material_pearlescent_paint(Color::new_rgb(0.05, 0.1, 0.35)))));
+    world.add(Rc::new(Sphere::new(Point3::new(1.1, 1.0, 0.0), 1.0, Rc::new(candy))));
+    world.add(Rc::new(Sphere::new(Point3::new(3.3, 1.0, 0.0), 1.0, lacquer)));
+    for (x, z, radius, thickness) in [(-2.2, 2.2, 0.6, 350.0), (0.0, 2.6, 0.5, 500.0), (2.2, 2.2, 0.6, 700.0)] {
+        world.add(Rc::new(Sphere::new(Point3::new(x, radius + 0.2, z), radius, new_material_thin_film_bubble(thickness, 1.33))));
+    }
+
+    Preset {
+        world,
+        camera: CameraSettings {
+            fov: 30.0,
+            lookfrom: Point3::new(0.0, 4.0, 14.0),
+            lookat: Point3::new(0.0, 0.9, 0.0),
+            focus_dist: 14.0,
+            ..CameraSettings::default()
+        },
+    }
+}
+
+// Combined materials: steel rusting where a noise mask is bright, a glazed ceramic that turns
+// glossy at grazing angles and a lattice screen cut out of a quad.
+fn mix() -> Preset {
+    let mut world = HittableList::new();
+    world.add(Rc::new(Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, new_material_lambertian_color_float(0.6, 0.6, 0.55))));
+
+    let steel = new_material_metal_color_float(0.75, 0.75, 0.78, 0.15);
+    let rust = new_material_lambertian_color_float(0.35, 0.12, 0.04);
+    let rusted = new_material_mix(steel, rust, MixWeight::Mask(Rc::new(NoiseTexture::new(4.0))));
+    world.add(Rc::new(Sphere::new(Point3::new(-1.2, 1.0, 0.0), 1.0, rusted)));
+
+    let clay = new_material_lambertian_color_float(0.1, 0.3, 0.45);
+    let glaze = new_material_metal_color_float(1.0, 1.0, 1.0, 0.0);
+    world.add(Rc::new(Sphere::new(Point3::new(1.2, 1.0, 0.0), 1.0, new_material_mix(clay, glaze, MixWeight::Fresnel(1.5)))));
+
+    let holes = Rc::new(CheckerTexture::from_colors(0.25, Color::new_rgb(0.0, 0.0, 0.0), Color::new_rgb(1.0, 1.0, 1.0)));
+    let screen = new_material_cutout(new_material_lambertian_color_float(0.2, 0.5, 0.15), holes);
+    world.add(Rc::new(Quad::new(Point3::new(-2.5, 0.0, 1.6), RtVec3::new(5.0, 0.0, 0.0), RtVec3::new(0.0, 1.2, 0.0), screen)));
+
+    Preset {
+        world,
+        camera: CameraSettings {
+            fov: 30.0,
+            lookfrom: Point3::new(0.0, 2.5, 9.0),
+            lookat: Point3::new(0.0, 0.8, 0.0),
+            focus_dist: 9.0,
+            ..CameraSettings::default()
+        },
+    }
+}
@@ -0,0 +1,160 @@
+use crate::ray::Color;
+
+use crate::rtvec3::RtVec3;
+
+use std::sync::OnceLock;
+
+// Spectral rendering
+//
+// In spectral mode every camera path carries a few wavelengths instead of red, green and blue:
+// a random hero wavelength and companions spaced evenly across the visible range. RGB material
+// colors are turned into smooth spectra on the fly, and the radiance each wavelength brings back
+// is weighted by the CIE color matching functions and converted to linear sRGB for the film.
+// When something on the path depends on the wavelength, like the refraction of dispersive
+// glass, the path follows the hero and the companions are dropped.
+
+// Visible range in nanometres.
+pub const WAVELENGTH_MIN: f64 = 380.0;
+pub const WAVELENGTH_MAX: f64 = 780.0;
+
+// Wavelengths carried by one path.
+pub const WAVELENGTH_SAMPLES: usize = 4;
+
+// Sodium D line, where glass catalogues quote the index of refraction.
+pub const D_LINE: f64 = 587.6;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SampledWavelengths {
+    pub lambda: [f64; WAVELENGTH_SAMPLES], // The hero first
+    pub secondary_terminated: bool,
+}
+
+impl SampledWavelengths {
+    // Hero wavelength at `u` in [0, 1) across the range, companions a quarter of the range apart
+    // (wrapping around).
+    pub fn sample(u: f64) -> Self {
+        let mut lambda = [0.0; WAVELENGTH_SAMPLES];
+        for (index, wavelength) in lambda.iter_mut().enumerate() {
+            let offset = (u + index as f64 / WAVELENGTH_SAMPLES as f64).fract();
+            *wavelength = WAVELENGTH_MIN + offset * (WAVELENGTH_MAX - WAVELENGTH_MIN);
+        }
+        SampledWavelengths {
+            lambda,
+            secondary_terminated: false,
+        }
+    }
+
+    pub fn hero(&self) -> f64 {
+        self.lambda[0]
+    }
+
+    // The path has taken a turn only the hero wavelength would take.
+    pub fn terminate_secondary(&mut self) {
+        self.secondary_terminated = true;
+    }
+
+    // Linear sRGB of the radiance `values` brought back at each wavelength. A flat spectrum of
+    // 1 comes out as white (1, 1, 1).
+    pub fn to_rgb(&self, values: [f64; WAVELENGTH_SAMPLES]) -> RtVec3 {
+        let count = if self.secondary_terminated { 1 } else { WAVELENGTH_SAMPLES };
+        let mut xyz = RtVec3::new(0.0, 0.0, 0.0);
+        for (value, wavelength) in values.iter().zip(self.lambda).take(count) {
+            xyz = xyz + *value * cie_xyz(wavelength);
+        }
+        // Uniform wavelength density, the range cancels against the white point's
+        let rgb = xyz_to_linear_srgb(xyz / count as f64);
+        let white = white_point();
+        RtVec3::new(rgb.x / white.x, rgb.y / white.y, rgb.z / white.z)
+    }
+}
+
+// CIE 1931 2° color matching functions x̄, ȳ, z̄ at `wavelength` nm, from the multi-lobe
+// Gaussian fit of Wyman, Sloan and Shirley (2013).
+pub fn cie_xyz(wavelength: f64) -> RtVec3 {
+    let lobe = |mean: f64, below: f64, above: f64| {
+        let t = (wavelength - mean) / if wavelength < mean { below } else { above };
+        (-0.5 * t * t).exp()
+    };
+    RtVec3::new(
+        1.056 * lobe(599.8, 37.9, 31.0) + 0.362 * lobe(442.0, 16.0, 26.7) - 0.065 * lobe(501.1, 20.4, 26.2),
+        0.821 * lobe(568.8, 46.9, 40.5) + 0.286 * lobe(530.9, 16.3, 31.1),
+        1.217 * lobe(437.0, 11.8, 36.0) + 0.681 * lobe(459.0, 26.0, 13.8),
+    )
+}
+
+// XYZ to linear sRGB (D65 primaries).
+pub fn xyz_to_linear_srgb(xyz: RtVec3) -> RtVec3 {
+    RtVec3::new(
+        3.2404542 * xyz.x - 1.5371385 * xyz.y - 0.4985314 * xyz.z,
+        -0.9692660 * xyz.x + 1.8760108 * xyz.y + 0.0415560 * xyz.z,
+        0.0556434 * xyz.x - 0.2040259 * xyz.y + 1.0572252 * xyz.z,
+    )
+}
+
+// sRGB of a flat spectrum averaged over the range, dividing by it white balances the output.
+fn white_point() -> RtVec3 {
+    static WHITE: OnceLock<RtVec3> = OnceLock::new();
+    *WHITE.get_or_init(|| {
+        let steps = 4000;
+        let mut xyz = RtVec3::new(0.0, 0.0, 0.0);
+        for step in 0..steps {
+            let wavelength = WAVELENGTH_MIN + (step as f64 + 0.5) / steps as f64 * (WAVELENGTH_MAX - WAVELENGTH_MIN);
+            xyz = xyz + cie_xyz(wavelength);
+        }
+        xyz_to_linear_srgb(xyz / steps as f64)
+    })
+}
+
+// Value at `wavelength` of a smooth spectrum for the RGB `color`: blue below about 490 nm, red
+// above about 590 nm and green in between, blended with smoothsteps. The three bands add up to
+// one, so grays stay flat and reflectances in [0, 1] stay in [0, 1].
+pub fn rgb_to_spectrum(color: Color, wavelength: f64) -> f64 {
+    let smoothstep = |edge0: f64, edge1: f64| {
+        let t = ((wavelength - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    };
+    let red = smoothstep(570.0, 610.0);
+    let blue = 1.0 - smoothstep(470.0, 510.0);
+    let green = 1.0 - red - blue;
+    color.r * red + color.g * green + color.b * blue
+}
+
+// How a glass's index of refraction changes with wavelength.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dispersion {
+    // n = a + b / λ², λ in micrometres
+    Cauchy { a: f64, b: f64 },
+    // n² = 1 + Σ b λ² / (λ² - c), λ in micrometres and c in µm²
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+impl Dispersion {
+    // Schott N-BK7, common optical crown glass.
+    pub fn bk7() -> Self {
+        Dispersion::Sellmeier {
+            b: [1.03961212, 0.231792344, 1.01046945],
+            c: [0.00600069867, 0.0200179144, 103.560653],
+        }
+    }
+
+    // Schott SF11, dense flint glass with strong dispersion.
+    pub fn dense_flint() -> Self {
+        Dispersion::Sellmeier {
+            b: [1.73759695, 0.313747346, 1.89878101],
+            c: [0.013188707, 0.0623068142, 155.23629],
+        }
+    }
+
+    // Index of refraction at `wavelength` nm.
+    pub fn index(&self, wavelength: f64) -> f64 {
+        let micrometres = wavelength / 1000.0;
+        let squared = micrometres * micrometres;
+        match self {
+            Dispersion::Cauchy { a, b } => a + b / squared,
+            Dispersion::Sellmeier { b, c } => {
+                let sum: f64 = b.iter().zip(c).map(|(b, c)| b * squared / (squared - c)).sum();
+                (1.0 + sum).sqrt()
+            }
+        }
+    }
+}
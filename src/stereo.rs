@@ -0,0 +1,51 @@
+// Stereoscopic rendering: both eyes are rendered into one image, left eye first.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StereoLayout {
+    SideBySide, // Left eye on the left half, output is twice as wide
+    TopBottom,  // Left eye on the top half, output is twice as tall
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StereoMode {
+    // Eyes shifted along the camera u axis, sharing the focus plane viewport so both views
+    // converge at the focus distance (zero parallax there)
+    OffAxis,
+    // Omni-directional stereo: an equirectangular panorama where every ray starts on a circle
+    // of interocular diameter, tangent to its direction, for 360 degree headset viewing
+    OmniDirectional,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stereo {
+    pub mode: StereoMode,
+    pub layout: StereoLayout,
+    pub interocular: f64, // Distance between the eyes, in world units
+}
+
+impl Stereo {
+    pub fn new(mode: StereoMode, layout: StereoLayout, interocular: f64) -> Self {
+        Stereo {
+            mode,
+            layout,
+            interocular,
+        }
+    }
+
+    // Size of the combined image for eyes of `width` x `height` pixels.
+    pub fn output_dimensions(&self, width: u32, height: u32) -> (u32, u32) {
+        match self.layout {
+            StereoLayout::SideBySide => (width * 2, height),
+            StereoLayout::TopBottom => (width, height * 2),
+        }
+    }
+
+    // Which eye output pixel i, j belongs to (-1.0 left, 1.0 right) and its pixel within that eye.
+    pub fn eye_pixel(&self, i: u32, j: u32, width: u32, height: u32) -> (f64, u32, u32) {
+        match self.layout {
+            StereoLayout::SideBySide if i >= width => (1.0, i - width, j),
+            StereoLayout::TopBottom if j >= height => (1.0, i, j - height),
+            _ => (-1.0, i, j),
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use crate::material::Material;
+
+use crate::mesh::Mesh;
+
+use crate::rtvec3::Point3;
+
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+use std::rc::Rc;
+
+// Loads an ASCII or binary STL file as a mesh with one material. Facets are flat shaded, the
+// stored facet normals are ignored in favour of the vertex winding.
+pub fn load_stl(path: &Path, material: Rc<dyn Material>) -> std::io::Result<Mesh> {
+    let bytes = fs::read(path)?;
+    let invalid = |message: &str| Error::new(ErrorKind::InvalidData, format!("{:?}: {}", path, message));
+
+    // Binary files may also start with "solid", the size matching the facet count decides
+    let positions = match binary_facet_count(&bytes) {
+        Some(count) => (0..count)
+            .flat_map(|facet| {
+                let facet = &bytes[84 + 50 * facet..84 + 50 * (facet + 1)];
+                (1..4).map(move |vertex| {
+                    let value = |axis: usize| {
+                        let start = 12 * vertex + 4 * axis;
+                        f32::from_le_bytes([facet[start], facet[start + 1], facet[start + 2], facet[start + 3]]) as f64
+                    };
+                    Point3::new(value(0), value(1), value(2))
+                })
+            })
+            .collect(),
+        None if bytes.starts_with(b"solid") => parse_ascii(&String::from_utf8_lossy(&bytes)).map_err(|message| invalid(&message))?,
+        None => return Err(invalid("not an STL file")),
+    };
+
+    let triangles = (0..positions.len() / 3).map(|facet| [3 * facet, 3 * facet + 1, 3 * facet + 2]).collect();
+    Ok(Mesh::new(positions, None, None, triangles, material))
+}
+
+// 80 byte header, facet count, then 50 bytes per facet.
+fn binary_facet_count(bytes: &[u8]) -> Option<usize> {
+    let count = u32::from_le_bytes(bytes.get(80..84)?.try_into().ok()?) as usize;
+    (bytes.len() == 84 + 50 * count).then_some(count)
+}
+
+// The vertices of every facet, three "vertex x y z" lines each.
+fn parse_ascii(text: &str) -> Result<Vec<Point3>, String> {
+    let mut positions = Vec::new();
+    let mut vertices_in_facet = 0;
+    for (number, line) in text.lines().enumerate() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.first() {
+            Some(&"vertex") => {
+                let coordinates: Vec<f64> = words[1..].iter().filter_map(|word| word.parse().ok()).collect();
+                if coordinates.len() != 3 {
+                    return Err(format!("line {}: expected \"vertex x y z\"", number + 1));
+                }
+                positions.push(Point3::new(coordinates[0], coordinates[1], coordinates[2]));
+                vertices_in_facet += 1;
+            }
+            Some(&"endfacet") => {
+                if vertices_in_facet != 3 {
+                    return Err(format!("line {}: facet with {} vertices", number + 1, vertices_in_facet));
+                }
+                vertices_in_facet = 0;
+            }
+            _ => {}
+        }
+    }
+    Ok(positions)
+}
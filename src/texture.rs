@@ -0,0 +1,190 @@
+use crate::image::Image;
+
+use crate::perlin::Perlin;
+
+use crate::ray::Color;
+
+use crate::rtvec3::{Point3, RtVec3};
+
+use crate::scene::TextureDescription;
+
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+// Color varying over a surface, looked up by surface coordinates u, v in [0, 1] and hit point p.
+pub trait Texture {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color;
+
+    // The single color of textures that don't vary, `None` for every other texture.
+    fn solid_color(&self) -> Option<Color> {
+        None
+    }
+
+    // Parameters for saving the texture to a scene file, `None` if it can't be written out.
+    fn describe(&self) -> Option<TextureDescription> {
+        None
+    }
+}
+
+pub struct SolidColor {
+    pub albedo: Color,
+}
+
+impl SolidColor {
+    pub fn new(albedo: Color) -> Self {
+        SolidColor { albedo }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _p: &Point3) -> Color {
+        self.albedo
+    }
+
+    fn solid_color(&self) -> Option<Color> {
+        Some(self.albedo)
+    }
+}
+
+// 3D checkerboard of cubes `scale` wide, alternating between two textures.
+pub struct CheckerTexture {
+    pub scale: f64,
+    pub inv_scale: f64,
+    pub even: Rc<dyn Texture>,
+    pub odd: Rc<dyn Texture>,
+}
+
+impl CheckerTexture {
+    pub fn new(scale: f64, even: Rc<dyn Texture>, odd: Rc<dyn Texture>) -> Self {
+        CheckerTexture {
+            scale,
+            inv_scale: 1.0 / scale,
+            even,
+            odd,
+        }
+    }
+
+    pub fn from_colors(scale: f64, even: Color, odd: Color) -> Self {
+        CheckerTexture::new(scale, Rc::new(SolidColor::new(even)), Rc::new(SolidColor::new(odd)))
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        let x = (self.inv_scale * p.x).floor() as i64;
+        let y = (self.inv_scale * p.y).floor() as i64;
+        let z = (self.inv_scale * p.z).floor() as i64;
+        if (x + y + z) % 2 == 0 {
+            self.even.value(u, v, p)
+        } else {
+            self.odd.value(u, v, p)
+        }
+    }
+
+    fn describe(&self) -> Option<TextureDescription> {
+        Some(TextureDescription::Checker {
+            scale: self.scale,
+            even: self.even.solid_color()?,
+            odd: self.odd.solid_color()?,
+        })
+    }
+}
+
+// How surface coordinates outside [0, 1] land on an image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WrapMode {
+    Clamp,          // Stretch the edge texels outwards
+    Repeat,         // Tile the image
+    MirroredRepeat, // Tile the image, flipping every other copy
+}
+
+impl WrapMode {
+    fn apply(self, t: f64) -> f64 {
+        match self {
+            WrapMode::Clamp => t.clamp(0.0, 1.0),
+            WrapMode::Repeat => t - t.floor(),
+            WrapMode::MirroredRepeat => {
+                let t = t.rem_euclid(2.0);
+                if t > 1.0 { 2.0 - t } else { t }
+            }
+        }
+    }
+}
+
+// Image wrapped over the surface by its u, v coordinates (v = 0 is the bottom row).
+pub struct ImageTexture {
+    pub image: Option<Image>,
+    pub source: Option<PathBuf>, // File the image was loaded from, scene files refer to it
+    pub wrap_u: WrapMode,
+    pub wrap_v: WrapMode,
+}
+
+impl ImageTexture {
+    pub fn new(image: Image) -> Self {
+        ImageTexture { image: Some(image), source: None, wrap_u: WrapMode::Clamp, wrap_v: WrapMode::Clamp }
+    }
+
+    pub fn with_wrap(mut self, wrap_u: WrapMode, wrap_v: WrapMode) -> Self {
+        self.wrap_u = wrap_u;
+        self.wrap_v = wrap_v;
+        self
+    }
+
+    // A missing or unreadable file gives the bright cyan "no image" texture rather than an error.
+    pub fn load(path: &Path) -> Self {
+        let image = match Image::load_netpbm(path) {
+            Ok(image) => Some(image),
+            Err(error) => {
+                eprintln!("Could not load texture {:?}: {}", path, error);
+                None
+            }
+        };
+        ImageTexture { image, source: Some(path.to_path_buf()), wrap_u: WrapMode::Clamp, wrap_v: WrapMode::Clamp }
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: &Point3) -> Color {
+        let image = match &self.image {
+            Some(image) if image.width > 0 && image.height > 0 => image,
+            _ => return Color::new_rgb(0.0, 1.0, 1.0),
+        };
+        let u = self.wrap_u.apply(u);
+        let v = 1.0 - self.wrap_v.apply(v);
+        let i = ((u * image.width as f64) as u32).min(image.width - 1);
+        let j = ((v * image.height as f64) as u32).min(image.height - 1);
+        Color::from_vec(image.pixel(i, j))
+    }
+
+    fn describe(&self) -> Option<TextureDescription> {
+        Some(TextureDescription::Image { path: self.source.clone()? })
+    }
+}
+
+// Marble-like Perlin turbulence, `scale` sets the frequency of the veins.
+pub struct NoiseTexture {
+    pub noise: Perlin,
+    pub scale: f64,
+}
+
+impl NoiseTexture {
+    pub fn new(scale: f64) -> Self {
+        NoiseTexture {
+            noise: Perlin::new(),
+            scale,
+        }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Point3) -> Color {
+        let shade = 0.5 * (1.0 + f64::sin(self.scale * p.z + 10.0 * self.noise.turbulence(p, 7)));
+        Color::from_vec(RtVec3::new(shade, shade, shade))
+    }
+
+    // The noise itself comes from the random generator, so it only matches when the scene is
+    // built from the same seed.
+    fn describe(&self) -> Option<TextureDescription> {
+        Some(TextureDescription::Noise { scale: self.scale })
+    }
+}
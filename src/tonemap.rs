@@ -0,0 +1,122 @@
+use crate::rtvec3::RtVec3;
+
+// Tone mapping: compresses the unbounded linear radiance the renderer accumulates into the
+// displayable [0, 1] range, before the sRGB transfer function encodes it for the image file.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ToneMapOperator {
+    // Leave values untouched, anything above 1.0 clips to white
+    Clamp,
+    // x / (1 + x), never reaches white
+    Reinhard,
+    // Reinhard that maps `white` (in linear radiance) to exactly 1.0. A white point that isn't
+    // positive tone maps as plain Reinhard, the limit for an infinite white.
+    ExtendedReinhard { white: f64 },
+    // John Hable's filmic curve from Uncharted 2
+    Hable,
+    // Stephen Hill's fit of the ACES reference rendering and output transforms
+    AcesFitted,
+}
+
+impl ToneMapOperator {
+    // Extended Reinhard with its white point checked, so a bad white point is reported rather
+    // than quietly rendered as plain Reinhard.
+    pub fn extended_reinhard(white: f64) -> Result<Self, String> {
+        if white.is_nan() || white <= 0.0 {
+            return Err(format!("the extended Reinhard white point must be a positive number, got {}", white));
+        }
+        Ok(ToneMapOperator::ExtendedReinhard { white })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ToneMapper {
+    pub operator: ToneMapOperator,
+    pub exposure: f64, // In stops, every +1.0 doubles the incoming radiance
+}
+
+impl Default for ToneMapper {
+    fn default() -> Self {
+        ToneMapper::new(ToneMapOperator::Clamp, 0.0)
+    }
+}
+
+impl ToneMapper {
+    pub fn new(operator: ToneMapOperator, exposure: f64) -> Self {
+        ToneMapper {
+            operator,
+            exposure,
+        }
+    }
+
+    // Linear radiance in, display referred linear values in [0, 1] out.
+    pub fn tone_map(&self, color: RtVec3) -> RtVec3 {
+        let exposed = color * f64::powf(2.0, self.exposure);
+        let mapped = match self.operator {
+            ToneMapOperator::Clamp => exposed,
+            ToneMapOperator::Reinhard => per_channel(exposed, |x| x / (1.0 + x)),
+            ToneMapOperator::ExtendedReinhard { white } if white.is_nan() || white <= 0.0 => {
+                per_channel(exposed, |x| x / (1.0 + x))
+            }
+            ToneMapOperator::ExtendedReinhard { white } => {
+                let white_squared = white * white;
+                per_channel(exposed, |x| x * (1.0 + x / white_squared) / (1.0 + x))
+            }
+            ToneMapOperator::Hable => {
+                // The curve is tuned for an exposure bias of 2, then normalised so linear white (11.2) hits 1.0
+                let white_scale = 1.0 / hable_partial(11.2);
+                per_channel(exposed, |x| hable_partial(x * 2.0) * white_scale)
+            }
+            ToneMapOperator::AcesFitted => aces_fitted(exposed),
+        };
+        per_channel(mapped, |x| x.clamp(0.0, 1.0))
+    }
+
+    // Tone maps and sRGB encodes a linear radiance value, ready to be quantized.
+    pub fn map(&self, color: RtVec3) -> RtVec3 {
+        per_channel(self.tone_map(color), srgb_oetf)
+    }
+}
+
+fn per_channel(color: RtVec3, f: impl Fn(f64) -> f64) -> RtVec3 {
+    RtVec3::new(f(color.x()), f(color.y()), f(color.z()))
+}
+
+fn hable_partial(x: f64) -> f64 {
+    const A: f64 = 0.15; // Shoulder strength
+    const B: f64 = 0.50; // Linear strength
+    const C: f64 = 0.10; // Linear angle
+    const D: f64 = 0.20; // Toe strength
+    const E: f64 = 0.02; // Toe numerator
+    const F: f64 = 0.30; // Toe denominator
+    ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+}
+
+fn aces_fitted(color: RtVec3) -> RtVec3 {
+    // sRGB => XYZ => D65_2_D60 => AP1 => RRT_SAT
+    let input = RtVec3::new(
+        0.59719 * color.x() + 0.35458 * color.y() + 0.04823 * color.z(),
+        0.07600 * color.x() + 0.90834 * color.y() + 0.01566 * color.z(),
+        0.02840 * color.x() + 0.13383 * color.y() + 0.83777 * color.z(),
+    );
+    let fitted = per_channel(input, |v| {
+        (v * (v + 0.0245786) - 0.000090537) / (v * (0.983729 * v + 0.4329510) + 0.238081)
+    });
+    // ODT_SAT => XYZ => D60_2_D65 => sRGB
+    RtVec3::new(
+        1.60475 * fitted.x() - 0.53108 * fitted.y() - 0.07367 * fitted.z(),
+        -0.10208 * fitted.x() + 1.10813 * fitted.y() - 0.00605 * fitted.z(),
+        -0.00327 * fitted.x() - 0.07276 * fitted.y() + 1.07602 * fitted.z(),
+    )
+}
+
+// Exact sRGB opto-electronic transfer function (linear to encoded), replacing the gamma 2.0 square root.
+pub fn srgb_oetf(linear_component: f64) -> f64 {
+    if linear_component <= 0.0 {
+        0.0
+    } else if linear_component <= 0.0031308 {
+        12.92 * linear_component
+    } else {
+        1.055 * linear_component.powf(1.0 / 2.4) - 0.055
+    }
+}
@@ -0,0 +1,124 @@
+#[cfg(test)]
+mod animation_tests {
+    use raytracing_in_a_weekend::animation::{Animation, CameraKeyframe, CameraPath};
+    use raytracing_in_a_weekend::camera::Camera;
+    use raytracing_in_a_weekend::hit::{HittableList, Sphere};
+    use raytracing_in_a_weekend::image::ImageFormat;
+    use raytracing_in_a_weekend::material::new_material_lambertian_color_float;
+    use raytracing_in_a_weekend::ray::Color;
+    use raytracing_in_a_weekend::{Point3, RtVec3};
+
+    use std::fs;
+    use std::rc::Rc;
+
+    fn key(time: f64, x: f64, fov: f64) -> CameraKeyframe {
+        CameraKeyframe::new(time, Point3::new(x, 1.0, 0.0), Point3::new(0.0, 0.0, -1.0), fov, 1.0)
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn path_passes_through_keyframes() {
+        let path = CameraPath::new(vec![key(0.0, 0.0, 90.0), key(1.0, 3.0, 60.0), key(3.0, -2.0, 40.0), key(4.0, 1.0, 50.0)]).unwrap();
+        for (time, x, fov) in [(0.0, 0.0, 90.0), (1.0, 3.0, 60.0), (3.0, -2.0, 40.0), (4.0, 1.0, 50.0)] {
+            let sample = path.sample(time);
+            assert_close(sample.lookfrom.x, x);
+            assert_close(sample.fov, fov);
+        }
+        // Clamped outside the keyframes
+        assert_close(path.sample(-1.0).lookfrom.x, 0.0);
+        assert_close(path.sample(9.0).lookfrom.x, 1.0);
+    }
+
+    #[test]
+    fn evenly_moving_keyframes_give_linear_motion() {
+        let path = CameraPath::new(vec![key(0.0, 0.0, 90.0), key(1.0, 1.0, 80.0), key(3.0, 3.0, 60.0)]).unwrap();
+        for time in [0.25, 0.5, 1.5, 2.75] {
+            let sample = path.sample(time);
+            assert_close(sample.lookfrom.x, time);
+            assert_close(sample.fov, 90.0 - 10.0 * time);
+        }
+    }
+
+    #[test]
+    fn looping_path_is_continuous_across_the_seam() {
+        let path = CameraPath::looping(vec![key(0.0, 0.0, 90.0), key(1.0, 2.0, 90.0), key(2.0, 1.0, 90.0)], 3.0).unwrap();
+        assert_close(path.sample(3.0).lookfrom.x, 0.0);
+        assert_close(path.sample(4.0).lookfrom.x, 2.0);
+        let before = path.sample(3.0 - 1e-6).lookfrom.x;
+        let after = path.sample(3.0 + 1e-6).lookfrom.x;
+        assert!((before - after).abs() < 1e-4);
+    }
+
+    #[test]
+    fn paths_reject_keyframes_sharing_a_time() {
+        assert!(CameraPath::new(Vec::new()).is_err());
+        assert!(CameraPath::new(vec![key(1.0, 0.0, 90.0), key(0.0, 1.0, 90.0), key(1.0, 2.0, 90.0)]).is_err());
+        assert!(CameraPath::new(vec![key(0.0, 0.0, 90.0), key(f64::NAN, 1.0, 90.0)]).is_err());
+        assert!(CameraPath::looping(vec![key(0.0, 0.0, 90.0), key(2.0, 1.0, 90.0)], 2.0).is_err());
+        // Out of order but distinct is fine
+        let path = CameraPath::new(vec![key(1.0, 1.0, 90.0), key(0.0, 0.0, 90.0)]).unwrap();
+        assert_close(path.sample(0.5).lookfrom.x, 0.5);
+    }
+
+    // 8 x 8 pixels of a grey sphere, one sample each.
+    fn small_camera() -> Camera {
+        let mut world = HittableList::new();
+        world.add(Rc::new(Sphere::new(Point3::new(0.0, 0.0, -1.0), 0.5, new_material_lambertian_color_float(0.5, 0.5, 0.5))));
+        Camera::new(
+            world,
+            Color::new_rgb(0.0, 0.0, 0.0),
+            1.0,
+            90.0,
+            0.0,
+            1.0,
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.0, 0.0, -1.0),
+            RtVec3::new(0.0, 1.0, 0.0),
+            8,
+            1,
+            2,
+        )
+    }
+
+    #[test]
+    fn animation_writes_numbered_frames() {
+        let dir = std::env::temp_dir().join("raytracing_in_a_weekend_tests").join("animation");
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut camera = small_camera();
+        let path = CameraPath::new(vec![key(0.0, -1.0, 90.0), key(1.0, 1.0, 70.0)]).unwrap();
+        Animation::new(path, 0.0, 1.0, 3, &dir).render(&mut camera).unwrap();
+
+        for frame in 1..=3 {
+            assert!(dir.join(format!("frame_{:04}.ppm", frame)).exists());
+        }
+        assert!(!dir.join("frame_0004.ppm").exists());
+    }
+
+    #[test]
+    fn frames_are_named_after_their_format() {
+        let dir = std::env::temp_dir().join("raytracing_in_a_weekend_tests").join("animation_pfm");
+        let _ = fs::remove_dir_all(&dir);
+        let mut camera = small_camera().with_format(ImageFormat::Pfm);
+        let path = CameraPath::new(vec![key(0.0, -1.0, 90.0), key(1.0, 1.0, 70.0)]).unwrap();
+        Animation::new(path, 0.0, 1.0, 2, &dir).render(&mut camera).unwrap();
+
+        assert!(dir.join("frame_0001.pfm").exists() && dir.join("frame_0002.pfm").exists());
+        assert!(!dir.join("frame_0001.ppm").exists());
+    }
+
+    #[test]
+    fn failed_frame_writes_are_reported() {
+        let dir = std::env::temp_dir().join("raytracing_in_a_weekend_tests").join("animation_blocked");
+        let _ = fs::remove_dir_all(&dir);
+        // A directory where the first frame should go can't be opened as a file
+        fs::create_dir_all(dir.join("frame_0001.ppm")).unwrap();
+
+        let mut camera = small_camera();
+        let path = CameraPath::new(vec![key(0.0, -1.0, 90.0), key(1.0, 1.0, 70.0)]).unwrap();
+        assert!(Animation::new(path, 0.0, 1.0, 2, &dir).render(&mut camera).is_err());
+    }
+}
@@ -0,0 +1,267 @@
+#[cfg(test)]
+mod camera_tests {
+    use raytracing_in_a_weekend::aov::{aov_path, Aov};
+    use raytracing_in_a_weekend::camera::Camera;
+    use raytracing_in_a_weekend::crop::{CropOutput, CropWindow};
+    use raytracing_in_a_weekend::hit::{HittableList, Sphere};
+    use raytracing_in_a_weekend::filter::Filter;
+    use raytracing_in_a_weekend::material::{new_material_dielectric, new_material_lambertian_color_float, new_material_metal_color_float, Material};
+    use raytracing_in_a_weekend::ray::Color;
+    use raytracing_in_a_weekend::stereo::{Stereo, StereoLayout, StereoMode};
+    use raytracing_in_a_weekend::{Point3, RtVec3};
+
+    use std::fs;
+    use std::io::Error;
+    use std::path::PathBuf;
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn scratch_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join("raytracing_in_a_weekend_tests");
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    fn setup() -> Camera {
+        setup_with_center(new_material_lambertian_color_float(0.1, 0.2, 0.5))
+    }
+
+    // The test scene with another material on the centre sphere.
+    fn setup_with_center(center: Rc<dyn Material>) -> Camera {
+        let mut world = HittableList::new();
+        world.add(Rc::new(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, new_material_lambertian_color_float(0.8, 0.8, 0.0))));
+        world.add(Rc::new(Sphere::new(Point3::new(0.0, 0.0, -1.2), 0.5, center)));
+        world.add(Rc::new(Sphere::new(Point3::new(-1.0, 0.0, -1.0), 0.5, new_material_dielectric(1.5))));
+        world.add(Rc::new(Sphere::new(Point3::new(1.0, 0.0, -1.0), 0.5, new_material_metal_color_float(0.8, 0.6, 0.2, 0.3))));
+
+        Camera::new(
+            world,
+            Color::new_rgb(0.0, 0.0, 0.0),
+            16.0 / 9.0,
+            90.0,
+            0.6,
+            1.0,
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.0, 0.0, -1.0),
+            RtVec3::new(0.0, 1.0, 0.0),
+            32,
+            4,
+            8,
+        )
+    }
+
+    #[test]
+    fn resumed_render_matches_uninterrupted() {
+        let uninterrupted = scratch_path("uninterrupted.ppm");
+        let resumed = scratch_path("resumed.ppm");
+        let checkpoint = scratch_path("resumed.checkpoint");
+        let _ = fs::remove_file(&checkpoint);
+
+        setup().with_seed(7).with_output(&uninterrupted).render();
+
+        // First job stops part way through, a fresh camera picks the render back up.
+        setup().with_seed(7).with_output(&resumed).with_checkpoint(&checkpoint, 2).render_until(7).unwrap();
+        assert!(checkpoint.exists());
+        setup().with_seed(7).with_output(&resumed).with_checkpoint(&checkpoint, 2).resume().unwrap();
+
+        assert!(!checkpoint.exists());
+        assert_eq!(fs::read(&uninterrupted).unwrap(), fs::read(&resumed).unwrap());
+    }
+
+    #[test]
+    fn resume_rejects_mismatched_checkpoint() {
+        let checkpoint = scratch_path("mismatched.checkpoint");
+        let _ = fs::remove_file(&checkpoint);
+        setup().with_seed(3).with_checkpoint(&checkpoint, 1).render_until(2).unwrap();
+
+        let other = Camera::new(
+            HittableList::new(),
+            Color::new_rgb(0.0, 0.0, 0.0),
+            1.0,
+            90.0,
+            0.0,
+            1.0,
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.0, 0.0, -1.0),
+            RtVec3::new(0.0, 1.0, 0.0),
+            16,
+            4,
+            8,
+        );
+        assert!(other.with_checkpoint(&checkpoint, 1).resume().is_err());
+        let _ = fs::remove_file(&checkpoint);
+    }
+
+    #[test]
+    fn resume_rejects_checkpoint_of_another_render_the_same_size() {
+        let checkpoint = scratch_path("another_render.checkpoint");
+        let output = scratch_path("another_render.ppm");
+        let _ = fs::remove_file(&checkpoint);
+        setup().with_seed(3).with_output(&output).with_checkpoint(&checkpoint, 1).render_until(2).unwrap();
+
+        let error = |camera: Camera| camera.with_output(&output).with_checkpoint(&checkpoint, 1).resume().unwrap_err().to_string();
+        assert!(error(setup().with_seed(4)).contains("seed"));
+        assert!(error(setup().with_seed(3).with_filter(Filter::Tent { radius: 1.0 })).contains("filter"));
+        let red = new_material_lambertian_color_float(0.8, 0.1, 0.1);
+        assert!(error(setup_with_center(red).with_seed(3)).contains("different scene"));
+
+        // The render it was written for still picks it up
+        setup().with_seed(3).with_output(&output).with_checkpoint(&checkpoint, 1).resume().unwrap();
+        assert!(!checkpoint.exists());
+    }
+
+    // Reads back the first channel of a little endian PFM written by the renderer.
+    fn read_pfm_channel(path: &PathBuf) -> (usize, usize, Vec<f32>) {
+        let bytes = fs::read(path).unwrap();
+        let mut header_lines = 0;
+        let mut offset = 0;
+        while header_lines < 3 {
+            if bytes[offset] == b'\n' {
+                header_lines += 1;
+            }
+            offset += 1;
+        }
+        let header = String::from_utf8_lossy(&bytes[..offset]).to_string();
+        let dims: Vec<usize> = header.lines().nth(1).unwrap().split_whitespace().map(|v| v.parse().unwrap()).collect();
+        let values = bytes[offset..]
+            .chunks(12)
+            .map(|pixel| f32::from_le_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]))
+            .collect();
+        (dims[0], dims[1], values)
+    }
+
+    #[test]
+    fn aov_passes_are_written() {
+        let output = scratch_path("aovs.ppm");
+        setup().with_seed(11).with_output(&output).with_aovs(&Aov::ALL).render();
+
+        for aov in Aov::ALL {
+            assert!(aov_path(&output, aov).exists(), "missing {} pass", aov.name());
+        }
+
+        // PFM rows run bottom to top: the last row is the top of the image, which only sees sky.
+        let (width, height, object_ids) = read_pfm_channel(&aov_path(&output, Aov::ObjectId));
+        assert_eq!((width, height), (32, 18));
+        assert_eq!(object_ids[(height - 1) * width + width / 2], 0.0);
+        // The centre of the frame is the blue sphere, the second object in the world.
+        assert_eq!(object_ids[(height / 2) * width + width / 2], 2.0);
+
+        let (_, _, depth) = read_pfm_channel(&aov_path(&output, Aov::Depth));
+        let centre_depth = depth[(height / 2) * width + width / 2];
+        assert!(centre_depth > 0.6 && centre_depth < 0.8, "unexpected depth {}", centre_depth);
+    }
+
+    fn ppm_dimensions(path: &PathBuf) -> (u32, u32) {
+        let contents = fs::read_to_string(path).unwrap();
+        let dims: Vec<u32> = contents.lines().nth(1).unwrap().split_whitespace().map(|v| v.parse().unwrap()).collect();
+        (dims[0], dims[1])
+    }
+
+    #[test]
+    fn stereo_layouts_double_the_frame() {
+        let side_by_side = scratch_path("stereo_sbs.ppm");
+        let stereo = Stereo::new(StereoMode::OffAxis, StereoLayout::SideBySide, 0.064);
+        setup().with_seed(1).with_output(&side_by_side).with_stereo(stereo).render();
+        assert_eq!(ppm_dimensions(&side_by_side), (64, 18));
+
+        let top_bottom = scratch_path("stereo_ods.ppm");
+        let stereo = Stereo::new(StereoMode::OmniDirectional, StereoLayout::TopBottom, 0.064);
+        setup().with_seed(1).with_output(&top_bottom).with_stereo(stereo).render();
+        assert_eq!(ppm_dimensions(&top_bottom), (32, 36));
+    }
+
+    #[test]
+    fn stereo_eye_lookup() {
+        let stereo = Stereo::new(StereoMode::OffAxis, StereoLayout::SideBySide, 0.064);
+        assert_eq!(stereo.eye_pixel(3, 5, 32, 18), (-1.0, 3, 5));
+        assert_eq!(stereo.eye_pixel(35, 5, 32, 18), (1.0, 3, 5));
+        let stereo = Stereo::new(StereoMode::OffAxis, StereoLayout::TopBottom, 0.064);
+        assert_eq!(stereo.eye_pixel(3, 17, 32, 18), (-1.0, 3, 17));
+        assert_eq!(stereo.eye_pixel(3, 20, 32, 18), (1.0, 3, 2));
+    }
+
+    fn ppm_pixels(path: &PathBuf) -> Vec<[u32; 3]> {
+        let contents = fs::read_to_string(path).unwrap();
+        let values: Vec<u32> = contents.lines().skip(3).flat_map(|line| line.split_whitespace()).map(|v| v.parse().unwrap()).collect();
+        values.chunks(3).map(|pixel| [pixel[0], pixel[1], pixel[2]]).collect()
+    }
+
+    #[test]
+    fn crop_window_writes_only_the_region() {
+        let cropped = scratch_path("crop_pixels.ppm");
+        let window = CropWindow::Pixels { min: (8, 0), max: (24, 9) };
+        setup().with_seed(5).with_output(&cropped).with_crop(window, CropOutput::Cropped).render();
+        assert_eq!(ppm_dimensions(&cropped), (16, 9));
+
+        // The same window given as fractions of the frame
+        let normalized = scratch_path("crop_normalized.ppm");
+        let window = CropWindow::Normalized { min: (0.25, 0.0), max: (0.75, 0.5) };
+        setup().with_seed(5).with_output(&normalized).with_crop(window, CropOutput::Cropped).render();
+        assert_eq!(fs::read(&cropped).unwrap(), fs::read(&normalized).unwrap());
+    }
+
+    #[test]
+    fn full_frame_crop_leaves_the_rest_black() {
+        let output = scratch_path("crop_full_frame.ppm");
+        let window = CropWindow::Pixels { min: (8, 0), max: (24, 9) };
+        setup().with_seed(5).with_output(&output).with_crop(window, CropOutput::FullFrame).render();
+        assert_eq!(ppm_dimensions(&output), (32, 18));
+
+        let pixels = ppm_pixels(&output);
+        for j in 0..18 {
+            for i in 0..32 {
+                let inside = (8..24).contains(&i) && j < 9;
+                let black = pixels[j * 32 + i] == [0, 0, 0];
+                assert_eq!(inside, !black, "pixel {}, {}", i, j);
+            }
+        }
+    }
+
+    #[test]
+    fn threaded_render_matches_single_thread() {
+        let single = scratch_path("single_thread.ppm");
+        let threaded = scratch_path("threaded.ppm");
+        setup().with_seed(9).with_output(&single).render();
+        Camera::render_threaded(|| Ok(setup().with_seed(9).with_output(&threaded)), 3).unwrap();
+        assert_eq!(fs::read(&single).unwrap(), fs::read(&threaded).unwrap());
+    }
+
+    #[test]
+    fn threaded_render_reports_cameras_that_fail_to_build() {
+        let output = scratch_path("threaded_unbuilt.ppm");
+        assert!(Camera::render_threaded(|| Err(Error::other("no model")), 2).is_err());
+
+        // Only the render threads' cameras fail
+        let built = AtomicUsize::new(0);
+        let make_camera = || match built.fetch_add(1, Ordering::SeqCst) {
+            0 => Ok(setup().with_output(&output)),
+            _ => Err(Error::other("no model")),
+        };
+        assert_eq!(Camera::render_threaded(make_camera, 2).unwrap_err().to_string(), "no model");
+    }
+
+    #[test]
+    fn threaded_render_checkpoints_and_resumes() {
+        let uninterrupted = scratch_path("threaded_uninterrupted.ppm");
+        let resumed = scratch_path("threaded_resumed.ppm");
+        let checkpoint = scratch_path("threaded.checkpoint");
+        let _ = fs::remove_file(&checkpoint);
+        setup().with_seed(13).with_output(&uninterrupted).render();
+
+        // Writing the image fails (the output is a directory), the checkpoint of the last block survives
+        let blocked = scratch_path("threaded_blocked");
+        fs::create_dir_all(&blocked).unwrap();
+        let camera = || setup().with_seed(13).with_checkpoint(&checkpoint, 4);
+        assert!(Camera::render_threaded(|| Ok(camera().with_output(&blocked)), 3).is_err());
+        assert!(checkpoint.exists());
+
+        Camera::render_threaded(|| Ok(camera().with_output(&resumed)), 2).unwrap();
+        assert!(!checkpoint.exists());
+        assert_eq!(fs::read(&uninterrupted).unwrap(), fs::read(&resumed).unwrap());
+
+        // A checkpoint left by a single threaded job is picked up too
+        setup().with_seed(13).with_output(&resumed).with_checkpoint(&checkpoint, 4).render_until(7).unwrap();
+        Camera::render_threaded(|| Ok(camera().with_output(&resumed)), 3).unwrap();
+        assert_eq!(fs::read(&uninterrupted).unwrap(), fs::read(&resumed).unwrap());
+    }
+}
@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod cli_tests {
+    use raytracing_in_a_weekend::cli::{parse_args, usage, Command, RenderOptions};
+    use raytracing_in_a_weekend::image::ImageFormat;
+
+    use std::path::PathBuf;
+
+    const SCENES: [&str; 2] = ["spheres", "demo"];
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()), &SCENES)
+    }
+
+    fn render_options(args: &[&str]) -> RenderOptions {
+        match parse(args) {
+            Ok(Command::Render(options)) => options,
+            other => panic!("expected render options, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn defaults_match_the_original_render() {
+        let options = render_options(&[]);
+        assert_eq!((options.width, options.samples_per_pixel, options.max_depth), (1920, 500, 50));
+        assert_eq!(options.seed, 2024);
+        assert_eq!(options.scene, "spheres");
+        assert_eq!(options.output, PathBuf::from("image.ppm"));
+        assert_eq!(options.format, ImageFormat::Ppm);
+        assert!(options.threads >= 1);
+        assert_eq!(options.checkpoint, None);
+        assert!(!options.spectral);
+    }
+
+    #[test]
+    fn flags_are_parsed() {
+        let options = render_options(&[
+            "--width", "640", "--spp=16", "--max-depth", "8", "--seed", "7",
+            "--scene", "demo", "--output", "out/frame.ppm", "--threads", "3", "--format", "ppm-binary",
+            "--save-scene", "demo.toml", "--checkpoint", "out/frame.checkpoint", "--spectral",
+        ]);
+        assert_eq!((options.width, options.samples_per_pixel, options.max_depth), (640, 16, 8));
+        assert_eq!(options.seed, 7);
+        assert_eq!(options.scene, "demo");
+        assert_eq!(options.output, PathBuf::from("out/frame.ppm"));
+        assert_eq!(options.threads, 3);
+        assert_eq!(options.format, ImageFormat::BinaryPpm);
+        assert_eq!(options.save_scene, Some(PathBuf::from("demo.toml")));
+        assert_eq!(options.checkpoint, Some(PathBuf::from("out/frame.checkpoint")));
+        assert!(options.spectral);
+    }
+
+    #[test]
+    fn format_follows_the_output_extension() {
+        assert_eq!(render_options(&["--output", "hdr.pfm"]).format, ImageFormat::Pfm);
+        assert_eq!(render_options(&["--format", "pfm"]).output, PathBuf::from("image.pfm"));
+        assert_eq!(render_options(&["--scene", "models/helmet.glb"]).scene, "models/helmet.glb");
+    }
+
+    #[test]
+    fn usage_lists_the_scenes_it_is_given() {
+        let scenes = ["alpha", "beta", "gamma", "delta", "epsilon", "zeta", "eta", "theta", "iota", "kappa", "lambda"];
+        let text = usage(&scenes);
+        for scene in scenes {
+            assert!(text.contains(scene), "{} missing from the usage", scene);
+        }
+        // Wrapped under the --scene description
+        let listed: Vec<&str> = text.lines().filter(|line| line.contains("beta") || line.contains("lambda")).collect();
+        assert!(listed.len() == 2 && listed.iter().all(|line| line.len() <= 80 && line.starts_with(&" ".repeat(24))));
+        assert!(text.contains("[default: alpha]"));
+        assert!(text.contains("lambda\n  --output"));
+    }
+
+    #[test]
+    fn invalid_arguments_are_rejected() {
+        assert_eq!(parse(&["--help"]), Ok(Command::Help));
+        for args in [
+            &["--width", "0"][..],
+            &["--spp", "many"],
+            &["--max-depth"],
+            &["--scene", "cornell"],
+            &["--format", "png"],
+            &["--output", "render.png"],
+            &["--output", "hdr.pfm", "--format", "ppm"],
+            &["--seed", "-1"],
+            &["--fast"],
+        ] {
+            assert!(parse(args).is_err(), "{:?} should be rejected", args);
+        }
+    }
+}
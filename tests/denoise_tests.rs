@@ -0,0 +1,78 @@
+#[cfg(test)]
+mod denoise_tests {
+    use raytracing_in_a_weekend::denoise::Denoiser;
+    use raytracing_in_a_weekend::film::Film;
+    use raytracing_in_a_weekend::{random_float, seed_rng, RtVec3};
+
+    const WIDTH: u32 = 24;
+    const HEIGHT: u32 = 16;
+
+    fn uniform(value: RtVec3) -> Film {
+        let mut film = Film::new(WIDTH, HEIGHT);
+        for j in 0..HEIGHT {
+            for i in 0..WIDTH {
+                film.add_sample(i, j, value);
+            }
+        }
+        film
+    }
+
+    fn setup() -> (Film, Film, Film) {
+        (
+            uniform(RtVec3::new(0.0, 0.0, 1.0)),
+            uniform(RtVec3::new(5.0, 5.0, 5.0)),
+            uniform(RtVec3::new(0.5, 0.5, 0.5)),
+        )
+    }
+
+    fn variance(film: &Film) -> f64 {
+        let values: Vec<f64> = (0..HEIGHT).flat_map(|j| (0..WIDTH).map(move |i| (i, j))).map(|(i, j)| film.resolve(i, j).y()).collect();
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / values.len() as f64
+    }
+
+    #[test]
+    fn flat_image_is_unchanged() {
+        let (normal, depth, albedo) = setup();
+        let beauty = uniform(RtVec3::new(0.25, 0.5, 0.75));
+        let denoised = Denoiser::default().denoise(&beauty, &normal, &depth, &albedo);
+        for j in 0..HEIGHT {
+            for i in 0..WIDTH {
+                assert!((denoised.resolve(i, j) - beauty.resolve(i, j)).length() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn noise_is_reduced() {
+        let (normal, depth, albedo) = setup();
+        seed_rng(5);
+        let mut beauty = Film::new(WIDTH, HEIGHT);
+        for j in 0..HEIGHT {
+            for i in 0..WIDTH {
+                let noisy = 0.5 + (random_float() - 0.5) * 0.4;
+                beauty.add_sample(i, j, RtVec3::new(noisy, noisy, noisy));
+            }
+        }
+        let denoised = Denoiser::default().denoise(&beauty, &normal, &depth, &albedo);
+        assert!(variance(&denoised) < variance(&beauty) * 0.25);
+    }
+
+    #[test]
+    fn geometry_edges_are_kept() {
+        let (_, depth, albedo) = setup();
+        // Left half faces the camera and is lit, right half faces sideways and is dark.
+        let mut normal = Film::new(WIDTH, HEIGHT);
+        let mut beauty = Film::new(WIDTH, HEIGHT);
+        for j in 0..HEIGHT {
+            for i in 0..WIDTH {
+                let left = i < WIDTH / 2;
+                normal.add_sample(i, j, if left { RtVec3::new(0.0, 0.0, 1.0) } else { RtVec3::new(1.0, 0.0, 0.0) });
+                beauty.add_sample(i, j, if left { RtVec3::new(1.0, 1.0, 1.0) } else { RtVec3::new(0.0, 0.0, 0.0) });
+            }
+        }
+        let denoised = Denoiser::default().denoise(&beauty, &normal, &depth, &albedo);
+        assert!((denoised.resolve(WIDTH / 2 - 1, HEIGHT / 2).x() - 1.0).abs() < 1e-6);
+        assert!(denoised.resolve(WIDTH / 2, HEIGHT / 2).x().abs() < 1e-6);
+    }
+}
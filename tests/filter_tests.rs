@@ -0,0 +1,83 @@
+#[cfg(test)]
+mod filter_tests {
+    use raytracing_in_a_weekend::film::Film;
+    use raytracing_in_a_weekend::filter::Filter;
+    use raytracing_in_a_weekend::RtVec3;
+
+    fn setup() -> [Filter; 4] {
+        [
+            Filter::Box { radius: 0.5 },
+            Filter::Tent { radius: 1.0 },
+            Filter::Gaussian { radius: 1.5, alpha: 2.0 },
+            Filter::MitchellNetravali { radius: 2.0, b: 1.0 / 3.0, c: 1.0 / 3.0 },
+        ]
+    }
+
+    #[test]
+    fn zero_outside_radius() {
+        for filter in setup() {
+            let outside = filter.radius() + 0.01;
+            assert_eq!(filter.evaluate(outside, 0.0), 0.0, "{:?}", filter);
+            assert_eq!(filter.evaluate(0.0, -outside), 0.0, "{:?}", filter);
+            assert!(filter.evaluate(0.0, 0.0) > 0.0, "{:?}", filter);
+        }
+    }
+
+    #[test]
+    fn symmetric() {
+        for filter in setup() {
+            assert_eq!(filter.evaluate(0.3, 0.7), filter.evaluate(-0.3, -0.7));
+            assert_eq!(filter.evaluate(0.3, 0.7), filter.evaluate(0.7, 0.3));
+        }
+    }
+
+    #[test]
+    fn mitchell_has_negative_lobe() {
+        let filter = Filter::MitchellNetravali { radius: 2.0, b: 1.0 / 3.0, c: 1.0 / 3.0 };
+        assert!(filter.evaluate(1.5, 0.0) < 0.0);
+    }
+
+    #[test]
+    fn box_splat_stays_in_one_pixel() {
+        let mut film = Film::new(4, 4);
+        let color = RtVec3::new(1.0, 2.0, 3.0);
+        // Sample on the left edge of pixel (1, 1) and one just short of its right edge
+        film.splat(1.0, 1.5, color, &Filter::default());
+        film.splat(1.999, 1.5, color, &Filter::default());
+        assert_eq!(film.sample_count(1, 1), 2);
+        assert_eq!(film.sample_count(0, 1), 0);
+        assert_eq!(film.sample_count(2, 1), 0);
+        assert_eq!(film.resolve(1, 1), color);
+    }
+
+    #[test]
+    fn wide_splat_reaches_neighbours() {
+        let mut film = Film::new(5, 5);
+        film.splat(2.5, 2.5, RtVec3::new(1.0, 1.0, 1.0), &Filter::Gaussian { radius: 1.5, alpha: 2.0 });
+        for j in 1..4 {
+            for i in 1..4 {
+                assert_eq!(film.sample_count(i, j), 1);
+                assert_eq!(film.resolve(i, j), RtVec3::new(1.0, 1.0, 1.0));
+            }
+        }
+        assert_eq!(film.sample_count(0, 2), 0);
+        assert_eq!(film.sample_count(4, 4), 0);
+    }
+
+    #[test]
+    fn edge_pixels_reached_only_by_negative_lobes_stay_black() {
+        let filter = Filter::MitchellNetravali { radius: 2.0, b: 1.0 / 3.0, c: 1.0 / 3.0 };
+        let mut film = Film::new(1, 1);
+        // 1.5 pixels off the only pixel, just the negative lobe reaches it
+        film.splat(2.0, 0.5, RtVec3::new(1.0, 1.0, 1.0), &filter);
+        assert_eq!(film.sample_count(0, 0), 1);
+        assert_eq!(film.resolve(0, 0), RtVec3::new(0.0, 0.0, 0.0));
+
+        // Lobes that all but cancel leave next to nothing to divide by
+        let mut film = Film::new(1, 1);
+        film.add_weighted_sample(0, 0, RtVec3::new(1.0, 1.0, 1.0), 0.5);
+        film.add_weighted_sample(0, 0, RtVec3::new(1.0, 1.0, 1.0), -0.5 + 1e-16);
+        assert!(film.weights[0] > 0.0);
+        assert_eq!(film.resolve(0, 0), RtVec3::new(0.0, 0.0, 0.0));
+    }
+}
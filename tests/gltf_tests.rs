@@ -0,0 +1,138 @@
+#[cfg(test)]
+mod gltf_tests {
+    use raytracing_in_a_weekend::gltf_import::load_gltf;
+    use raytracing_in_a_weekend::hit::{HitRecord, Hittable};
+    use raytracing_in_a_weekend::material::{default_material_lambertian, PrincipledParameters};
+    use raytracing_in_a_weekend::ray::Color;
+    use raytracing_in_a_weekend::scene::MaterialDescription;
+    use raytracing_in_a_weekend::{Interval, Point3, Ray, RtVec3};
+
+    use std::fs;
+    use std::io::ErrorKind;
+    use std::path::PathBuf;
+
+    // A unit quad in the XY plane, shared by a red diffuse mesh and a grey metal one.
+    const MODEL: &str = r#"{
+        "asset": {"version": "2.0"},
+        "scene": 0,
+        "scenes": [{"nodes": [0, 2, 3]}],
+        "nodes": [
+            {"translation": [0, 0, -2], "children": [1]},
+            {"mesh": 0, "scale": [2, 2, 2]},
+            {"mesh": 1, "translation": [10, 0, -2]},
+            {"camera": 0, "translation": [0, 1, 5]}
+        ],
+        "cameras": [{"type": "perspective", "perspective": {"yfov": 0.5, "aspectRatio": 1.5, "znear": 0.1}}],
+        "meshes": [
+            {"primitives": [{"attributes": {"POSITION": 0, "TEXCOORD_0": 1}, "indices": 2, "material": 0}]},
+            {"primitives": [{"attributes": {"POSITION": 0}, "indices": 2, "material": 1}]}
+        ],
+        "materials": [
+            {"pbrMetallicRoughness": {"baseColorFactor": [0.8, 0.1, 0.1, 1], "metallicFactor": 0}},
+            {"pbrMetallicRoughness": {"baseColorFactor": [0.9, 0.9, 0.9, 1], "metallicFactor": 1, "roughnessFactor": 0.25}}
+        ],
+        "buffers": [{"uri": "quad.bin", "byteLength": 92}],
+        "bufferViews": [
+            {"buffer": 0, "byteOffset": 0, "byteLength": 48},
+            {"buffer": 0, "byteOffset": 48, "byteLength": 32},
+            {"buffer": 0, "byteOffset": 80, "byteLength": 12}
+        ],
+        "accessors": [
+            {"bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3", "min": [-0.5, -0.5, 0], "max": [0.5, 0.5, 0]},
+            {"bufferView": 1, "componentType": 5126, "count": 4, "type": "VEC2"},
+            {"bufferView": 2, "componentType": 5123, "count": 6, "type": "SCALAR"}
+        ]
+    }"#;
+
+    fn write_model(name: &str, json: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join("raytracing_in_a_weekend_tests").join(name);
+        fs::create_dir_all(&dir).unwrap();
+        let mut buffer: Vec<u8> = Vec::new();
+        for value in [-0.5f32, -0.5, 0.0, 0.5, -0.5, 0.0, 0.5, 0.5, 0.0, -0.5, 0.5, 0.0] {
+            buffer.extend(value.to_le_bytes());
+        }
+        for value in [0.0f32, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0] {
+            buffer.extend(value.to_le_bytes());
+        }
+        for index in [0u16, 1, 2, 0, 2, 3] {
+            buffer.extend(index.to_le_bytes());
+        }
+        fs::write(dir.join("quad.bin"), buffer).unwrap();
+        let path = dir.join("model.gltf");
+        fs::write(&path, json).unwrap();
+        path
+    }
+
+    fn cast(world: &dyn Hittable, origin: Point3) -> Option<HitRecord> {
+        let mut record = HitRecord::new(origin, origin, 0.0, false, default_material_lambertian());
+        let ray = Ray::new(origin, RtVec3::new(0.0, 0.0, -1.0));
+        world.hit(&ray, Interval::new(0.001, f64::INFINITY), &mut record).then_some(record)
+    }
+
+    #[test]
+    fn meshes_are_placed_by_their_node_transforms() {
+        let preset = load_gltf(&write_model("gltf_nodes", MODEL)).unwrap();
+
+        // The parent translation and child scale both apply, the quad spans -1..1 at z = -2
+        let record = cast(&preset.world, Point3::new(0.5, 0.5, 0.0)).unwrap();
+        assert!((record.t - 2.0).abs() < 1e-9);
+        assert!(record.front_face);
+        assert!((record.normal - RtVec3::new(0.0, 0.0, 1.0)).length() < 1e-9);
+        assert!(cast(&preset.world, Point3::new(1.5, 0.0, 0.0)).is_none());
+
+        // Texture coordinates are flipped to put v = 0 at the bottom
+        assert!((record.u - 0.75).abs() < 1e-9);
+        assert!((record.v - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn metallic_roughness_materials_are_mapped() {
+        let preset = load_gltf(&write_model("gltf_materials", MODEL)).unwrap();
+
+        let diffuse = cast(&preset.world, Point3::new(0.0, 0.0, 0.0)).unwrap();
+        let expected = |base_color: Color, metallic: f64, roughness: f64| MaterialDescription::Principled {
+            base_color,
+            parameters: PrincipledParameters { metallic, roughness, ..PrincipledParameters::default() },
+        };
+        assert_eq!(diffuse.material.describe(), Some(expected(Color::new_rgb(0.8f32 as f64, 0.1f32 as f64, 0.1f32 as f64), 0.0, 1.0)));
+        let metal = cast(&preset.world, Point3::new(10.0, 0.0, 0.0)).unwrap();
+        assert_eq!(metal.material.describe(), Some(expected(Color::new_rgb(0.9f32 as f64, 0.9f32 as f64, 0.9f32 as f64), 1.0, 0.25)));
+    }
+
+    #[test]
+    fn perspective_cameras_are_imported() {
+        let camera = load_gltf(&write_model("gltf_camera", MODEL)).unwrap().camera;
+        assert_eq!(camera.lookfrom, Point3::new(0.0, 1.0, 5.0));
+        assert_eq!(camera.lookat, Point3::new(0.0, 1.0, 4.0));
+        assert_eq!(camera.vup, RtVec3::new(0.0, 1.0, 0.0));
+        assert!((camera.fov - 0.5f32.to_degrees() as f64).abs() < 1e-4);
+        assert_eq!(camera.aspect_ratio, 1.5);
+    }
+
+    #[test]
+    fn models_without_a_camera_are_framed() {
+        let json = MODEL.replace(r#""nodes": [0, 2, 3]"#, r#""nodes": [0]"#);
+        let preset = load_gltf(&write_model("gltf_framed", &json)).unwrap();
+        assert_eq!(preset.camera.lookat, Point3::new(0.0, 0.0, -2.0));
+        assert!(preset.camera.lookfrom.z > 0.0);
+
+        let output = std::env::temp_dir().join("raytracing_in_a_weekend_tests").join("gltf_framed").join("render.ppm");
+        preset.build_camera(8, 1, 2).with_seed(1).with_output(&output).render();
+        assert!(output.exists());
+    }
+
+    #[test]
+    fn primitives_with_short_attributes_are_skipped() {
+        // Two texture coordinates for four vertices, the red quad is left out and the metal one stays
+        let json = MODEL.replace(r#""count": 4, "type": "VEC2""#, r#""count": 2, "type": "VEC2""#);
+        let preset = load_gltf(&write_model("gltf_short_attributes", &json)).unwrap();
+        assert!(cast(&preset.world, Point3::new(0.5, 0.5, 0.0)).is_none());
+        assert!(cast(&preset.world, Point3::new(10.0, 0.0, 0.0)).is_some());
+    }
+
+    #[test]
+    fn missing_files_are_reported() {
+        let error = load_gltf(&std::env::temp_dir().join("raytracing_in_a_weekend_tests").join("missing.gltf")).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,87 @@
+#[cfg(test)]
+mod lens_tests {
+    use raytracing_in_a_weekend::image::Image;
+    use raytracing_in_a_weekend::lens::{passes_vignetting, Aperture, ApertureMask};
+    use raytracing_in_a_weekend::{seed_rng, RtVec3};
+
+    use std::fs;
+    use std::io::ErrorKind;
+
+    #[test]
+    fn polygon_samples_stay_inside_the_blades() {
+        seed_rng(9);
+        let aperture = Aperture::Polygon { blades: 6, rotation: 0.0 };
+        // Inscribed radius of a regular hexagon with unit circumradius
+        let apothem = f64::cos(std::f64::consts::PI / 6.0);
+        for _ in 0..2000 {
+            let p = aperture.sample();
+            assert!(p.length() <= 1.0 + 1e-12);
+            // Every edge normal of the hexagon points at angle 30 + 60k degrees
+            for k in 0..6 {
+                let angle = (30.0 + 60.0 * k as f64).to_radians();
+                let along_normal = p.x() * angle.cos() + p.y() * angle.sin();
+                assert!(along_normal <= apothem + 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn mask_only_samples_open_pixels() {
+        seed_rng(4);
+        // 2x2 mask with only the top right quadrant open
+        let black = RtVec3::new(0.0, 0.0, 0.0);
+        let white = RtVec3::new(1.0, 1.0, 1.0);
+        let mask = ApertureMask::from_image(&Image::new(2, 2, vec![black, white, black, black]));
+        for _ in 0..500 {
+            let p = mask.sample();
+            assert!(p.x() >= 0.0 && p.x() <= 1.0);
+            assert!(p.y() >= 0.0 && p.y() <= 1.0);
+        }
+    }
+
+    #[test]
+    fn vignetting_clips_the_frame_edges() {
+        // Nothing is clipped in the centre of the frame
+        assert!(passes_vignetting(RtVec3::new(-0.7, -0.7, 0.0), (0.0, 0.0), 0.8));
+        // In the corner the far side of the aperture is cut off, the near side isn't
+        assert!(!passes_vignetting(RtVec3::new(-0.7, -0.7, 0.0), (1.0, 1.0), 0.8));
+        assert!(passes_vignetting(RtVec3::new(0.7, 0.7, 0.0), (1.0, 1.0), 0.8));
+        assert!(passes_vignetting(RtVec3::new(-0.7, -0.7, 0.0), (1.0, 1.0), 0.0));
+    }
+
+    #[test]
+    fn loads_ascii_and_binary_netpbm() {
+        let dir = std::env::temp_dir().join("raytracing_in_a_weekend_tests");
+        fs::create_dir_all(&dir).unwrap();
+
+        let ascii = dir.join("mask_ascii.pgm");
+        fs::write(&ascii, "P2\n# aperture\n2 1\n4\n0 4\n").unwrap();
+        let image = Image::load_netpbm(&ascii).unwrap();
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.pixel(0, 0), RtVec3::new(0.0, 0.0, 0.0));
+        assert_eq!(image.pixel(1, 0), RtVec3::new(1.0, 1.0, 1.0));
+
+        let binary = dir.join("mask_binary.ppm");
+        let mut bytes = b"P6\n1 1\n255\n".to_vec();
+        bytes.extend_from_slice(&[255, 0, 51]);
+        fs::write(&binary, bytes).unwrap();
+        let image = Image::load_netpbm(&binary).unwrap();
+        assert_eq!(image.pixel(0, 0), RtVec3::new(1.0, 0.0, 0.2));
+    }
+
+    #[test]
+    fn malformed_netpbm_is_an_error_not_a_panic() {
+        let dir = std::env::temp_dir().join("raytracing_in_a_weekend_tests");
+        fs::create_dir_all(&dir).unwrap();
+        let load = |name: &str, bytes: &[u8]| {
+            let path = dir.join(name);
+            fs::write(&path, bytes).unwrap();
+            Image::load_netpbm(&path).unwrap_err().kind()
+        };
+        // Header running into the end of the file, no raster at all
+        assert_eq!(load("truncated_header.ppm", b"P6\n1 1\n255"), ErrorKind::InvalidData);
+        assert_eq!(load("truncated_raster.ppm", b"P6\n2 2\n255\n\x01\x02"), ErrorKind::InvalidData);
+        // Sample count overflowing 32 (and 64) bits
+        assert_eq!(load("huge.ppm", b"P6\n4294967295 4294967295\n255\n\x00"), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,451 @@
+#[cfg(test)]
+mod material_tests {
+    use raytracing_in_a_weekend::hit::HitRecord;
+    use raytracing_in_a_weekend::material::{default_material_lambertian, Dielectric, Material, Metal, Principled, PrincipledParameters};
+    use raytracing_in_a_weekend::hit::{Hittable, HittableList, Quad, Sphere};
+    use raytracing_in_a_weekend::mesh::Mesh;
+    use raytracing_in_a_weekend::material::{
+        BumpMap, Coated, Cutout, Lambertian, MixMaterial, MixWeight, NormalMap, OrenNayar, Subsurface, ThinFilm, Velvet,
+    };
+    use raytracing_in_a_weekend::texture::{SolidColor, Texture};
+    use raytracing_in_a_weekend::microfacet::{fresnel_dielectric, fresnel_thin_film, Frame, Ggx};
+    use raytracing_in_a_weekend::spectrum::SampledWavelengths;
+    use raytracing_in_a_weekend::ray::{color, Background, Color};
+    use raytracing_in_a_weekend::{seed_rng, Interval, Point3, Ray, RtVec3};
+
+    use std::rc::Rc;
+
+    // Hit on the z = 0 plane facing +z, tangent along x and bitangent along y.
+    fn plane_hit() -> HitRecord {
+        let mut record = HitRecord::new(Point3::new(0.0, 0.0, 0.0), RtVec3::new(0.0, 0.0, 1.0), 1.0, true, default_material_lambertian());
+        record.tangent = RtVec3::new(1.0, 0.0, 0.0);
+        record.bitangent = RtVec3::new(0.0, 1.0, 0.0);
+        record
+    }
+
+    fn incoming(direction: RtVec3) -> Ray {
+        Ray::new(Point3::new(0.0, 0.0, 0.0) - direction, direction)
+    }
+
+    #[test]
+    fn smooth_metal_is_a_mirror() {
+        let metal = Metal::new(Color::new_rgb(0.9, 0.6, 0.3), 0.0);
+        let (attenuation, scattered) = metal.scatter(incoming(RtVec3::new(1.0, 0.0, -1.0)), plane_hit()).unwrap();
+        assert!((scattered.direction().unit_vector() - RtVec3::new(1.0, 0.0, 1.0).unit_vector()).length() < 1e-9);
+        // Schlick brightens the tint towards white away from head on
+        assert!(attenuation.b > 0.3 && attenuation.b < 1.0);
+
+        let (head_on, _) = metal.scatter(incoming(RtVec3::new(0.0, 0.0, -1.0)), plane_hit()).unwrap();
+        assert!((head_on.r - 0.9).abs() < 1e-9 && (head_on.b - 0.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rough_white_metal_keeps_most_energy() {
+        // A white furnace: only the single-scattering loss may darken a perfect reflector
+        seed_rng(3);
+        for (roughness, minimum) in [(0.2, 0.97), (0.6, 0.8)] {
+            let metal = Metal::new(Color::new_rgb(1.0, 1.0, 1.0), roughness);
+            let samples = 20000;
+            let mut total = 0.0;
+            for _ in 0..samples {
+                if let Some((attenuation, scattered)) = metal.scatter(incoming(RtVec3::new(0.3, 0.2, -1.0)), plane_hit()) {
+                    assert!(scattered.direction().z > 0.0);
+                    assert!(attenuation.g <= 1.0 + 1e-9);
+                    total += attenuation.g;
+                }
+            }
+            let average = total / samples as f64;
+            assert!(average > minimum && average <= 1.0, "roughness {} kept {}", roughness, average);
+        }
+    }
+
+    #[test]
+    fn anisotropic_roughness_spreads_along_the_tangent() {
+        seed_rng(5);
+        let brushed = Metal::new(Color::new_rgb(1.0, 1.0, 1.0), 0.0).with_anisotropy(0.8, 0.05);
+        let (mut spread_u, mut spread_v) = (0.0, 0.0);
+        for _ in 0..2000 {
+            if let Some((_, scattered)) = brushed.scatter(incoming(RtVec3::new(0.0, 0.0, -1.0)), plane_hit()) {
+                let direction = scattered.direction().unit_vector();
+                spread_u += direction.x.abs();
+                spread_v += direction.y.abs();
+            }
+        }
+        assert!(spread_u > 10.0 * spread_v, "{} vs {}", spread_u, spread_v);
+        assert!(brushed.describe().is_none());
+    }
+
+    #[test]
+    fn conductor_presets_have_their_colors() {
+        let gold = Metal::gold(0.0).albedo(&plane_hit());
+        assert!(gold.r > 0.9 && gold.g > 0.7 && gold.b < 0.4, "{:?}", gold);
+        let copper = Metal::copper(0.0).albedo(&plane_hit());
+        assert!(copper.r > copper.g && copper.g > 0.5 && copper.b < 0.7, "{:?}", copper);
+        let aluminium = Metal::aluminium(0.0).albedo(&plane_hit());
+        assert!(aluminium.r > 0.85 && aluminium.b > 0.85, "{:?}", aluminium);
+        // Conductors approach white at grazing angles
+        let (grazing, _) = Metal::gold(0.0).scatter(incoming(RtVec3::new(1.0, 0.0, -0.01)), plane_hit()).unwrap();
+        assert!(grazing.b > 0.9);
+    }
+
+    #[test]
+    fn visible_normals_face_the_viewer() {
+        seed_rng(9);
+        let ggx = Ggx::from_roughness(0.5, 0.3);
+        let wo = RtVec3::new(0.6, -0.2, 0.3).unit_vector();
+        for k in 0..500 {
+            let h = ggx.sample_visible_normal(wo, (k as f64 + 0.5) / 500.0, ((k * 37) % 500) as f64 / 500.0);
+            assert!(h.z >= 0.0 && wo.dot(&h) >= -1e-9);
+            assert!((h.length() - 1.0).abs() < 1e-9);
+        }
+
+        let frame = Frame::new(RtVec3::new(0.0, 1.0, 0.0), RtVec3::new(0.0, 0.0, 0.0));
+        let v = RtVec3::new(0.2, 0.5, -0.7);
+        assert!((frame.to_world(frame.to_local(v)) - v).length() < 1e-12);
+    }
+
+    #[test]
+    fn principled_metal_and_glass_limits() {
+        let chrome = Principled::new(Color::new_rgb(0.9, 0.5, 0.2), PrincipledParameters { metallic: 1.0, roughness: 0.0, ..Default::default() });
+        let (attenuation, scattered) = chrome.scatter(incoming(RtVec3::new(0.0, 0.0, -1.0)), plane_hit()).unwrap();
+        assert!((scattered.direction().unit_vector() - RtVec3::new(0.0, 0.0, 1.0)).length() < 1e-9);
+        assert!((attenuation.r - 0.9).abs() < 1e-9 && (attenuation.b - 0.2).abs() < 1e-9);
+
+        // Smooth clear glass reflects about 4% head on and refracts the rest straight through
+        seed_rng(11);
+        let glass = Principled::new(Color::new_rgb(1.0, 1.0, 1.0), PrincipledParameters { roughness: 0.0, transmission: 1.0, ..Default::default() });
+        let samples = 20000;
+        let mut reflected = 0;
+        for _ in 0..samples {
+            let (_, scattered) = glass.scatter(incoming(RtVec3::new(0.0, 0.0, -1.0)), plane_hit()).unwrap();
+            if scattered.direction().z > 0.0 {
+                reflected += 1;
+            } else {
+                assert!(scattered.direction().unit_vector().z < -0.999);
+            }
+        }
+        let fraction = reflected as f64 / samples as f64;
+        assert!((fraction - 0.04).abs() < 0.01, "{}", fraction);
+    }
+
+    #[test]
+    fn principled_layers_keep_energy_bounded() {
+        seed_rng(13);
+        let layered = PrincipledParameters { roughness: 0.4, sheen: 1.0, clearcoat: 1.0, ..Default::default() };
+        let material = Principled::new(Color::new_rgb(1.0, 1.0, 1.0), layered);
+        let samples = 20000;
+        let mut total = 0.0;
+        for _ in 0..samples {
+            if let Some((attenuation, scattered)) = material.scatter(incoming(RtVec3::new(0.5, 0.0, -1.0)), plane_hit()) {
+                assert!(scattered.direction().z > 0.0);
+                total += attenuation.g;
+            }
+        }
+        let average = total / samples as f64;
+        assert!(average > 0.9 && average < 1.1, "{}", average);
+        assert!(material.describe().is_some());
+    }
+
+    #[test]
+    fn glass_absorbs_along_the_path_inside() {
+        let glass = Dielectric::tinted(Color::new_rgb(0.5, 0.8, 1.0), 2.0, 1.5);
+        let exit = |t: f64| {
+            // Leaving the glass head on, so it never reflects internally
+            let mut record = HitRecord::new(Point3::new(0.0, 0.0, -t), RtVec3::new(0.0, 0.0, 1.0), t, false, default_material_lambertian());
+            record.tangent = RtVec3::new(1.0, 0.0, 0.0);
+            glass.scatter(Ray::new(Point3::new(0.0, 0.0, 0.0), RtVec3::new(0.0, 0.0, -1.0)), record).unwrap().0
+        };
+        let thin = exit(2.0);
+        assert!((thin.r - 0.5).abs() < 1e-9 && (thin.g - 0.8).abs() < 1e-9 && thin.b == 1.0);
+        let thick = exit(4.0);
+        assert!((thick.r - 0.25).abs() < 1e-9 && (thick.g - 0.64).abs() < 1e-9);
+
+        // Entering costs nothing, the absorption is paid on the way out
+        let (entering, _) = glass.scatter(incoming(RtVec3::new(0.0, 0.0, -1.0)), plane_hit()).unwrap();
+        assert_eq!(entering, Color::new_rgb(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn thin_films_interfere() {
+        // A vanishing film leaves the bare surface, a quarter wave film of index sqrt(n) cancels
+        // its reflection
+        for cos_theta in [1.0, 0.7, 0.2] {
+            assert!((fresnel_thin_film(cos_theta, 550.0, 0.0, 1.38, 1.5) - fresnel_dielectric(cos_theta, 1.5)).abs() < 1e-12);
+        }
+        let coating = 1.5f64.sqrt();
+        assert!(fresnel_thin_film(1.0, 550.0, 550.0 / (4.0 * coating), coating, 1.5) < 1e-12);
+        assert!((fresnel_dielectric(1.0, 1.5) - 0.04).abs() < 1e-12);
+
+        // A bubble reflects some colors more than others, and the colors move with the angle
+        let bubble = ThinFilm::bubble(400.0, 1.33);
+        let head_on = bubble.reflectance_rgb(1.0);
+        let grazing = bubble.reflectance_rgb(0.4);
+        assert!((head_on.r - head_on.g).abs() > 0.02, "{:?}", head_on);
+        assert!((head_on.r - grazing.r).abs() + (head_on.b - grazing.b).abs() > 0.02);
+    }
+
+    #[test]
+    fn bubbles_reflect_or_pass_straight_through() {
+        seed_rng(17);
+        let bubble = ThinFilm::bubble(500.0, 1.33);
+        let direction = RtVec3::new(0.3, 0.1, -1.0);
+        let samples = 20000;
+        let mut total = RtVec3::new(0.0, 0.0, 0.0);
+        for _ in 0..samples {
+            let (attenuation, scattered) = bubble.scatter(incoming(direction), plane_hit()).unwrap();
+            if scattered.direction().z < 0.0 {
+                assert!((scattered.direction().unit_vector() - direction.unit_vector()).length() < 1e-12);
+            }
+            total = total + attenuation.to_vec();
+        }
+        // Nothing is absorbed, whatever isn't reflected is transmitted
+        let average = total / samples as f64;
+        assert!((average - RtVec3::new(1.0, 1.0, 1.0)).length() < 0.05, "{:?}", average);
+
+        // The spectral renderer follows the hero wavelength through the film
+        let mut wavelengths = SampledWavelengths::sample(0.5);
+        let (attenuation, _) = bubble.scatter_spectral(incoming(direction), plane_hit(), &mut wavelengths).unwrap();
+        assert!(wavelengths.secondary_terminated);
+        assert!(attenuation.r == attenuation.g && attenuation.g == attenuation.b);
+    }
+
+    #[test]
+    fn coats_reflect_by_fresnel_and_tint_the_base() {
+        seed_rng(19);
+        let base = Rc::new(Lambertian::new(Color::new_rgb(0.5, 0.5, 0.5)));
+        let coated = Coated::new(base, 1.5, 0.0).with_tint(Color::new_rgb(0.8, 0.4, 0.2));
+        let samples = 20000;
+        let mut reflected = 0;
+        for _ in 0..samples {
+            let (attenuation, scattered) = coated.scatter(incoming(RtVec3::new(0.0, 0.0, -1.0)), plane_hit()).unwrap();
+            if (scattered.direction().unit_vector() - RtVec3::new(0.0, 0.0, 1.0)).length() < 1e-12 {
+                reflected += 1;
+            } else {
+                // Grazing exits cross more of the coat than the way in
+                assert!(attenuation.r <= 0.4 + 1e-9 && attenuation.b <= 0.1 + 1e-9);
+                assert!(attenuation.r > 0.0 && attenuation.b / attenuation.r < 0.25 + 1e-9);
+            }
+        }
+        let fraction = reflected as f64 / samples as f64;
+        assert!((fraction - 0.04).abs() < 0.01, "{}", fraction);
+        assert_eq!(coated.albedo(&plane_hit()), Color::new_rgb(0.4, 0.2, 0.1));
+
+        // Spectral paths reach the base through the coat
+        let pearl = Coated::new(Rc::new(ThinFilm::over(Rc::new(Lambertian::new(Color::new_rgb(1.0, 1.0, 1.0))), 380.0, 2.4, 1.5)), 1.5, 0.0);
+        let mut terminated = false;
+        for k in 0..50 {
+            let mut wavelengths = SampledWavelengths::sample(k as f64 / 50.0);
+            pearl.scatter_spectral(incoming(RtVec3::new(0.0, 0.0, -1.0)), plane_hit(), &mut wavelengths);
+            terminated |= wavelengths.secondary_terminated;
+        }
+        assert!(terminated);
+    }
+
+    #[test]
+    fn subsurface_walks_scatter_inside_and_come_back_out() {
+        seed_rng(23);
+        let wax = Subsurface::new(Color::new_rgb(0.9, 0.9, 0.9), Color::new_rgb(0.1, 0.1, 0.1), 1.5);
+        // From inside, 10 free paths short of the surface: nearly always a scattering event on the way
+        let mut record = HitRecord::new(Point3::new(0.0, 0.0, -1.0), RtVec3::new(0.0, 0.0, 1.0), 1.0, false, default_material_lambertian());
+        record.tangent = RtVec3::new(1.0, 0.0, 0.0);
+        let (attenuation, scattered) = wax.scatter(Ray::new(Point3::new(0.0, 0.0, 0.0), RtVec3::new(0.0, 0.0, -1.0)), record).unwrap();
+        assert!(scattered.origin().z < 0.0 && scattered.origin().z > -1.0);
+        assert!((attenuation.g - 0.9).abs() < 1e-9);
+
+        // A white furnace: with no absorption, everything that goes in comes back out
+        let mut world = HittableList::new();
+        world.add(Rc::new(Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, Rc::new(Subsurface::new(
+            Color::new_rgb(1.0, 1.0, 1.0),
+            Color::new_rgb(0.5, 0.3, 0.2),
+            1.4,
+        ).with_anisotropy(0.5)))));
+        let white = Background::Solid(Color::new_rgb(1.0, 1.0, 1.0));
+        let samples = 2000;
+        let mut total = RtVec3::new(0.0, 0.0, 0.0);
+        for k in 0..samples {
+            let offset = (k as f64 + 0.5) / samples as f64 - 0.5;
+            total = total + color(Ray::new(Point3::new(offset, 0.0, 5.0), RtVec3::new(0.0, 0.0, -1.0)), &world, 10000, &white);
+        }
+        let average = total / samples as f64;
+        assert!((average - RtVec3::new(1.0, 1.0, 1.0)).length() < 0.1, "{:?}", average);
+    }
+
+    #[test]
+    fn oren_nayar_flattens_rough_diffuse() {
+        seed_rng(29);
+        // No roughness is exactly Lambertian
+        let smooth = OrenNayar::new(Color::new_rgb(0.5, 0.6, 0.7), 0.0);
+        let (attenuation, _) = smooth.scatter(incoming(RtVec3::new(0.4, 0.0, -1.0)), plane_hit()).unwrap();
+        assert_eq!(attenuation, Color::new_rgb(0.5, 0.6, 0.7));
+
+        // Rough surfaces scatter back towards the light more than away from it
+        let clay = OrenNayar::new(Color::new_rgb(1.0, 1.0, 1.0), 30.0);
+        let wo = RtVec3::new(0.8, 0.0, 0.6);
+        let back = clay.relative_reflectance(wo, RtVec3::new(0.6, 0.0, 0.8));
+        let forward = clay.relative_reflectance(wo, RtVec3::new(-0.6, 0.0, 0.8));
+        assert!(back > forward, "{} vs {}", back, forward);
+
+        let samples = 20000;
+        let mut total = 0.0;
+        for _ in 0..samples {
+            let (attenuation, scattered) = clay.scatter(incoming(RtVec3::new(0.0, 0.0, -1.0)), plane_hit()).unwrap();
+            assert!(scattered.direction().z > 0.0);
+            total += attenuation.g;
+        }
+        let average = total / samples as f64;
+        assert!(average > 0.75 && average < 1.0, "{}", average);
+    }
+
+    #[test]
+    fn velvet_sheen_brightens_grazing_angles() {
+        seed_rng(31);
+        let velvet = Velvet::new(Color::new_rgb(0.1, 0.0, 0.2), Color::new_rgb(1.0, 1.0, 1.0), 0.4);
+        let head_on = velvet.sheen_brdf(RtVec3::new(0.0, 0.0, 1.0), RtVec3::new(0.0, 0.0, 1.0));
+        let grazing = velvet.sheen_brdf(RtVec3::new(0.99, 0.0, 0.141), RtVec3::new(0.99, 0.0, 0.141));
+        assert!(grazing > 10.0 * head_on, "{} vs {}", grazing, head_on);
+        assert_eq!(velvet.sheen_brdf(RtVec3::new(0.0, 0.0, 1.0), RtVec3::new(0.0, 0.6, -0.8)), 0.0);
+
+        let average = |direction: RtVec3| {
+            let samples = 20000;
+            let mut total = 0.0;
+            for _ in 0..samples {
+                let (attenuation, scattered) = velvet.scatter(incoming(direction), plane_hit()).unwrap();
+                assert!(scattered.direction().z > 0.0);
+                total += attenuation.g;
+            }
+            total / samples as f64
+        };
+        // The green channel is all sheen
+        let (facing, edge) = (average(RtVec3::new(0.0, 0.0, -1.0)), average(RtVec3::new(1.0, 0.0, -0.1)));
+        assert!(edge > facing && edge < 1.0, "{} vs {}", edge, facing);
+    }
+
+    #[test]
+    fn mixes_pick_each_material_by_weight() {
+        seed_rng(37);
+        let red = Rc::new(Lambertian::new(Color::new_rgb(1.0, 0.0, 0.0)));
+        let mirror = Rc::new(Metal::new(Color::new_rgb(0.0, 0.0, 1.0), 0.0));
+        let fraction_mirrored = |material: &MixMaterial, direction: RtVec3| {
+            let samples = 20000;
+            let mirrored = (0..samples).filter(|_| material.scatter(incoming(direction), plane_hit()).unwrap().0.b > 0.0).count();
+            mirrored as f64 / samples as f64
+        };
+
+        let quarter = MixMaterial::new(red.clone(), mirror.clone(), MixWeight::Constant(0.25));
+        assert!((fraction_mirrored(&quarter, RtVec3::new(0.0, 0.0, -1.0)) - 0.25).abs() < 0.015);
+        assert_eq!(quarter.albedo(&plane_hit()), Color::new_rgb(0.75, 0.0, 0.25));
+
+        // Fresnel weights favour the second material at grazing angles
+        let glazed = MixMaterial::new(red.clone(), mirror.clone(), MixWeight::Fresnel(1.5));
+        assert!((glazed.weight(&plane_hit(), 1.0) - 0.04).abs() < 1e-12);
+        assert!(fraction_mirrored(&glazed, RtVec3::new(1.0, 0.0, -0.05)) > 0.5);
+
+        let masked = MixMaterial::new(red, mirror, MixWeight::Mask(Rc::new(SolidColor::new(Color::new_rgb(1.0, 1.0, 1.0)))));
+        assert_eq!(fraction_mirrored(&masked, RtVec3::new(0.0, 0.0, -1.0)), 1.0);
+    }
+
+    #[test]
+    fn cutouts_let_rays_through_where_transparent() {
+        seed_rng(41);
+        let leaf = Rc::new(Lambertian::new(Color::new_rgb(0.2, 0.6, 0.1)));
+        let direction = RtVec3::new(0.2, 0.3, -1.0);
+        let passed = |opacity: f64| {
+            let cutout = Cutout::new(leaf.clone(), Rc::new(SolidColor::new(Color::new_rgb(opacity, opacity, opacity))));
+            let samples = 20000;
+            let through = (0..samples)
+                .filter(|_| {
+                    let (attenuation, scattered) = cutout.scatter(incoming(direction), plane_hit()).unwrap();
+                    let straight = (scattered.direction() - direction).length() < 1e-12;
+                    assert!(!straight || attenuation == Color::new_rgb(1.0, 1.0, 1.0));
+                    straight
+                })
+                .count();
+            through as f64 / samples as f64
+        };
+        assert_eq!(passed(0.0), 1.0);
+        assert_eq!(passed(1.0), 0.0);
+        assert!((passed(0.3) - 0.7).abs() < 0.015);
+    }
+
+    // Height rising along u.
+    struct Ramp;
+
+    impl Texture for Ramp {
+        fn value(&self, u: f64, _v: f64, _p: &Point3) -> Color {
+            Color::new_rgb(u, u, u)
+        }
+    }
+
+    #[test]
+    fn normal_maps_bend_the_shading_normal() {
+        let mirror: Rc<dyn Material> = Rc::new(Metal::new(Color::new_rgb(1.0, 1.0, 1.0), 0.0));
+        let mapped = |color: Color| NormalMap::new(mirror.clone(), Rc::new(SolidColor::new(color)));
+
+        let flat = mapped(Color::new_rgb(0.5, 0.5, 1.0));
+        assert!((flat.normal(&plane_hit()) - RtVec3::new(0.0, 0.0, 1.0)).length() < 1e-9);
+
+        // (0.6, 0, 0.8) in the tangent frame sends a straight down ray off to +x
+        let tilted = mapped(Color::new_rgb(0.8, 0.5, 0.9));
+        let (_, scattered) = tilted.scatter(incoming(RtVec3::new(0.0, 0.0, -1.0)), plane_hit()).unwrap();
+        assert!((scattered.direction().unit_vector() - RtVec3::new(0.96, 0.0, 0.28)).length() < 1e-9);
+        assert!(tilted.with_strength(0.0).normal(&plane_hit()).x.abs() < 1e-9);
+
+        // Mirrored texture coordinates flip the bitangent, and with it the green channel
+        let mut mirrored = plane_hit();
+        mirrored.bitangent = RtVec3::new(0.0, -1.0, 0.0);
+        assert!(mapped(Color::new_rgb(0.5, 0.8, 0.9)).normal(&mirrored).y < 0.0);
+
+        // A reflection the bent normal would send below the surface is dropped
+        let steep = mapped(Color::new_rgb(0.9, 0.5, 0.8));
+        assert!(steep.scatter(incoming(RtVec3::new(0.3, 0.0, -1.0)), plane_hit()).is_none());
+    }
+
+    #[test]
+    fn bump_maps_follow_the_height_slopes() {
+        let mirror: Rc<dyn Material> = Rc::new(Metal::new(Color::new_rgb(1.0, 1.0, 1.0), 0.0));
+        let level = BumpMap::new(mirror.clone(), Rc::new(SolidColor::new(Color::new_rgb(0.7, 0.7, 0.7))), 1.0);
+        assert!((level.normal(&plane_hit()) - RtVec3::new(0.0, 0.0, 1.0)).length() < 1e-9);
+
+        // Rising along u by half a unit per unit tilts the normal back towards -u
+        let ramp = BumpMap::new(mirror, Rc::new(Ramp), 0.5);
+        let normal = ramp.normal(&plane_hit());
+        assert!((normal - RtVec3::new(-0.5, 0.0, 1.0).unit_vector()).length() < 1e-6);
+        let (_, scattered) = ramp.scatter(incoming(RtVec3::new(0.0, 0.0, -1.0)), plane_hit()).unwrap();
+        assert!(scattered.direction().x < 0.0 && scattered.direction().z > 0.0);
+    }
+
+    // Height rising along x in world space.
+    struct SolidRamp;
+
+    impl Texture for SolidRamp {
+        fn value(&self, _u: f64, _v: f64, p: &Point3) -> Color {
+            Color::new_rgb(p.x, p.x, p.x)
+        }
+    }
+
+    #[test]
+    fn bump_slopes_are_per_world_unit_whatever_the_parameterization() {
+        // A 2 x 2 square at z = 0 from x = 0, once as a quad (u from 0 to 1) and once as a mesh
+        // with u from 0 to 2. The ramps all rise by half a unit per world unit along x.
+        let mirror: Rc<dyn Material> = Rc::new(Metal::new(Color::new_rgb(1.0, 1.0, 1.0), 0.0));
+        let quad = Quad::new(Point3::new(0.0, 0.0, 0.0), RtVec3::new(2.0, 0.0, 0.0), RtVec3::new(0.0, 2.0, 0.0), mirror.clone());
+        let positions = vec![Point3::new(0.0, 0.0, 0.0), Point3::new(2.0, 0.0, 0.0), Point3::new(0.0, 2.0, 0.0)];
+        let uvs = vec![(0.0, 0.0), (2.0, 0.0), (0.0, 2.0)];
+        let mesh = Mesh::new(positions, None, Some(uvs), vec![[0, 1, 2]], mirror.clone());
+
+        let down = Ray::new(Point3::new(0.5, 0.5, 1.0), RtVec3::new(0.0, 0.0, -1.0));
+        let hit = |surface: &dyn Hittable| {
+            let mut record = plane_hit();
+            assert!(surface.hit(&down, Interval::new(0.001, f64::INFINITY), &mut record));
+            record
+        };
+        let expected = RtVec3::new(-0.5, 0.0, 1.0).unit_vector();
+        let on_quad = BumpMap::new(mirror.clone(), Rc::new(Ramp), 1.0).normal(&hit(&quad));
+        let on_mesh = BumpMap::new(mirror.clone(), Rc::new(Ramp), 0.5).normal(&hit(&mesh));
+        let solid = BumpMap::new(mirror, Rc::new(SolidRamp), 0.5);
+        assert!((on_quad - expected).length() < 1e-6, "{:?}", on_quad);
+        assert!((on_mesh - expected).length() < 1e-6, "{:?}", on_mesh);
+        assert!((solid.normal(&hit(&quad)) - expected).length() < 1e-6);
+        assert!((solid.normal(&hit(&mesh)) - expected).length() < 1e-6);
+    }
+}
@@ -0,0 +1,130 @@
+#[cfg(test)]
+mod mesh_tests {
+    use raytracing_in_a_weekend::hit::{HitRecord, Hittable, HittableList, Sphere};
+    use raytracing_in_a_weekend::material::default_material_lambertian;
+    use raytracing_in_a_weekend::mesh::Mesh;
+    use raytracing_in_a_weekend::{Interval, Point3, Ray, RtVec3};
+
+    use std::rc::Rc;
+
+    fn cast(world: &dyn Hittable, ray: &Ray) -> Option<HitRecord> {
+        let origin = ray.origin();
+        let mut record = HitRecord::new(origin, origin, 0.0, false, default_material_lambertian());
+        world.hit(ray, Interval::new(0.001, f64::INFINITY), &mut record).then_some(record)
+    }
+
+    // A bumpy 20 x 20 height field, 800 triangles.
+    fn terrain() -> (Vec<Point3>, Vec<[usize; 3]>) {
+        let size = 21;
+        let mut positions = Vec::new();
+        for j in 0..size {
+            for i in 0..size {
+                let (x, z) = (i as f64 * 0.1 - 1.0, j as f64 * 0.1 - 1.0);
+                positions.push(Point3::new(x, 0.2 * (5.0 * x).sin() * (3.0 * z).cos(), z));
+            }
+        }
+        let mut triangles = Vec::new();
+        for j in 0..size - 1 {
+            for i in 0..size - 1 {
+                let corner = j * size + i;
+                triangles.push([corner, corner + size, corner + 1]);
+                triangles.push([corner + 1, corner + size, corner + size + 1]);
+            }
+        }
+        (positions, triangles)
+    }
+
+    #[test]
+    fn bvh_finds_the_same_hits_as_testing_every_triangle() {
+        let (positions, triangles) = terrain();
+        let mesh = Mesh::new(positions.clone(), None, None, triangles.clone(), default_material_lambertian());
+        let mut brute_force = HittableList::new();
+        for triangle in triangles {
+            brute_force.add(Rc::new(Mesh::new(positions.clone(), None, None, vec![triangle], default_material_lambertian())));
+        }
+
+        for k in 0..200 {
+            let angle = k as f64 * 0.37;
+            let origin = Point3::new(1.5 * angle.cos(), 1.0 + 0.01 * k as f64, 1.5 * angle.sin());
+            let target = Point3::new(0.3 * (k as f64 * 0.7).sin(), 0.0, 0.3 * (k as f64 * 1.3).cos());
+            let ray = Ray::new(origin, target - origin);
+            let expected = cast(&brute_force, &ray).map(|record| record.t);
+            let found = cast(&mesh, &ray).map(|record| record.t);
+            assert_eq!(found, expected, "ray {}", k);
+        }
+        assert!(cast(&mesh, &Ray::new(Point3::new(0.0, 1.0, 0.0), RtVec3::new(0.0, 1.0, 0.0))).is_none());
+    }
+
+    #[test]
+    fn vertex_normals_and_uvs_are_interpolated() {
+        let positions = vec![Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 0.0, 0.0), Point3::new(0.0, 1.0, 0.0)];
+        let normals = vec![RtVec3::new(0.0, 0.0, 1.0), RtVec3::new(1.0, 0.0, 1.0).unit_vector(), RtVec3::new(0.0, 0.0, 1.0)];
+        let uvs = vec![(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)];
+        let mesh = Mesh::new(positions, Some(normals), Some(uvs), vec![[0, 1, 2]], default_material_lambertian());
+        assert_eq!(mesh.bounds().max, Point3::new(1.0, 1.0, 0.0));
+
+        let record = cast(&mesh, &Ray::new(Point3::new(0.5, 0.25, 1.0), RtVec3::new(0.0, 0.0, -1.0))).unwrap();
+        assert!(record.front_face);
+        assert!((record.u - 0.5).abs() < 1e-9 && (record.v - 0.25).abs() < 1e-9);
+        assert!(record.normal.x > 0.1 && record.normal.z > 0.0);
+
+        // From behind, the shading normal is flipped to face the ray
+        let record = cast(&mesh, &Ray::new(Point3::new(0.5, 0.25, -1.0), RtVec3::new(0.0, 0.0, 1.0))).unwrap();
+        assert!(!record.front_face);
+        assert!(record.normal.z < 0.0);
+    }
+
+    #[test]
+    fn tangents_follow_the_texture_coordinates() {
+        let positions = vec![Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 0.0, 0.0), Point3::new(0.0, 1.0, 0.0)];
+        let down = Ray::new(Point3::new(0.25, 0.25, 1.0), RtVec3::new(0.0, 0.0, -1.0));
+
+        // u along x, v along y
+        let uvs = vec![(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)];
+        let mesh = Mesh::new(positions.clone(), None, Some(uvs), vec![[0, 1, 2]], default_material_lambertian());
+        let record = cast(&mesh, &down).unwrap();
+        assert!((record.tangent.unit_vector() - RtVec3::new(1.0, 0.0, 0.0)).length() < 1e-9);
+        assert!((record.bitangent.unit_vector() - RtVec3::new(0.0, 1.0, 0.0)).length() < 1e-9);
+
+        // v mirrored, the bitangent points down y
+        let uvs = vec![(0.0, 1.0), (1.0, 1.0), (0.0, 0.0)];
+        let mesh = Mesh::new(positions, None, Some(uvs), vec![[0, 1, 2]], default_material_lambertian());
+        let record = cast(&mesh, &down).unwrap();
+        assert!((record.bitangent.unit_vector() - RtVec3::new(0.0, -1.0, 0.0)).length() < 1e-9);
+        assert_eq!(record.geometric_normal, RtVec3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn normal_map_coordinates_are_kept_apart() {
+        let positions = vec![Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 0.0, 0.0), Point3::new(0.0, 1.0, 0.0)];
+        let down = Ray::new(Point3::new(0.25, 0.5, 1.0), RtVec3::new(0.0, 0.0, -1.0));
+        let uvs = vec![(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)];
+        let mesh = Mesh::new(positions.clone(), None, Some(uvs.clone()), vec![[0, 1, 2]], default_material_lambertian());
+        assert_eq!(cast(&mesh, &down).unwrap().normal_uv, None);
+
+        // Normal map set swaps u and v, the color coordinates stay as they were
+        let normal_uvs = vec![(0.0, 0.0), (0.0, 1.0), (1.0, 0.0)];
+        let mesh = Mesh::new(positions, None, Some(uvs), vec![[0, 1, 2]], default_material_lambertian()).with_normal_uvs(normal_uvs);
+        let record = cast(&mesh, &down).unwrap();
+        assert!((record.u - 0.25).abs() < 1e-9 && (record.v - 0.5).abs() < 1e-9);
+        let (u, v) = record.normal_uv.unwrap();
+        assert!((u - 0.5).abs() < 1e-9 && (v - 0.25).abs() < 1e-9);
+        assert!((record.tangent.unit_vector() - RtVec3::new(0.0, 1.0, 0.0)).length() < 1e-9);
+        assert!((record.bitangent.unit_vector() - RtVec3::new(1.0, 0.0, 0.0)).length() < 1e-9);
+    }
+
+    #[test]
+    fn nearer_objects_drop_the_normal_map_coordinates_of_a_mesh_behind() {
+        // The mesh is tested first and hit, then the sphere in front of it wins
+        let positions = vec![Point3::new(-1.0, -1.0, -3.0), Point3::new(1.0, -1.0, -3.0), Point3::new(0.0, 1.0, -3.0)];
+        let uvs = vec![(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)];
+        let mesh = Mesh::new(positions, None, Some(uvs.clone()), vec![[0, 1, 2]], default_material_lambertian()).with_normal_uvs(uvs);
+        let mut world = HittableList::new();
+        world.add(Rc::new(mesh));
+        world.add(Rc::new(Sphere::new(Point3::new(0.0, 0.0, -2.0), 0.5, default_material_lambertian())));
+
+        let record = cast(&world, &Ray::new(Point3::new(0.0, 0.0, 0.0), RtVec3::new(0.0, 0.0, -1.0))).unwrap();
+        assert!((record.t - 1.5).abs() < 1e-9);
+        assert_eq!(record.normal_uv, None);
+    }
+}
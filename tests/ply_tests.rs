@@ -0,0 +1,101 @@
+#[cfg(test)]
+mod ply_tests {
+    use raytracing_in_a_weekend::hit::{HitRecord, Hittable, HittableList, Sphere};
+    use raytracing_in_a_weekend::material::{default_material_lambertian, new_material_lambertian_color};
+    use raytracing_in_a_weekend::ply::load_ply;
+    use raytracing_in_a_weekend::ray::Color;
+    use raytracing_in_a_weekend::{Interval, Point3, Ray, RtVec3};
+
+    use std::fs;
+    use std::io::ErrorKind;
+    use std::path::PathBuf;
+    use std::rc::Rc;
+
+    // A unit square at z = 0 as one quad face, red on the left edge and blue on the right.
+    const SQUARE: &str = "ply
+format ascii 1.0
+comment made by hand
+element vertex 4
+property float x
+property float y
+property float z
+property float nx
+property float ny
+property float nz
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 0 0 1 255 0 0
+1 0 0 0 0 1 0 0 255
+1 1 0 0 0 1 0 0 255
+0 1 0 0 0 1 255 0 0
+4 0 1 2 3
+";
+
+    fn write(name: &str, bytes: &[u8]) -> PathBuf {
+        let dir = std::env::temp_dir().join("raytracing_in_a_weekend_tests").join("ply");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    fn cast(world: &dyn Hittable, origin: Point3) -> Option<HitRecord> {
+        let mut record = HitRecord::new(origin, origin, 0.0, false, default_material_lambertian());
+        let ray = Ray::new(origin, RtVec3::new(0.0, 0.0, -1.0));
+        world.hit(&ray, Interval::new(0.001, f64::INFINITY), &mut record).then_some(record)
+    }
+
+    #[test]
+    fn ascii_faces_are_triangulated_with_vertex_colors() {
+        let mesh = load_ply(&write("square.ply", SQUARE.as_bytes()), new_material_lambertian_color(Color::new_rgb(0.5, 0.5, 0.5))).unwrap();
+        assert_eq!(mesh.triangles, vec![[0, 1, 2], [0, 2, 3]]);
+        assert!(mesh.normals.is_some());
+
+        let record = cast(&mesh, Point3::new(0.25, 0.75, 1.0)).unwrap();
+        let tint = record.vertex_color.unwrap();
+        assert!((tint.r - 0.75).abs() < 1e-9 && tint.g == 0.0 && (tint.b - 0.25).abs() < 1e-9);
+        // The vertex color tints the material's own albedo
+        assert!((record.material.albedo(&record).r - 0.375).abs() < 1e-9);
+
+        // A sphere hit in front of the mesh doesn't inherit its color
+        let mut world = HittableList::with_object(Rc::new(mesh));
+        world.add(Rc::new(Sphere::new(Point3::new(0.25, 0.75, 0.5), 0.1, default_material_lambertian())));
+        assert_eq!(cast(&world, Point3::new(0.25, 0.75, 1.0)).unwrap().vertex_color, None);
+    }
+
+    #[test]
+    fn binary_little_endian_files_are_read() {
+        let mut bytes = b"ply\nformat binary_little_endian 1.0\nelement vertex 3\nproperty double x\nproperty double y\nproperty double z\n\
+element edge 1\nproperty int vertex1\nproperty int vertex2\n\
+element face 1\nproperty list uchar uint vertex_index\nend_header\n"
+            .to_vec();
+        for value in [0.0f64, 0.0, -1.0, 2.0, 0.0, -1.0, 0.0, 2.0, -1.0] {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.extend(0i32.to_le_bytes());
+        bytes.extend(1i32.to_le_bytes());
+        bytes.push(3);
+        for index in [0u32, 1, 2] {
+            bytes.extend(index.to_le_bytes());
+        }
+
+        let mesh = load_ply(&write("triangle.ply", &bytes), default_material_lambertian()).unwrap();
+        assert_eq!(mesh.positions[1], Point3::new(2.0, 0.0, -1.0));
+        assert!(mesh.normals.is_none() && mesh.colors.is_none());
+        assert!((cast(&mesh, Point3::new(0.5, 0.5, 0.0)).unwrap().t - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn unsupported_files_are_rejected() {
+        let big_endian = SQUARE.replace("ascii", "binary_big_endian");
+        let missing_vertex = SQUARE.replace("4 0 1 2 3", "3 0 1 7");
+        for (name, contents) in [("big_endian.ply", big_endian.as_str()), ("missing_vertex.ply", missing_vertex.as_str()), ("not.ply", "solid")] {
+            let error = load_ply(&write(name, contents.as_bytes()), default_material_lambertian()).err().unwrap();
+            assert_eq!(error.kind(), ErrorKind::InvalidData, "{}", name);
+        }
+    }
+}
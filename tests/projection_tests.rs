@@ -0,0 +1,64 @@
+#[cfg(test)]
+mod projection_tests {
+    use raytracing_in_a_weekend::projection::{FisheyeMapping, Projection};
+    use raytracing_in_a_weekend::RtVec3;
+
+    fn close(a: RtVec3, b: RtVec3) -> bool {
+        (a - b).length() < 1e-9
+    }
+
+    fn direction(projection: Projection, s: f64, t: f64, aspect_ratio: f64) -> RtVec3 {
+        projection.local_ray(s, t, aspect_ratio).unwrap().1.unit_vector()
+    }
+
+    #[test]
+    fn orthographic_rays_are_parallel() {
+        let projection = Projection::Orthographic { height: 4.0 };
+        let (corner_origin, corner_direction) = projection.local_ray(0.0, 0.0, 2.0).unwrap();
+        let (centre_origin, centre_direction) = projection.local_ray(0.5, 0.5, 2.0).unwrap();
+        assert_eq!(corner_direction, centre_direction);
+        assert!(close(corner_origin, RtVec3::new(-4.0, 2.0, 0.0)));
+        assert!(close(centre_origin, RtVec3::new(0.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn fisheye_covers_its_image_circle() {
+        for mapping in [FisheyeMapping::Equidistant, FisheyeMapping::Equisolid] {
+            let projection = Projection::Fisheye { fov: 180.0, mapping };
+            assert!(close(direction(projection, 0.5, 0.5, 1.0), RtVec3::new(0.0, 0.0, 1.0)));
+            // The rim of a 180 degree lens looks sideways
+            assert!(close(direction(projection, 1.0, 0.5, 1.0), RtVec3::new(1.0, 0.0, 0.0)));
+            assert!(close(direction(projection, 0.5, 0.0, 1.0), RtVec3::new(0.0, 1.0, 0.0)));
+            assert!(projection.local_ray(0.0, 0.0, 1.0).is_none());
+        }
+    }
+
+    #[test]
+    fn equirectangular_wraps_around() {
+        let projection = Projection::Equirectangular;
+        assert!(close(direction(projection, 0.5, 0.5, 2.0), RtVec3::new(0.0, 0.0, 1.0)));
+        assert!(close(direction(projection, 0.75, 0.5, 2.0), RtVec3::new(1.0, 0.0, 0.0)));
+        assert!(close(direction(projection, 0.0, 0.5, 2.0), RtVec3::new(0.0, 0.0, -1.0)));
+        assert!(close(direction(projection, 0.3, 0.0, 2.0), RtVec3::new(0.0, 1.0, 0.0)));
+    }
+
+    #[test]
+    fn cubemap_face_centres() {
+        let projection = Projection::Cubemap;
+        let centres = [
+            (1.0 / 6.0, 0.25, RtVec3::new(1.0, 0.0, 0.0)),
+            (3.0 / 6.0, 0.25, RtVec3::new(-1.0, 0.0, 0.0)),
+            (5.0 / 6.0, 0.25, RtVec3::new(0.0, 1.0, 0.0)),
+            (1.0 / 6.0, 0.75, RtVec3::new(0.0, -1.0, 0.0)),
+            (3.0 / 6.0, 0.75, RtVec3::new(0.0, 0.0, 1.0)),
+            (5.0 / 6.0, 0.75, RtVec3::new(0.0, 0.0, -1.0)),
+        ];
+        for (s, t, expected) in centres {
+            assert!(close(direction(projection, s, t, 1.5), expected), "face at {} {}", s, t);
+        }
+        // Neighbouring faces share their edge: front's right edge is the right face's left edge
+        let front_edge = direction(projection, 4.0 / 6.0 - 1e-12, 0.75, 1.5);
+        let right_edge = direction(projection, 1e-12, 0.25, 1.5);
+        assert!((front_edge - right_edge).length() < 1e-6);
+    }
+}
@@ -0,0 +1,190 @@
+#[cfg(test)]
+mod scene_tests {
+    use raytracing_in_a_weekend::camera::Camera;
+    use raytracing_in_a_weekend::hit::{HittableList, Sphere};
+    use raytracing_in_a_weekend::lens::Aperture;
+    use raytracing_in_a_weekend::material::{Dielectric, Material};
+    use raytracing_in_a_weekend::material::{new_material_dielectric, new_material_lambertian_color, new_material_metal_color, PrincipledParameters};
+    use raytracing_in_a_weekend::ray::Color;
+    use raytracing_in_a_weekend::scene::{CameraSettings, MaterialDescription, ObjectDescription, SceneDescription};
+    use raytracing_in_a_weekend::spectrum::Dispersion;
+    use raytracing_in_a_weekend::{Point3, RtVec3};
+
+    use std::fs;
+    use std::path::Path;
+    use std::rc::Rc;
+
+    const SCENE: &str = r#"
+# Two spheres
+[camera]
+fov = 40
+lookfrom = [0, 1, 3]   # Slightly above
+lookat = [0, 0, 0]
+
+[material.ground]
+type = "lambertian"
+albedo = [0.5, 0.5, 0.5]
+
+[material."steel #2"]
+type = "metal"
+albedo = [0.8, 0.8, 0.8]
+fuzz = 0.1
+
+[[object]]
+type = "sphere"
+center = [0, -100, 0]
+radius = 100
+material = "ground"
+
+[[object]]
+type = "sphere"
+center = [0, 0.5, 0]
+radius = 0.5
+material = "steel #2"
+"#;
+
+    fn error_line(text: &str) -> usize {
+        SceneDescription::parse(text).expect_err("scene should be rejected").line
+    }
+
+    #[test]
+    fn parses_camera_materials_and_objects() {
+        let scene = SceneDescription::parse(SCENE).unwrap();
+        let camera = CameraSettings {
+            fov: 40.0,
+            lookfrom: Point3::new(0.0, 1.0, 3.0),
+            lookat: Point3::new(0.0, 0.0, 0.0),
+            ..CameraSettings::default()
+        };
+        assert_eq!(scene.camera, camera);
+        assert_eq!(scene.materials[1], (
+            "steel #2".to_string(),
+            MaterialDescription::Metal { albedo: Color::new_rgb(0.8, 0.8, 0.8), roughness: 0.1 },
+        ));
+        assert_eq!(scene.objects.len(), 2);
+        assert_eq!(scene.objects[1], ObjectDescription::Sphere {
+            center: RtVec3::new(0.0, 0.5, 0.0),
+            radius: 0.5,
+            material: "steel #2".to_string(),
+        });
+        assert_eq!(scene.build_world().objects().len(), 2);
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_line() {
+        assert_eq!(error_line("[camera]\nfov = wide\n"), 2);
+        assert_eq!(error_line("[camera]\n\nzoom = 2\n"), 3);
+        assert_eq!(error_line("[lights]\n"), 1);
+        assert_eq!(error_line("fov = 20\n"), 1);
+        assert_eq!(error_line("[material.red]\ntype = \"lambertian\"\nalbedo = [1, 0]\n"), 3);
+        // Missing keys are reported at the table header
+        assert_eq!(error_line("[camera]\n[material.red]\ntype = \"lambertian\"\n"), 2);
+        assert_eq!(error_line("[[object]]\ntype = \"sphere\"\ncenter = [0, 0, 0]\nradius = 1\nmaterial = \"nope\"\n"), 1);
+        let error = SceneDescription::parse("[material.a]\ntype = \"plastic\"\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 1: unknown material type \"plastic\", expected lambertian, oren_nayar, velvet, metal, dielectric, principled, subsurface or light");
+    }
+
+    #[test]
+    fn bundled_scenes_load() {
+        let scene = SceneDescription::load(Path::new("scenes/demo.toml")).unwrap();
+        assert_eq!(scene.objects.len(), 5);
+        assert_eq!(scene.materials.len(), 5);
+
+        let principled = SceneDescription::load(Path::new("scenes/principled.toml")).unwrap();
+        let MaterialDescription::Principled { parameters, .. } = &principled.materials[1].1 else {
+            panic!("expected a principled material");
+        };
+        // Unlisted parameters keep their defaults
+        assert_eq!(parameters.clearcoat, 1.0);
+        assert_eq!(parameters.ior, PrincipledParameters::default().ior);
+        assert_eq!(SceneDescription::parse(&principled.to_string()).unwrap(), principled);
+
+        let subsurface = SceneDescription::load(Path::new("scenes/subsurface.toml")).unwrap();
+        assert_eq!(subsurface.materials[3].1, MaterialDescription::Subsurface {
+            albedo: Color::new_rgb(0.98, 0.88, 0.82),
+            mean_free_path: Color::new_rgb(0.25, 0.08, 0.05),
+            refraction_index: 1.4,
+            anisotropy: 0.8,
+        });
+        assert_eq!(SceneDescription::parse(&subsurface.to_string()).unwrap(), subsurface);
+        assert_eq!(error_line("[material.a]\ntype = \"subsurface\"\nalbedo = [1, 1, 1]\nmean_free_path = [0.1, 0, 0.1]\n"), 1);
+
+        let cloth = SceneDescription::parse("[material.clay]\ntype = \"oren_nayar\"\nalbedo = [0.7, 0.4, 0.3]\n\n[material.velvet]\ntype = \"velvet\"\nalbedo = [0.2, 0, 0.1]\nsheen = [0.9, 0.8, 1]\n").unwrap();
+        assert_eq!(cloth.materials[0].1, MaterialDescription::OrenNayar { albedo: Color::new_rgb(0.7, 0.4, 0.3), sigma: 20.0 });
+        assert_eq!(SceneDescription::parse(&cloth.to_string()).unwrap(), cloth);
+
+        let glass = SceneDescription::parse("[material.bottle]\ntype = \"dielectric\"\nrefraction_index = 1.5\nabsorption = [0.9, 0.1, 0.5]\n").unwrap();
+        assert_eq!(SceneDescription::parse(&glass.to_string()).unwrap(), glass);
+    }
+
+    #[test]
+    fn dispersive_glass_round_trips() {
+        let prism = SceneDescription::parse("[material.prism]\ntype = \"dielectric\"\ncauchy = [1.5, 0.0042]\n").unwrap();
+        let MaterialDescription::Dielectric { refraction_index, dispersion, .. } = &prism.materials[0].1 else {
+            panic!("expected a dielectric");
+        };
+        // The index for RGB renders defaults to the D line value
+        assert!((refraction_index - (1.5 + 0.0042 / 0.5876 / 0.5876)).abs() < 1e-12);
+        assert_eq!(*dispersion, Some(Dispersion::Cauchy { a: 1.5, b: 0.0042 }));
+        assert_eq!(SceneDescription::parse(&prism.to_string()).unwrap(), prism);
+
+        let mut flint = SceneDescription::default();
+        flint.materials.push(("flint".to_string(), Dielectric::dispersive(Dispersion::dense_flint()).describe().unwrap()));
+        assert_eq!(SceneDescription::parse(&flint.to_string()).unwrap(), flint);
+
+        assert_eq!(error_line("[material.a]\ntype = \"dielectric\"\ncauchy = [1.5]\n"), 3);
+        assert_eq!(error_line("[material.a]\ntype = \"dielectric\"\ncauchy = [1.5, 0.004]\nsellmeier = [1, 1, 1, 1, 1, 1]\n"), 1);
+    }
+
+    // A scene with awkward, randomly generated numbers, like the final render in main.rs
+    fn random_camera() -> Camera {
+        raytracing_in_a_weekend::seed_rng(42);
+        let shared = new_material_metal_color(Color::random(), 0.3);
+        let mut world = HittableList::new();
+        for index in 0..6 {
+            let center = Point3::new(index as f64 - 2.5 + 0.9 * raytracing_in_a_weekend::random_float(), 0.0, -3.0);
+            let material = match index % 3 {
+                0 => new_material_lambertian_color(Color::random() * Color::random()),
+                1 => Rc::clone(&shared),
+                _ => new_material_dielectric(1.5),
+            };
+            world.add(Rc::new(Sphere::new(center, 0.2 + 0.3 * raytracing_in_a_weekend::random_float(), material)));
+        }
+        Camera::new(
+            world,
+            Color::new_rgb(0.0, 0.0, 0.0),
+            16.0 / 9.0,
+            37.5,
+            0.6,
+            3.1,
+            Point3::new(0.1, 0.7, 0.3),
+            Point3::new(0.0, 0.0, -3.0),
+            RtVec3::new(0.0, 1.0, 0.0),
+            24,
+            2,
+            6,
+        )
+        .with_aperture(Aperture::Polygon { blades: 6, rotation: 15.0 })
+    }
+
+    #[test]
+    fn saved_scene_renders_identically() {
+        let dir = std::env::temp_dir().join("raytracing_in_a_weekend_tests");
+        fs::create_dir_all(&dir).unwrap();
+        let scene_path = dir.join("saved_scene.toml");
+
+        let camera = random_camera();
+        let scene = SceneDescription::capture(&camera).unwrap();
+        // Shared material instances keep sharing one entry
+        assert_eq!(scene.materials.len(), 5);
+        scene.save(&scene_path).unwrap();
+        let loaded = SceneDescription::load(&scene_path).unwrap();
+        assert_eq!(loaded, scene);
+
+        let original = dir.join("saved_scene_original.ppm");
+        let reloaded = dir.join("saved_scene_reloaded.ppm");
+        camera.with_seed(3).with_output(&original).render();
+        loaded.build_camera(24, 2, 6).with_seed(3).with_output(&reloaded).render();
+        assert_eq!(fs::read(&original).unwrap(), fs::read(&reloaded).unwrap());
+    }
+}
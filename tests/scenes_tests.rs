@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod scenes_tests {
+    use raytracing_in_a_weekend::scene::SceneDescription;
+    use raytracing_in_a_weekend::scenes::{preset, PRESETS};
+    use raytracing_in_a_weekend::seed_rng;
+
+    use std::fs;
+
+    #[test]
+    fn every_preset_renders() {
+        let dir = std::env::temp_dir().join("raytracing_in_a_weekend_tests").join("presets");
+        fs::create_dir_all(&dir).unwrap();
+        for name in PRESETS {
+            seed_rng(1);
+            let output = dir.join(format!("{}.ppm", name));
+            preset(name).unwrap().build_camera(12, 1, 4).with_seed(1).with_output(&output).render();
+            assert!(output.exists(), "{} wasn't rendered", name);
+        }
+        assert!(preset("teapot").is_none());
+    }
+
+    #[test]
+    fn seeded_presets_are_reproducible() {
+        let capture = || {
+            seed_rng(7);
+            SceneDescription::capture(&preset("final-scene").unwrap().build_camera(12, 1, 4)).unwrap()
+        };
+        let scene = capture();
+        assert!(scene.objects.len() > 400);
+        assert_eq!(scene, capture());
+    }
+
+    #[test]
+    fn presets_without_meshes_or_layered_materials_save_and_reload() {
+        let dir = std::env::temp_dir().join("raytracing_in_a_weekend_tests").join("saved_presets");
+        fs::create_dir_all(&dir).unwrap();
+        let unsaveable = ["prism", "coatings", "mix"];
+        for name in PRESETS {
+            seed_rng(1);
+            let camera = preset(name).unwrap().build_camera(12, 1, 4);
+            let scene = SceneDescription::capture(&camera);
+            assert_eq!(scene.is_err(), unsaveable.contains(&name), "{} saving", name);
+            let Ok(scene) = scene else { continue };
+            let loaded = SceneDescription::parse(&scene.to_string()).unwrap();
+            assert_eq!(loaded, scene, "{} round trip", name);
+
+            let original = dir.join(format!("{}_original.ppm", name));
+            let reloaded = dir.join(format!("{}_reloaded.ppm", name));
+            camera.with_seed(3).with_output(&original).render();
+            seed_rng(1);
+            loaded.build_camera(12, 1, 4).with_seed(3).with_output(&reloaded).render();
+            assert_eq!(fs::read(&original).unwrap(), fs::read(&reloaded).unwrap(), "{} render", name);
+        }
+    }
+}
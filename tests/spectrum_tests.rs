@@ -0,0 +1,94 @@
+#[cfg(test)]
+mod spectrum_tests {
+    use raytracing_in_a_weekend::hit::HitRecord;
+    use raytracing_in_a_weekend::material::{default_material_lambertian, Dielectric, Lambertian, Material};
+    use raytracing_in_a_weekend::ray::Color;
+    use raytracing_in_a_weekend::spectrum::{rgb_to_spectrum, Dispersion, SampledWavelengths, D_LINE, WAVELENGTH_MAX, WAVELENGTH_MIN};
+    use raytracing_in_a_weekend::{Point3, Ray, RtVec3};
+
+    #[test]
+    fn wavelengths_are_spread_across_the_range() {
+        let wavelengths = SampledWavelengths::sample(0.9);
+        assert!((wavelengths.hero() - 740.0).abs() < 1e-9);
+        let mut sorted = wavelengths.lambda;
+        sorted.sort_by(f64::total_cmp);
+        assert!((sorted[0] - 440.0).abs() < 1e-9);
+        for pair in sorted.windows(2) {
+            assert!((pair[1] - pair[0] - 100.0).abs() < 1e-9);
+        }
+        assert!(sorted.iter().all(|&lambda| (WAVELENGTH_MIN..WAVELENGTH_MAX).contains(&lambda)));
+    }
+
+    #[test]
+    fn flat_spectra_average_to_white() {
+        // Averaged over many hero wavelengths a flat spectrum gives back exactly what went in
+        let samples = 4000;
+        let mut total = RtVec3::new(0.0, 0.0, 0.0);
+        for k in 0..samples {
+            total = total + SampledWavelengths::sample((k as f64 + 0.5) / samples as f64).to_rgb([0.5; 4]);
+        }
+        let average = total / samples as f64;
+        assert!((average - RtVec3::new(0.5, 0.5, 0.5)).length() < 1e-3, "{:?}", average);
+
+        // Gray material colors uplift to flat spectra, reflectances stay in [0, 1]
+        for lambda in [400.0, 500.0, 590.0, 700.0] {
+            assert!((rgb_to_spectrum(Color::new_rgb(0.3, 0.3, 0.3), lambda) - 0.3).abs() < 1e-12);
+            let value = rgb_to_spectrum(Color::new_rgb(1.0, 0.2, 0.0), lambda);
+            assert!((0.0..=1.0).contains(&value));
+        }
+        assert!(rgb_to_spectrum(Color::new_rgb(1.0, 0.0, 0.0), 650.0) > 0.99);
+        assert!(rgb_to_spectrum(Color::new_rgb(1.0, 0.0, 0.0), 450.0) < 0.01);
+    }
+
+    #[test]
+    fn terminated_paths_keep_only_the_hero() {
+        let mut wavelengths = SampledWavelengths::sample(0.7);
+        let values = [1.0, 0.0, 0.0, 0.0];
+        let shared = wavelengths.to_rgb(values);
+        wavelengths.terminate_secondary();
+        let hero_only = wavelengths.to_rgb(values);
+        // One wavelength instead of four carries four times the weight
+        assert!((hero_only - 4.0 * shared).length() < 1e-9);
+        // A hero in the red shows up red
+        assert!(hero_only.x > hero_only.y && hero_only.x > hero_only.z);
+    }
+
+    #[test]
+    fn glass_indices_fall_with_wavelength() {
+        let bk7 = Dispersion::bk7();
+        assert!((bk7.index(D_LINE) - 1.5168).abs() < 1e-4);
+        assert!(bk7.index(450.0) > bk7.index(650.0));
+        let flint = Dispersion::dense_flint();
+        assert!((flint.index(D_LINE) - 1.7847).abs() < 1e-3);
+        assert!(flint.index(450.0) - flint.index(650.0) > bk7.index(450.0) - bk7.index(650.0));
+        let cauchy = Dispersion::Cauchy { a: 1.5, b: 0.01 };
+        assert!((cauchy.index(500.0) - 1.54).abs() < 1e-12);
+    }
+
+    #[test]
+    fn dispersive_glass_bends_each_wavelength_differently() {
+        let glass = Dielectric::dispersive(Dispersion::dense_flint());
+        assert!((glass.refraction_index - Dispersion::dense_flint().index(D_LINE)).abs() < 1e-12);
+        let record = HitRecord::new(Point3::new(0.0, 0.0, 0.0), RtVec3::new(0.0, 0.0, 1.0), 1.0, true, default_material_lambertian());
+        let ray = Ray::new(Point3::new(-1.0, 0.0, 1.0), RtVec3::new(1.0, 0.0, -1.0));
+        // Refraction of the hero, lucky reflections are skipped
+        let bend = |u: f64| loop {
+            let mut wavelengths = SampledWavelengths::sample(u);
+            let (_, scattered) = glass.scatter_spectral(ray, record.clone(), &mut wavelengths).unwrap();
+            assert!(wavelengths.secondary_terminated);
+            if scattered.direction().z < 0.0 {
+                break scattered.direction().unit_vector().x;
+            }
+        };
+        // Blue bends further towards the normal than red
+        let blue = bend((450.0 - WAVELENGTH_MIN) / (WAVELENGTH_MAX - WAVELENGTH_MIN));
+        let red = bend((650.0 - WAVELENGTH_MIN) / (WAVELENGTH_MAX - WAVELENGTH_MIN));
+        assert!(red - blue > 0.005, "{} vs {}", red, blue);
+
+        // Everything else is wavelength independent and leaves the companions alone
+        let mut wavelengths = SampledWavelengths::sample(0.3);
+        Lambertian::new(Color::new_rgb(0.5, 0.5, 0.5)).scatter_spectral(ray, record.clone(), &mut wavelengths);
+        Dielectric::new(Color::new_rgb(1.0, 1.0, 1.0), 1.5).scatter_spectral(ray, record, &mut wavelengths);
+        assert!(!wavelengths.secondary_terminated);
+    }
+}
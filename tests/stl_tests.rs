@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod stl_tests {
+    use raytracing_in_a_weekend::hit::{HitRecord, Hittable};
+    use raytracing_in_a_weekend::material::default_material_lambertian;
+    use raytracing_in_a_weekend::stl::load_stl;
+    use raytracing_in_a_weekend::{Interval, Point3, Ray, RtVec3};
+
+    use std::fs;
+    use std::io::ErrorKind;
+    use std::path::PathBuf;
+
+    fn write(name: &str, bytes: &[u8]) -> PathBuf {
+        let dir = std::env::temp_dir().join("raytracing_in_a_weekend_tests").join("stl");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    fn hits(world: &dyn Hittable, origin: Point3) -> bool {
+        let mut record = HitRecord::new(origin, origin, 0.0, false, default_material_lambertian());
+        world.hit(&Ray::new(origin, RtVec3::new(0.0, 0.0, -1.0)), Interval::new(0.001, f64::INFINITY), &mut record)
+    }
+
+    #[test]
+    fn ascii_facets_are_read() {
+        let text = "solid wedge
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 0 1 0
+    endloop
+  endfacet
+  facet normal 0 0 1
+    outer loop
+      vertex 1 0 0
+      vertex 1 1 0
+      vertex 0 1 0
+    endloop
+  endfacet
+endsolid wedge
+";
+        let mesh = load_stl(&write("square.stl", text.as_bytes()), default_material_lambertian()).unwrap();
+        assert_eq!(mesh.triangles.len(), 2);
+        assert!(hits(&mesh, Point3::new(0.9, 0.9, 1.0)));
+        assert!(!hits(&mesh, Point3::new(1.1, 0.5, 1.0)));
+
+        let broken = text.replacen("      vertex 0 1 0\n", "", 1);
+        let error = load_stl(&write("broken.stl", broken.as_bytes()), default_material_lambertian()).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn binary_files_starting_with_solid_are_read_as_binary() {
+        let mut bytes = b"solid exported by a CAD package".to_vec();
+        bytes.resize(80, 0);
+        bytes.extend(1u32.to_le_bytes());
+        for value in [0.0f32, 0.0, 1.0, 0.0, 0.0, -2.0, 1.0, 0.0, -2.0, 0.0, 1.0, -2.0] {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.extend(0u16.to_le_bytes());
+
+        let mesh = load_stl(&write("triangle.stl", &bytes), default_material_lambertian()).unwrap();
+        assert_eq!(mesh.positions, vec![Point3::new(0.0, 0.0, -2.0), Point3::new(1.0, 0.0, -2.0), Point3::new(0.0, 1.0, -2.0)]);
+        assert!(hits(&mesh, Point3::new(0.2, 0.2, 0.0)));
+    }
+}
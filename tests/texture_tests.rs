@@ -0,0 +1,108 @@
+#[cfg(test)]
+mod texture_tests {
+    use raytracing_in_a_weekend::hit::{make_box, HitRecord, Hittable, HittableList, Quad, Sphere};
+    use raytracing_in_a_weekend::image::Image;
+    use raytracing_in_a_weekend::material::{default_material_lambertian, new_material_diffuse_light_color};
+    use raytracing_in_a_weekend::ray::{color, Background, Color};
+    use raytracing_in_a_weekend::texture::{CheckerTexture, ImageTexture, NoiseTexture, Texture, WrapMode};
+    use raytracing_in_a_weekend::{Interval, Point3, Ray, RtVec3};
+
+    use std::rc::Rc;
+
+    fn empty_record() -> HitRecord {
+        HitRecord::new(RtVec3::new(0.0, 0.0, 0.0), RtVec3::new(0.0, 0.0, 0.0), 0.0, false, default_material_lambertian())
+    }
+
+    #[test]
+    fn checker_alternates_between_cells() {
+        let even = Color::new_rgb(1.0, 0.0, 0.0);
+        let odd = Color::new_rgb(0.0, 0.0, 1.0);
+        let checker = CheckerTexture::from_colors(1.0, even, odd);
+        assert_eq!(checker.value(0.0, 0.0, &Point3::new(0.5, 0.5, 0.5)), even);
+        assert_eq!(checker.value(0.0, 0.0, &Point3::new(1.5, 0.5, 0.5)), odd);
+        assert_eq!(checker.value(0.0, 0.0, &Point3::new(-0.5, 0.5, 0.5)), odd);
+        assert_eq!(checker.value(0.0, 0.0, &Point3::new(1.5, 1.5, 0.5)), even);
+    }
+
+    #[test]
+    fn image_texture_maps_v_up() {
+        // Two rows: white on top, black below
+        let image = Image::new(1, 2, vec![RtVec3::new(1.0, 1.0, 1.0), RtVec3::new(0.0, 0.0, 0.0)]);
+        let texture = ImageTexture::new(image);
+        let p = Point3::new(0.0, 0.0, 0.0);
+        assert_eq!(texture.value(0.5, 0.9, &p), Color::new_rgb(1.0, 1.0, 1.0));
+        assert_eq!(texture.value(0.5, 0.1, &p), Color::new_rgb(0.0, 0.0, 0.0));
+        // Missing images show up as cyan
+        let missing = ImageTexture::load(std::path::Path::new("no/such/texture.ppm"));
+        assert_eq!(missing.value(0.5, 0.5, &p), Color::new_rgb(0.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn coordinates_outside_the_image_follow_the_wrap_mode() {
+        // One row: red on the left, blue on the right
+        let red = Color::new_rgb(1.0, 0.0, 0.0);
+        let blue = Color::new_rgb(0.0, 0.0, 1.0);
+        let image = || Image::new(2, 1, vec![RtVec3::new(1.0, 0.0, 0.0), RtVec3::new(0.0, 0.0, 1.0)]);
+        let p = Point3::new(0.0, 0.0, 0.0);
+
+        let clamped = ImageTexture::new(image());
+        assert_eq!(clamped.value(1.25, 0.5, &p), blue);
+        assert_eq!(clamped.value(-0.25, 0.5, &p), red);
+
+        let repeated = ImageTexture::new(image()).with_wrap(WrapMode::Repeat, WrapMode::Repeat);
+        assert_eq!(repeated.value(1.25, 0.5, &p), red);
+        assert_eq!(repeated.value(-0.25, 0.5, &p), blue);
+        assert_eq!(repeated.value(0.25, 7.5, &p), red);
+
+        let mirrored = ImageTexture::new(image()).with_wrap(WrapMode::MirroredRepeat, WrapMode::Clamp);
+        assert_eq!(mirrored.value(1.25, 0.5, &p), blue);
+        assert_eq!(mirrored.value(-0.25, 0.5, &p), red);
+        assert_eq!(mirrored.value(2.25, 0.5, &p), red);
+    }
+
+    #[test]
+    fn noise_stays_in_range() {
+        raytracing_in_a_weekend::seed_rng(1);
+        let noise = NoiseTexture::new(4.0);
+        for index in 0..100 {
+            let p = Point3::new(index as f64 * 0.37, index as f64 * -0.11, index as f64 * 0.05);
+            let value = noise.value(0.0, 0.0, &p);
+            assert!((0.0..=1.0).contains(&value.r), "noise out of range at {:?}", p);
+        }
+    }
+
+    #[test]
+    fn sphere_and_quad_report_surface_coordinates() {
+        // Top of the sphere is v = 1, the point facing +x is u = 0.5
+        let sphere = Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, default_material_lambertian());
+        let mut record = empty_record();
+        assert!(sphere.hit(&Ray::new(Point3::new(5.0, 0.0, 0.0), RtVec3::new(-1.0, 0.0, 0.0)), Interval::new(0.001, f64::INFINITY), &mut record));
+        assert!((record.u - 0.5).abs() < 1e-9 && (record.v - 0.5).abs() < 1e-9);
+
+        let quad = Quad::new(Point3::new(0.0, 0.0, 0.0), RtVec3::new(2.0, 0.0, 0.0), RtVec3::new(0.0, 4.0, 0.0), default_material_lambertian());
+        let mut record = empty_record();
+        assert!(quad.hit(&Ray::new(Point3::new(0.5, 3.0, 1.0), RtVec3::new(0.0, 0.0, -1.0)), Interval::new(0.001, f64::INFINITY), &mut record));
+        assert!((record.u - 0.25).abs() < 1e-9 && (record.v - 0.75).abs() < 1e-9);
+        assert!(record.front_face);
+        assert!(!quad.hit(&Ray::new(Point3::new(2.5, 3.0, 1.0), RtVec3::new(0.0, 0.0, -1.0)), Interval::new(0.001, f64::INFINITY), &mut empty_record()));
+
+        let cube = make_box(Point3::new(1.0, 1.0, 1.0), Point3::new(-1.0, -1.0, -1.0), default_material_lambertian());
+        let mut record = empty_record();
+        assert!(cube.hit(&Ray::new(Point3::new(0.2, 5.0, 0.3), RtVec3::new(0.0, -1.0, 0.0)), Interval::new(0.001, f64::INFINITY), &mut record));
+        assert!((record.t - 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn lights_add_emission_and_background_is_used() {
+        let mut world = HittableList::new();
+        world.add(Rc::new(Sphere::new(Point3::new(0.0, 0.0, -2.0), 1.0, new_material_diffuse_light_color(Color::new_rgb(4.0, 2.0, 1.0)))));
+        let black = Background::Solid(Color::new_rgb(0.0, 0.0, 0.0));
+
+        let towards_light = Ray::new(Point3::new(0.0, 0.0, 0.0), RtVec3::new(0.0, 0.0, -1.0));
+        assert_eq!(color(towards_light, &world, 5, &black), RtVec3::new(4.0, 2.0, 1.0));
+        let away = Ray::new(Point3::new(0.0, 0.0, 0.0), RtVec3::new(0.0, 0.0, 1.0));
+        assert_eq!(color(away, &world, 5, &black), RtVec3::new(0.0, 0.0, 0.0));
+        let grey = Background::Solid(Color::new_rgb(0.25, 0.25, 0.25));
+        assert_eq!(color(away, &world, 5, &grey), RtVec3::new(0.25, 0.25, 0.25));
+    }
+}
@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod tonemap_tests {
+    use raytracing_in_a_weekend::tonemap::{srgb_oetf, ToneMapOperator, ToneMapper};
+    use raytracing_in_a_weekend::RtVec3;
+
+    fn grey(value: f64) -> RtVec3 {
+        RtVec3::new(value, value, value)
+    }
+
+    #[test]
+    fn srgb_endpoints() {
+        assert_eq!(srgb_oetf(0.0), 0.0);
+        assert!((srgb_oetf(1.0) - 1.0).abs() < 1e-12);
+        // Both branches meet at the linear segment threshold
+        let threshold = 0.0031308;
+        assert!((12.92 * threshold - (1.055 * f64::powf(threshold, 1.0 / 2.4) - 0.055)).abs() < 1e-6);
+        assert!((srgb_oetf(0.18) - 0.4613561295).abs() < 1e-6);
+    }
+
+    #[test]
+    fn exposure_in_stops() {
+        let mapper = ToneMapper::new(ToneMapOperator::Clamp, 1.0);
+        assert_eq!(mapper.tone_map(grey(0.25)), grey(0.5));
+        let mapper = ToneMapper::new(ToneMapOperator::Clamp, -2.0);
+        assert_eq!(mapper.tone_map(grey(2.0)), grey(0.5));
+    }
+
+    #[test]
+    fn clamp_clips_highlights() {
+        let mapper = ToneMapper::default();
+        assert_eq!(mapper.tone_map(grey(4.0)), grey(1.0));
+        assert_eq!(mapper.tone_map(grey(-1.0)), grey(0.0));
+    }
+
+    #[test]
+    fn reinhard_keeps_highlights_below_white() {
+        let mapper = ToneMapper::new(ToneMapOperator::Reinhard, 0.0);
+        assert_eq!(mapper.tone_map(grey(1.0)), grey(0.5));
+        let bright = mapper.tone_map(grey(50.0));
+        let brighter = mapper.tone_map(grey(100.0));
+        assert!(bright.x() < brighter.x() && brighter.x() < 1.0);
+    }
+
+    #[test]
+    fn extended_reinhard_maps_white_point_to_one() {
+        let mapper = ToneMapper::new(ToneMapOperator::extended_reinhard(8.0).unwrap(), 0.0);
+        assert!((mapper.tone_map(grey(8.0)).x() - 1.0).abs() < 1e-12);
+        assert!(mapper.tone_map(grey(4.0)).x() < 1.0);
+    }
+
+    #[test]
+    fn extended_reinhard_rejects_white_points_that_are_not_positive() {
+        for white in [0.0, -2.0, f64::NAN] {
+            assert!(ToneMapOperator::extended_reinhard(white).is_err(), "accepted {}", white);
+        }
+    }
+
+    #[test]
+    fn extended_reinhard_built_directly_with_a_bad_white_point_falls_back_to_reinhard() {
+        let reinhard = ToneMapper::new(ToneMapOperator::Reinhard, 0.0);
+        for white in [0.0, -2.0, f64::NAN] {
+            let mapper = ToneMapper::new(ToneMapOperator::ExtendedReinhard { white }, 0.0);
+            for value in [0.0, 0.5, 4.0, 100.0] {
+                let mapped = mapper.tone_map(grey(value));
+                assert!(mapped.x().is_finite(), "white {} gave {:?}", white, mapped);
+                assert_eq!(mapped, reinhard.tone_map(grey(value)));
+            }
+        }
+    }
+
+    #[test]
+    fn filmic_curves_are_monotonic() {
+        for operator in [ToneMapOperator::Hable, ToneMapOperator::AcesFitted] {
+            let mapper = ToneMapper::new(operator, 0.0);
+            let mut previous = mapper.tone_map(grey(0.0)).y();
+            for step in 1..200 {
+                let current = mapper.tone_map(grey(step as f64 * 0.1)).y();
+                assert!(current >= previous, "{:?} decreased at {}", operator, step);
+                previous = current;
+            }
+            assert!(previous <= 1.0);
+        }
+    }
+}